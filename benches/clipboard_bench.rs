@@ -2,6 +2,8 @@
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use crosscopy::clipboard::ClipboardContent;
+#[cfg(feature = "compression")]
+use {criterion::Throughput, crosscopy::clipboard::CompressionCodec};
 
 fn bench_clipboard_content_creation(c: &mut Criterion) {
     c.bench_function("clipboard_content_text_creation", |b| {
@@ -58,39 +60,77 @@ fn bench_clipboard_content_integrity_check(c: &mut Criterion) {
     });
 }
 
+/// Log lines and JSON similar to what developers copy around
+#[cfg(feature = "compression")]
+fn compression_samples() -> Vec<(&'static str, ClipboardContent)> {
+    let json = (0..500)
+        .map(|i| format!(r#"{{"id":{},"name":"device-{}","status":"connected","latency_ms":{}}}"#, i, i, i % 37))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let logs = (0..500)
+        .map(|i| format!("2024-01-01T12:00:{:02}Z INFO crosscopy::network: Peer {} connected\n", i % 60, i))
+        .collect::<String>();
+
+    vec![
+        ("json", ClipboardContent::new_text(format!("[{}]", json), "benchmark-device".to_string())),
+        ("logs", ClipboardContent::new_text(logs, "benchmark-device".to_string())),
+    ]
+}
+
+#[cfg(feature = "compression")]
+const CODECS: [CompressionCodec; 3] = [CompressionCodec::Gzip, CompressionCodec::Zstd, CompressionCodec::Lz4];
+
 #[cfg(feature = "compression")]
 fn bench_clipboard_content_compression(c: &mut Criterion) {
-    let large_text = "A".repeat(10000); // 10KB of text
-    let mut content = ClipboardContent::new_text(
-        large_text,
-        "benchmark-device".to_string(),
-    );
+    for (name, content) in compression_samples() {
+        let mut group = c.benchmark_group(format!("clipboard_content_compression/{}", name));
+        group.throughput(Throughput::Bytes(content.data.len() as u64));
+
+        for codec in CODECS {
+            let mut compressed = content.clone();
+            compressed.compress(codec).unwrap();
+            println!(
+                "{}/{}: {} -> {} bytes",
+                name,
+                codec,
+                content.data.len(),
+                compressed.data.len()
+            );
 
-    c.bench_function("clipboard_content_compression", |b| {
-        b.iter(|| {
-            let mut content_copy = black_box(content.clone());
-            content_copy.compress().unwrap();
-            black_box(content_copy)
-        })
-    });
+            group.bench_function(codec.to_string(), |b| {
+                b.iter(|| {
+                    let mut content_copy = black_box(content.clone());
+                    content_copy.compress(codec).unwrap();
+                    black_box(content_copy)
+                })
+            });
+        }
+
+        group.finish();
+    }
 }
 
 #[cfg(feature = "compression")]
 fn bench_clipboard_content_decompression(c: &mut Criterion) {
-    let large_text = "A".repeat(10000); // 10KB of text
-    let mut content = ClipboardContent::new_text(
-        large_text,
-        "benchmark-device".to_string(),
-    );
-    content.compress().unwrap();
-
-    c.bench_function("clipboard_content_decompression", |b| {
-        b.iter(|| {
-            let mut content_copy = black_box(content.clone());
-            content_copy.decompress().unwrap();
-            black_box(content_copy)
-        })
-    });
+    for (name, content) in compression_samples() {
+        let mut group = c.benchmark_group(format!("clipboard_content_decompression/{}", name));
+        group.throughput(Throughput::Bytes(content.data.len() as u64));
+
+        for codec in CODECS {
+            let mut compressed = content.clone();
+            compressed.compress(codec).unwrap();
+
+            group.bench_function(codec.to_string(), |b| {
+                b.iter(|| {
+                    let mut content_copy = black_box(compressed.clone());
+                    content_copy.decompress(usize::MAX).unwrap();
+                    black_box(content_copy)
+                })
+            });
+        }
+
+        group.finish();
+    }
 }
 
 fn bench_large_clipboard_content(c: &mut Criterion) {
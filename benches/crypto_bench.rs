@@ -0,0 +1,22 @@
+//! Encryption performance benchmarks
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use crosscopy::crypto::EncryptionService;
+
+fn bench_encryption_roundtrip(c: &mut Criterion) {
+    let key = EncryptionService::generate_random_key();
+    let service = EncryptionService::new(&key);
+    let data = b"Test data for encryption benchmark";
+
+    c.bench_function("encryption_roundtrip", |b| {
+        b.iter(|| {
+            let encrypted = service.encrypt(black_box(data)).unwrap();
+            let decrypted = service.decrypt(black_box(&encrypted)).unwrap();
+            black_box(decrypted)
+        })
+    });
+}
+
+criterion_group!(crypto_benches, bench_encryption_roundtrip);
+
+criterion_main!(crypto_benches);
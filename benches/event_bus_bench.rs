@@ -0,0 +1,119 @@
+//! Event bus performance benchmarks
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use crosscopy::config::{EventBusConfig, OverflowPolicy};
+use crosscopy::events::{Event, EventBus, EventPriority};
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const BURST: usize = 1000;
+
+fn priority(index: usize) -> EventPriority {
+    match index % 4 {
+        0 => EventPriority::Low,
+        1 => EventPriority::Normal,
+        2 => EventPriority::High,
+        _ => EventPriority::Critical,
+    }
+}
+
+fn synced(index: usize) -> Event {
+    Event::ClipboardSynced {
+        from_peer: "benchmark-peer".to_string(),
+        content_size: index,
+    }
+}
+
+fn bench_emit_and_poll(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("event_bus_emit_and_poll");
+    group.throughput(Throughput::Elements(BURST as u64));
+
+    group.bench_function("mixed_priorities", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let bus = EventBus::new();
+                for index in 0..BURST {
+                    bus.emit_with_priority(synced(index), priority(index)).await.unwrap();
+                }
+                while let Some(event) = bus.poll_event().await {
+                    black_box(event);
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_overflow(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("event_bus_overflow");
+    group.throughput(Throughput::Elements(BURST as u64));
+
+    // Every emit past the capacity drops an event
+    group.bench_function("drop_oldest_lowest", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let bus = EventBus::with_config(EventBusConfig {
+                    queue_capacity: 100,
+                    overflow: OverflowPolicy::DropOldestLowest,
+                    ..EventBusConfig::default()
+                });
+                for index in 0..BURST {
+                    bus.emit_with_priority(synced(index), priority(index)).await.unwrap();
+                }
+                black_box(bus.queue_size().await)
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_concurrent_emitters(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("event_bus_concurrent_emitters");
+    group.throughput(Throughput::Elements(BURST as u64));
+
+    for emitters in [1, 4, 16] {
+        group.bench_with_input(BenchmarkId::from_parameter(emitters), &emitters, |b, &emitters| {
+            b.iter(|| {
+                rt.block_on(async {
+                    let bus = Arc::new(EventBus::new());
+                    let mut events = bus.subscribe();
+
+                    let tasks: Vec<_> = (0..emitters)
+                        .map(|_| {
+                            let bus = bus.clone();
+                            tokio::spawn(async move {
+                                for index in 0..BURST / emitters {
+                                    bus.emit_with_priority(synced(index), priority(index)).await.unwrap();
+                                }
+                            })
+                        })
+                        .collect();
+                    for task in tasks {
+                        task.await.unwrap();
+                    }
+
+                    for _ in 0..(BURST / emitters) * emitters {
+                        black_box(events.next().await);
+                    }
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    event_bus_benches,
+    bench_emit_and_poll,
+    bench_overflow,
+    bench_concurrent_emitters,
+);
+
+criterion_main!(event_bus_benches);
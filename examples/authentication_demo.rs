@@ -3,10 +3,7 @@
 //! This example demonstrates the authentication flow between two devices
 //! using a 6-digit verification code for secure device pairing.
 
-use crosscopy::config::NetworkConfig;
-use crosscopy::events::EventBus;
-use crosscopy::network::NetworkManager;
-use std::sync::Arc;
+use std::io::Write;
 use std::time::Duration;
 use tokio::time::sleep;
 use tokio::io::{self, AsyncBufReadExt, BufReader};
@@ -32,6 +29,7 @@ pub struct AuthResult {
     pub error_message: Option<String>,
 }
 
+#[allow(dead_code)]
 pub struct AuthenticationDemo {
     device_name: String,
     is_server: bool,
@@ -82,7 +80,7 @@ impl AuthenticationDemo {
 
     /// Verify the authentication response (server side)
     pub fn verify_response(&mut self, response: &AuthResponse) -> AuthResult {
-        if let Some(ref challenge) = self.active_challenge {
+        if let Some(challenge) = self.active_challenge.clone() {
             // Check if challenge ID matches
             if challenge.challenge_id != response.challenge_id {
                 return AuthResult {
@@ -130,7 +128,7 @@ impl AuthenticationDemo {
 
     /// Simulate client-side authentication flow
     pub async fn simulate_client_authentication(&self) -> Result<(), Box<dyn std::error::Error>> {
-        println!("\n🔗 Initiating connection to '{}'...", "Server Device");
+        println!("\n🔗 Initiating connection to 'Server Device'...");
         sleep(Duration::from_millis(500)).await;
 
         println!("✅ Connection established");
@@ -154,7 +152,7 @@ impl AuthenticationDemo {
         let mut input = String::new();
         
         print!("Enter code: ");
-        io::Write::flush(&mut io::stdout())?;
+        std::io::stdout().flush()?;
         reader.read_line(&mut input).await?;
         
         let user_code = input.trim().to_string();
@@ -192,7 +190,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("1. Server (generates verification code)");
     println!("2. Client (enters verification code)");
     print!("Enter choice (1 or 2): ");
-    io::Write::flush(&mut io::stdout())?;
+    std::io::stdout().flush()?;
     
     let stdin = io::stdin();
     let mut reader = BufReader::new(stdin);
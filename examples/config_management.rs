@@ -6,11 +6,11 @@
 //! Run with: cargo run --example config_management
 
 use crosscopy::{
-    config::{AppConfig, ConfigManager, ClipboardConfig, NetworkConfig, SecurityConfig, LoggingConfig},
+    clipboard::CompressionCodec,
+    config::{AppConfig, ConfigManager, ClipboardConfig, FilterConfig, NetworkConfig, LazyTransferConfig, PolicyConfig, PrimarySelectionConfig, SecurityConfig, HistoryConfig, LoggingConfig, SyncConfig, TransformConfig, ExclusionConfig, ConcealedConfig, EventBusConfig, MetricsConfig},
     utils::logger,
 };
 use log::info;
-use std::path::PathBuf;
 use tempfile::tempdir;
 
 #[tokio::main]
@@ -170,15 +170,27 @@ fn create_custom_configuration() -> AppConfig {
             idle_connection_timeout: 300, // 5 minutes
             enable_quic: false,          // TCP only for this demo
             quic_port: None,
+            identity_file: None,
         },
         
         clipboard: ClipboardConfig {
             sync_images: true,
             sync_files: true,
             cooldown_millis: 250,
+            watch_changes: true,
+            poll_interval_millis: 100,
+            max_poll_interval_millis: 1000,
             max_content_size: 25 * 1024 * 1024, // 25MB
             enable_compression: true,
             compression_threshold: 5 * 1024, // 5KB
+            compression_codecs: vec![CompressionCodec::Lz4, CompressionCodec::Gzip],
+            filters: FilterConfig::default(),
+            primary: PrimarySelectionConfig::default(),
+            lazy: LazyTransferConfig::default(),
+            policies: PolicyConfig::default(),
+            transforms: TransformConfig::default(),
+            exclusions: ExclusionConfig::default(),
+            concealed: ConcealedConfig::default(),
         },
         
         security: SecurityConfig {
@@ -189,16 +201,22 @@ fn create_custom_configuration() -> AppConfig {
             max_message_age: 180, // 3 minutes
         },
         
+        history: HistoryConfig::default(),
+        sync: SyncConfig::default(),
+        events: EventBusConfig::default(),
+        metrics: MetricsConfig::default(),
         logging: LoggingConfig {
             level: "info".to_string(),
             file_path: Some("crosscopy_demo.log".to_string()),
             structured: false,
             max_file_size: 50 * 1024 * 1024, // 50MB
             max_files: 7,
+            ..LoggingConfig::default()
         },
     }
 }
 
+#[allow(clippy::field_reassign_with_default)]
 fn create_minimal_configuration() -> AppConfig {
     let mut config = AppConfig::default();
     config.device_name = "Minimal-CrossCopy".to_string();
@@ -209,6 +227,7 @@ fn create_minimal_configuration() -> AppConfig {
     config
 }
 
+#[allow(clippy::field_reassign_with_default)]
 fn create_high_security_configuration() -> AppConfig {
     let mut config = AppConfig::default();
     config.device_name = "Secure-CrossCopy".to_string();
@@ -220,6 +239,7 @@ fn create_high_security_configuration() -> AppConfig {
     config
 }
 
+#[allow(clippy::field_reassign_with_default)]
 fn create_performance_configuration() -> AppConfig {
     let mut config = AppConfig::default();
     config.device_name = "Performance-CrossCopy".to_string();
@@ -6,13 +6,16 @@
 //! Run with: cargo run --example custom_config
 
 use crosscopy::{
+    clipboard::CompressionCodec,
     config::{
-        AppConfig, ClipboardConfig, LoggingConfig, NetworkConfig, SecurityConfig,
+        AppConfig, ClipboardConfig, ConcealedConfig, EventBusConfig, ExclusionConfig, FilterConfig, HistoryConfig, LazyTransferConfig,
+        LoggingConfig, MetricsConfig, NetworkConfig, PolicyConfig, PrimarySelectionConfig, SecurityConfig, SyncConfig, TransformConfig,
     },
     utils::logger,
     CrossCopyApp,
 };
 use log::info;
+use std::collections::HashMap;
 use std::time::Duration;
 use tokio::signal;
 
@@ -67,7 +70,7 @@ async fn main() -> crosscopy::Result<()> {
 fn create_custom_config() -> AppConfig {
     AppConfig {
         device_name: "CustomCrossCopy".to_string(),
-        device_id: uuid::Uuid::new_v4().to_string(),
+        device_system: "CustomOS".to_string(),
         
         network: NetworkConfig {
             listen_port: 9999,
@@ -79,15 +82,27 @@ fn create_custom_config() -> AppConfig {
             idle_connection_timeout: 300, // 5 minutes
             enable_quic: false,        // TCP only for this example
             quic_port: None,
+            identity_file: None,
         },
         
         clipboard: ClipboardConfig {
             sync_images: true,
             sync_files: true,
             cooldown_millis: 500, // 500ms cooldown
+            watch_changes: true,
+            poll_interval_millis: 100,
+            max_poll_interval_millis: 2000, // Back off to 2s when idle
             max_content_size: 50 * 1024 * 1024, // 50MB
             enable_compression: true,
             compression_threshold: 10 * 1024, // 10KB
+            compression_codecs: vec![CompressionCodec::Zstd, CompressionCodec::Gzip],
+            filters: FilterConfig::default(),
+            primary: PrimarySelectionConfig::default(),
+            lazy: LazyTransferConfig::default(),
+            policies: PolicyConfig::default(),
+            transforms: TransformConfig::default(),
+            exclusions: ExclusionConfig::default(),
+            concealed: ConcealedConfig::default(),
         },
         
         security: SecurityConfig {
@@ -98,12 +113,18 @@ fn create_custom_config() -> AppConfig {
             max_message_age: 600, // 10 minutes
         },
         
+        history: HistoryConfig::default(),
+        sync: SyncConfig::default(),
+        events: EventBusConfig::default(),
+        metrics: MetricsConfig::default(),
         logging: LoggingConfig {
             level: "debug".to_string(),
             file_path: Some("crosscopy.log".to_string()),
             structured: true,
             max_file_size: 100 * 1024 * 1024, // 100MB
             max_files: 10,
+            stdout: true,
+            modules: HashMap::from([("libp2p".to_string(), "warn".to_string())]),
         },
     }
 }
@@ -120,7 +120,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let encrypt_time = start.elapsed();
     
     let start = std::time::Instant::now();
-    let large_decrypted = encryption_service.decrypt(&large_encrypted)?;
+    let _large_decrypted = encryption_service.decrypt(&large_encrypted)?;
     let decrypt_time = start.elapsed();
     
     info!("Large content (10KB) encryption time: {:?}", encrypt_time);
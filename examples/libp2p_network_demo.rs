@@ -29,6 +29,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         idle_connection_timeout: 300,
         enable_quic: false, // Use TCP only for this demo
         quic_port: None,
+        identity_file: None,
     };
     
     println!("Network Configuration:");
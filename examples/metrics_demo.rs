@@ -62,6 +62,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("    Average: {:?}", stats.average);
         info!("    Min: {:?}", stats.min);
         info!("    Max: {:?}", stats.max);
+        info!("    p50/p90/p99: {:?} / {:?} / {:?}", stats.p50, stats.p90, stats.p99);
         info!("    Total: {:?}", stats.total);
     }
 
@@ -227,7 +228,7 @@ async fn await_timer_metrics(metrics: &PerformanceMetrics) -> Result<(), Box<dyn
     
     // Test timer guard (automatic timing)
     {
-        let _guard = metrics.timer_guard("auto_timed_operation").await;
+        let _guard = metrics.timer_guard("auto_timed_operation");
         sleep(Duration::from_millis(25)).await;
         // Timer automatically ends when guard is dropped
     }
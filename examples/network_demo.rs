@@ -100,14 +100,26 @@ fn create_network_config(port: u16) -> AppConfig {
             idle_connection_timeout: 60, // Shorter timeout for demo (1 minute)
             enable_quic: false,          // TCP only for demo
             quic_port: None,
+            identity_file: None,
         },
         clipboard: crosscopy::config::ClipboardConfig {
             sync_images: false, // Simplified for demo
             sync_files: false,
             cooldown_millis: 300,
+            watch_changes: true,
+            poll_interval_millis: 100,
+            max_poll_interval_millis: 1000,
             max_content_size: 1024 * 1024, // 1MB
             enable_compression: false,
             compression_threshold: 1024,
+            compression_codecs: vec![crosscopy::clipboard::CompressionCodec::Zstd],
+            filters: crosscopy::config::FilterConfig::default(),
+            primary: crosscopy::config::PrimarySelectionConfig::default(),
+            lazy: crosscopy::config::LazyTransferConfig::default(),
+            policies: crosscopy::config::PolicyConfig::default(),
+            transforms: crosscopy::config::TransformConfig::default(),
+            exclusions: crosscopy::config::ExclusionConfig::default(),
+            concealed: crosscopy::config::ConcealedConfig::default(),
         },
         security: SecurityConfig {
             secret_key: "demo-secret-key".to_string(),
@@ -116,12 +128,17 @@ fn create_network_config(port: u16) -> AppConfig {
             enable_authentication: false,
             max_message_age: 300,
         },
+        history: crosscopy::config::HistoryConfig::default(),
+        sync: crosscopy::config::SyncConfig::default(),
+        events: crosscopy::config::EventBusConfig::default(),
+        metrics: crosscopy::config::MetricsConfig::default(),
         logging: crosscopy::config::LoggingConfig {
             level: "info".to_string(),
             file_path: None,
             structured: false,
             max_file_size: 10 * 1024 * 1024,
             max_files: 5,
+            ..crosscopy::config::LoggingConfig::default()
         },
     }
 }
@@ -4,7 +4,7 @@
 //! including mDNS peer discovery and connection management.
 
 use crosscopy::{
-    config::{AppConfig, NetworkConfig},
+    config::NetworkConfig,
     events::EventBus,
     network::NetworkManager,
 };
@@ -31,6 +31,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         idle_connection_timeout: 300,
         enable_quic: false,
         quic_port: None,
+        identity_file: None,
     };
     
     info!("Network Configuration:");
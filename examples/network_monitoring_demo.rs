@@ -59,6 +59,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         idle_connection_timeout: 300,
         enable_quic: false,
         quic_port: None,
+        identity_file: None,
     };
     
     info!("Network Configuration:");
@@ -69,21 +70,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create event bus and register event handler
     let event_bus = Arc::new(EventBus::new());
 
-    // Register network event handler
+    // Register network event handler; it runs for every event from now on
     let handler = Box::new(NetworkEventHandler);
     event_bus.register_handler(handler).await?;
-
-    let event_bus_clone = event_bus.clone();
-
-    // Start event processing task
-    tokio::spawn(async move {
-        loop {
-            if let Err(e) = event_bus_clone.process_events().await {
-                eprintln!("Error processing events: {}", e);
-            }
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }
-    });
     
     // Create network manager
     let mut network_manager = NetworkManager::new(config, event_bus.clone()).await?;
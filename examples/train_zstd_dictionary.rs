@@ -0,0 +1,72 @@
+//! Train the zstd dictionary used for text clipboard content
+//!
+//! Every file under the given paths is split into samples at line boundaries
+//! and a dictionary is trained from them. Peers must ship the same dictionary,
+//! so regenerate it only together with a protocol version bump.
+//!
+//! Run with: cargo run --example train_zstd_dictionary -- <output> <paths>...
+
+use std::path::{Path, PathBuf};
+
+/// Target size of a single training sample
+const SAMPLE_SIZE: usize = 1024;
+
+/// Size of the trained dictionary
+const DICTIONARY_SIZE: usize = 16 * 1024;
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?.collect::<Result<_, _>>()?;
+        entries.sort_by_key(|entry| entry.path());
+        for entry in entries {
+            collect_files(&entry.path(), files)?;
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn split_samples(text: &str, samples: &mut Vec<Vec<u8>>) {
+    let mut sample = String::new();
+    for line in text.lines() {
+        sample.push_str(line);
+        sample.push('\n');
+        if sample.len() >= SAMPLE_SIZE {
+            samples.push(std::mem::take(&mut sample).into_bytes());
+        }
+    }
+    if !sample.is_empty() {
+        samples.push(sample.into_bytes());
+    }
+}
+
+fn main() -> crosscopy::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let output = args.next().ok_or("usage: train_zstd_dictionary <output> <paths>...")?;
+
+    let mut files = Vec::new();
+    for path in args {
+        collect_files(Path::new(&path), &mut files)?;
+    }
+
+    let mut samples = Vec::new();
+    for file in &files {
+        // Skip binary files
+        if let Ok(text) = std::fs::read_to_string(file) {
+            split_samples(&text, &mut samples);
+        }
+    }
+
+    let dictionary = zstd::dict::from_samples(&samples, DICTIONARY_SIZE)?;
+    std::fs::write(&output, &dictionary)?;
+
+    println!(
+        "Trained {} byte dictionary from {} samples in {} files, written to {}",
+        dictionary.len(),
+        samples.len(),
+        files.len(),
+        output
+    );
+    Ok(())
+}
@@ -0,0 +1,360 @@
+//! Clipboard backends
+//!
+//! [`ClipboardBackend`] abstracts the system clipboard so the monitor can run
+//! against the real clipboard via arboard, or headless against
+//! [`MemoryClipboard`] in tests and CI containers.
+
+use crate::clipboard::watcher::{self, ChangeSignal, ChangeWatcher};
+use crate::clipboard::{ClipboardError, Result, Selection};
+use crate::utils::platform;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// MIME type used for plain text
+pub const MIME_TEXT: &str = "text/plain";
+
+/// MIME type used for images read from the system clipboard
+pub const MIME_IMAGE: &str = "image/png";
+
+/// Access to a clipboard, keyed by MIME type
+pub trait ClipboardBackend: Send + Sync {
+    /// Get backend name for logging
+    fn name(&self) -> &str;
+
+    /// List the MIME types (or platform formats) currently offered
+    fn formats(&self) -> Result<Vec<String>>;
+
+    /// Read the data offered for a MIME type, if any
+    fn get(&self, mime_type: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Replace the clipboard content with data of a single MIME type
+    fn set(&self, mime_type: &str, data: &[u8]) -> Result<()>;
+
+    /// Clear the clipboard
+    fn clear(&self) -> Result<()>;
+
+    /// Monotonic counter that changes whenever the clipboard changes
+    ///
+    /// Backends that cannot observe changes return `None` and are polled.
+    fn change_count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Signal raised on every clipboard change, for backends that push notifications
+    fn change_signal(&self) -> Option<Arc<ChangeSignal>> {
+        None
+    }
+
+    /// Check whether the current content is marked as concealed
+    fn is_concealed(&self) -> Result<bool> {
+        let formats = self.formats()?;
+        Ok(platform::formats_mark_concealed(&formats, |format| {
+            self.get(format).ok().flatten()
+        }))
+    }
+}
+
+/// System clipboard backend built on arboard
+pub struct ArboardBackend {
+    clipboard: Mutex<arboard::Clipboard>,
+    selection: Selection,
+    watcher: Option<ChangeWatcher>,
+}
+
+impl ArboardBackend {
+    /// Open the system clipboard
+    pub fn new() -> Result<Self> {
+        Self::for_selection(Selection::Clipboard)
+    }
+
+    /// Open a system selection; PRIMARY is only available on Linux
+    pub fn for_selection(selection: Selection) -> Result<Self> {
+        if selection == Selection::Primary && !cfg!(target_os = "linux") {
+            return Err(ClipboardError::AccessFailed(
+                "PRIMARY selection is only available on Linux".to_string(),
+            ));
+        }
+
+        let clipboard = arboard::Clipboard::new()
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))?;
+
+        Ok(Self {
+            clipboard: Mutex::new(clipboard),
+            selection,
+            watcher: None,
+        })
+    }
+
+    /// Open a system selection and watch it for change notifications
+    ///
+    /// Falls back to plain polling when the platform offers no notifications.
+    pub fn with_change_watcher(selection: Selection) -> Result<Self> {
+        let mut backend = Self::for_selection(selection)?;
+        backend.watcher = watcher::start_system_watcher(selection);
+        Ok(backend)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, arboard::Clipboard>> {
+        self.clipboard
+            .lock()
+            .map_err(|_| ClipboardError::AccessFailed("Clipboard lock poisoned".to_string()))
+    }
+
+    fn get_text(&self, clipboard: &mut arboard::Clipboard) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        if self.selection == Selection::Primary {
+            use arboard::GetExtLinux;
+            return clipboard.get().clipboard(arboard::LinuxClipboardKind::Primary).text().ok();
+        }
+
+        clipboard.get_text().ok()
+    }
+
+    fn get_image(&self, clipboard: &mut arboard::Clipboard) -> Option<Vec<u8>> {
+        // PRIMARY only ever holds selected text
+        if self.selection == Selection::Primary {
+            return None;
+        }
+
+        // arboard only exposes decoded RGBA pixels
+        clipboard.get_image().ok().map(|image| image.bytes.into_owned())
+    }
+}
+
+impl ClipboardBackend for ArboardBackend {
+    fn name(&self) -> &str {
+        match self.selection {
+            Selection::Clipboard => "arboard",
+            Selection::Primary => "arboard-primary",
+        }
+    }
+
+    fn formats(&self) -> Result<Vec<String>> {
+        let mut clipboard = self.lock()?;
+        let mut formats = Vec::new();
+
+        if self.get_text(&mut clipboard).is_some() {
+            formats.push(MIME_TEXT.to_string());
+        }
+        if self.get_image(&mut clipboard).is_some() {
+            formats.push(MIME_IMAGE.to_string());
+        }
+
+        Ok(formats)
+    }
+
+    fn get(&self, mime_type: &str) -> Result<Option<Vec<u8>>> {
+        let mut clipboard = self.lock()?;
+
+        match mime_type {
+            MIME_TEXT => Ok(self.get_text(&mut clipboard).map(String::into_bytes)),
+            MIME_IMAGE => Ok(self.get_image(&mut clipboard)),
+            _ => Ok(None),
+        }
+    }
+
+    fn set(&self, mime_type: &str, data: &[u8]) -> Result<()> {
+        if mime_type != MIME_TEXT {
+            return Err(ClipboardError::UnsupportedContentType);
+        }
+
+        let text = String::from_utf8(data.to_vec())
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))?;
+        let mut clipboard = self.lock()?;
+
+        #[cfg(target_os = "linux")]
+        if self.selection == Selection::Primary {
+            use arboard::SetExtLinux;
+            return clipboard
+                .set()
+                .clipboard(arboard::LinuxClipboardKind::Primary)
+                .text(text)
+                .map_err(|e| ClipboardError::AccessFailed(e.to_string()));
+        }
+
+        clipboard
+            .set_text(text)
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut clipboard = self.lock()?;
+
+        #[cfg(target_os = "linux")]
+        if self.selection == Selection::Primary {
+            use arboard::ClearExtLinux;
+            return clipboard
+                .clear_with()
+                .clipboard(arboard::LinuxClipboardKind::Primary)
+                .map_err(|e| ClipboardError::AccessFailed(e.to_string()));
+        }
+
+        clipboard
+            .clear()
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))
+    }
+
+    fn change_count(&self) -> Option<u64> {
+        match self.watcher.as_ref().map(ChangeWatcher::signal) {
+            Some(signal) if signal.is_active() => Some(signal.count()),
+            _ if self.selection == Selection::Clipboard => platform::clipboard_change_count(),
+            _ => None,
+        }
+    }
+
+    fn change_signal(&self) -> Option<Arc<ChangeSignal>> {
+        self.watcher.as_ref().map(|watcher| watcher.signal().clone())
+    }
+
+    fn is_concealed(&self) -> Result<bool> {
+        // arboard cannot enumerate formats, so ask the platform directly
+        let concealed = match self.selection {
+            Selection::Clipboard => platform::is_clipboard_concealed(),
+            Selection::Primary => platform::is_primary_concealed(),
+        };
+        concealed.map_err(|e| ClipboardError::AccessFailed(e.to_string()))
+    }
+}
+
+/// In-memory clipboard for tests and headless environments
+///
+/// Every change is pushed through its [`ChangeSignal`], like a backend with
+/// native notifications.
+#[derive(Default)]
+pub struct MemoryClipboard {
+    state: Mutex<MemoryState>,
+    signal: Arc<ChangeSignal>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    items: HashMap<String, Vec<u8>>,
+    change_count: u64,
+}
+
+impl MemoryClipboard {
+    /// Create an empty in-memory clipboard
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the content with several representations at once
+    ///
+    /// This mirrors how applications offer multiple formats, e.g. text plus
+    /// a password manager hint.
+    pub fn set_formats(&self, items: &[(&str, &[u8])]) {
+        let mut state = self.state.lock().expect("memory clipboard lock poisoned");
+        state.items = items
+            .iter()
+            .map(|(mime_type, data)| (mime_type.to_string(), data.to_vec()))
+            .collect();
+        state.change_count += 1;
+        drop(state);
+
+        self.signal.notify_change();
+    }
+
+    /// Convenience helper to place text on the clipboard
+    pub fn set_text(&self, text: &str) {
+        self.set_formats(&[(MIME_TEXT, text.as_bytes())]);
+    }
+
+    /// Convenience helper to read text from the clipboard
+    pub fn text(&self) -> Option<String> {
+        let state = self.state.lock().expect("memory clipboard lock poisoned");
+        state
+            .items
+            .get(MIME_TEXT)
+            .and_then(|data| String::from_utf8(data.clone()).ok())
+    }
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn formats(&self) -> Result<Vec<String>> {
+        let state = self.state.lock().expect("memory clipboard lock poisoned");
+        Ok(state.items.keys().cloned().collect())
+    }
+
+    fn get(&self, mime_type: &str) -> Result<Option<Vec<u8>>> {
+        let state = self.state.lock().expect("memory clipboard lock poisoned");
+        Ok(state.items.get(mime_type).cloned())
+    }
+
+    fn set(&self, mime_type: &str, data: &[u8]) -> Result<()> {
+        self.set_formats(&[(mime_type, data)]);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut state = self.state.lock().expect("memory clipboard lock poisoned");
+        state.items.clear();
+        state.change_count += 1;
+        drop(state);
+
+        self.signal.notify_change();
+        Ok(())
+    }
+
+    fn change_count(&self) -> Option<u64> {
+        Some(self.state.lock().expect("memory clipboard lock poisoned").change_count)
+    }
+
+    fn change_signal(&self) -> Option<Arc<ChangeSignal>> {
+        Some(self.signal.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_clipboard_roundtrip() {
+        let clipboard = MemoryClipboard::new();
+        assert_eq!(clipboard.get(MIME_TEXT).unwrap(), None);
+
+        clipboard.set(MIME_TEXT, b"hello").unwrap();
+        assert_eq!(clipboard.get(MIME_TEXT).unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(clipboard.formats().unwrap(), vec![MIME_TEXT.to_string()]);
+
+        clipboard.clear().unwrap();
+        assert!(clipboard.formats().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory_clipboard_change_count() {
+        let clipboard = MemoryClipboard::new();
+        let initial = clipboard.change_count().unwrap();
+
+        clipboard.set_text("one");
+        clipboard.set_text("two");
+        assert_eq!(clipboard.change_count(), Some(initial + 2));
+    }
+
+    #[test]
+    fn test_memory_clipboard_pushes_changes() {
+        let clipboard = MemoryClipboard::new();
+        let signal = clipboard.change_signal().unwrap();
+
+        clipboard.set_text("one");
+        clipboard.clear().unwrap();
+        assert_eq!(signal.count(), 2);
+    }
+
+    #[test]
+    fn test_memory_clipboard_concealed() {
+        let clipboard = MemoryClipboard::new();
+        clipboard.set_text("hello");
+        assert!(!clipboard.is_concealed().unwrap());
+
+        clipboard.set_formats(&[
+            (MIME_TEXT, b"hunter2"),
+            (platform::KDE_PASSWORD_MANAGER_HINT, b"secret"),
+        ]);
+        assert!(clipboard.is_concealed().unwrap());
+    }
+}
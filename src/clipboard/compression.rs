@@ -0,0 +1,276 @@
+//! Compression codecs for clipboard content
+//!
+//! The codec used for an item is recorded in its metadata. Peers advertise the
+//! codecs they can decode during the handshake and the sender picks the first
+//! codec from its preference list that every connected peer supports.
+
+#[cfg(feature = "compression")]
+use crate::clipboard::{ClipboardError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Compression codec applied to clipboard data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionCodec {
+    #[default]
+    None,
+    Gzip,
+    /// zstd, using the bundled dictionary for text
+    Zstd,
+    Lz4,
+}
+
+impl fmt::Display for CompressionCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionCodec::None => write!(f, "none"),
+            CompressionCodec::Gzip => write!(f, "gzip"),
+            CompressionCodec::Zstd => write!(f, "zstd"),
+            CompressionCodec::Lz4 => write!(f, "lz4"),
+        }
+    }
+}
+
+/// Dictionary trained on text, JSON, logs and code (see `examples/train_zstd_dictionary.rs`)
+#[cfg(feature = "compression")]
+static TEXT_DICTIONARY: &[u8] = include_bytes!("dictionaries/text.dict");
+
+/// zstd level balancing speed and ratio for interactive copies
+#[cfg(feature = "compression")]
+const ZSTD_LEVEL: i32 = 3;
+
+impl CompressionCodec {
+    /// Codecs this build can decode, in the order they are preferred
+    pub fn supported() -> Vec<CompressionCodec> {
+        #[cfg(feature = "compression")]
+        {
+            vec![
+                CompressionCodec::Zstd,
+                CompressionCodec::Lz4,
+                CompressionCodec::Gzip,
+                CompressionCodec::None,
+            ]
+        }
+
+        #[cfg(not(feature = "compression"))]
+        {
+            vec![CompressionCodec::None]
+        }
+    }
+
+    /// Pick the first preferred codec that every peer supports
+    ///
+    /// Falls back to no compression, which every peer can read.
+    pub fn negotiate<'a>(
+        preferred: &[CompressionCodec],
+        peers: impl IntoIterator<Item = &'a [CompressionCodec]>,
+    ) -> CompressionCodec {
+        let supported = Self::supported();
+        let mut candidates: Vec<CompressionCodec> = preferred
+            .iter()
+            .copied()
+            .filter(|codec| supported.contains(codec))
+            .collect();
+
+        for peer in peers {
+            candidates.retain(|codec| peer.contains(codec));
+        }
+
+        candidates.first().copied().unwrap_or(CompressionCodec::None)
+    }
+
+    /// Compress `data`; `text` selects the zstd text dictionary
+    #[cfg(feature = "compression")]
+    pub fn compress(&self, data: &[u8], text: bool) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        match self {
+            CompressionCodec::None => Ok(data.to_vec()),
+            CompressionCodec::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            CompressionCodec::Zstd => {
+                let dictionary = if text { TEXT_DICTIONARY } else { &[] };
+                let mut compressor = zstd::bulk::Compressor::with_dictionary(ZSTD_LEVEL, dictionary)?;
+                Ok(compressor.compress(data)?)
+            }
+            CompressionCodec::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        }
+    }
+
+    /// Decompress data produced by [`CompressionCodec::compress`]
+    ///
+    /// `max_size` bounds the decompressed size.
+    #[cfg(feature = "compression")]
+    pub fn decompress(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>> {
+        use std::io::Read;
+
+        match self {
+            CompressionCodec::None => Ok(data.to_vec()),
+            CompressionCodec::Gzip => {
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                if decompressed.len() > max_size {
+                    return Err(ClipboardError::ContentTooLarge {
+                        size: decompressed.len(),
+                        max_size,
+                    });
+                }
+                Ok(decompressed)
+            }
+            CompressionCodec::Zstd => {
+                // Frames record whether they were written with the dictionary
+                let mut decompressor = zstd::bulk::Decompressor::with_dictionary(TEXT_DICTIONARY)?;
+                decompressor
+                    .decompress(data, max_size)
+                    .map_err(|e| ClipboardError::Compression(format!("zstd: {}", e)))
+            }
+            CompressionCodec::Lz4 => {
+                let size = data
+                    .get(..4)
+                    .map(|prefix| u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize)
+                    .unwrap_or(0);
+                if size > max_size {
+                    return Err(ClipboardError::ContentTooLarge { size, max_size });
+                }
+                lz4_flex::decompress_size_prepended(data)
+                    .map_err(|e| ClipboardError::Compression(format!("lz4: {}", e)))
+            }
+        }
+    }
+}
+
+/// Codec fields of flattened metadata
+///
+/// Besides `compression`, the boolean `compressed` flag older peers require is
+/// written and read. Those peers take it to mean gzip, the only codec they are
+/// sent (see [`crate::network::NetworkManager::negotiate_codec`]).
+pub(crate) mod codec_fields {
+    use super::CompressionCodec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct Fields {
+        compression: CompressionCodec,
+        compressed: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct ReceivedFields {
+        #[serde(default)]
+        compression: Option<CompressionCodec>,
+        #[serde(default)]
+        compressed: Option<bool>,
+    }
+
+    pub fn serialize<S: Serializer>(codec: &CompressionCodec, serializer: S) -> Result<S::Ok, S::Error> {
+        Fields {
+            compression: *codec,
+            compressed: *codec != CompressionCodec::None,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CompressionCodec, D::Error> {
+        let fields = ReceivedFields::deserialize(deserializer)?;
+        Ok(match (fields.compression, fields.compressed) {
+            (Some(codec), _) => codec,
+            (None, Some(true)) => CompressionCodec::Gzip,
+            (None, _) => CompressionCodec::None,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        r#"{"level":"info","target":"crosscopy::network","message":"Peer connected"}"#
+            .repeat(50)
+            .into_bytes()
+    }
+
+    #[test]
+    fn test_codecs_roundtrip() {
+        let data = sample();
+        for codec in CompressionCodec::supported() {
+            for text in [true, false] {
+                let compressed = codec.compress(&data, text).unwrap();
+                assert_eq!(codec.decompress(&compressed, data.len()).unwrap(), data, "{}", codec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decompression_is_bounded() {
+        let data = sample();
+        for codec in [CompressionCodec::Gzip, CompressionCodec::Zstd, CompressionCodec::Lz4] {
+            let compressed = codec.compress(&data, true).unwrap();
+            assert!(codec.decompress(&compressed, 100).is_err(), "{}", codec);
+        }
+    }
+
+    #[test]
+    fn test_claimed_size_is_checked_before_decompressing() {
+        use crate::clipboard::{ClipboardContent, ClipboardError};
+
+        let mut content = ClipboardContent::new_text(String::from_utf8(sample()).unwrap(), "test".to_string());
+        content.compress(CompressionCodec::Zstd).unwrap();
+        let size = content.metadata.size;
+
+        let mut received = content.clone();
+        assert!(matches!(
+            received.decompress(size - 1),
+            Err(ClipboardError::ContentTooLarge { max_size, .. }) if max_size == size - 1
+        ));
+        assert_eq!(received.metadata.compression, CompressionCodec::Zstd);
+
+        content.decompress(size).unwrap();
+        assert_eq!(content.data, sample());
+    }
+
+    #[test]
+    fn test_negotiation_picks_common_codec() {
+        let preferred = [CompressionCodec::Zstd, CompressionCodec::Lz4, CompressionCodec::Gzip];
+        let modern = CompressionCodec::supported();
+        let legacy = vec![CompressionCodec::Gzip, CompressionCodec::None];
+
+        assert_eq!(CompressionCodec::negotiate(&preferred, []), CompressionCodec::Zstd);
+        assert_eq!(
+            CompressionCodec::negotiate(&preferred, [modern.as_slice(), legacy.as_slice()]),
+            CompressionCodec::Gzip
+        );
+        assert_eq!(
+            CompressionCodec::negotiate(&[CompressionCodec::Lz4], [legacy.as_slice()]),
+            CompressionCodec::None
+        );
+    }
+
+    #[test]
+    fn test_legacy_compressed_flag() {
+        #[derive(Serialize, Deserialize)]
+        struct Metadata {
+            size: usize,
+            #[serde(flatten, with = "codec_fields")]
+            compression: CompressionCodec,
+        }
+
+        let parse = |json: &str| serde_json::from_str::<Metadata>(json).unwrap().compression;
+        assert_eq!(parse(r#"{"size":1,"compressed":true}"#), CompressionCodec::Gzip);
+        assert_eq!(parse(r#"{"size":1,"compressed":false}"#), CompressionCodec::None);
+        assert_eq!(parse(r#"{"size":1}"#), CompressionCodec::None);
+        assert_eq!(parse(r#"{"size":1,"compression":"zstd","compressed":true}"#), CompressionCodec::Zstd);
+
+        // Older peers require the flag
+        let written = |compression| serde_json::to_value(Metadata { size: 1, compression }).unwrap();
+        assert_eq!(written(CompressionCodec::Gzip)["compressed"], true);
+        assert_eq!(written(CompressionCodec::None)["compressed"], false);
+        assert_eq!(written(CompressionCodec::Lz4)["compression"], "lz4");
+    }
+}
@@ -1,5 +1,8 @@
 //! Clipboard content types and serialization
 
+use crate::clipboard::sniff::{self, ContentKind};
+use crate::clipboard::CompressionCodec;
+use crate::network::HlcTimestamp;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -30,6 +33,26 @@ impl fmt::Display for ContentType {
     }
 }
 
+/// System selection an item was copied from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// The regular copy/paste clipboard
+    #[default]
+    Clipboard,
+    /// The Linux PRIMARY selection used by middle-click paste
+    Primary,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Clipboard => write!(f, "CLIPBOARD"),
+            Selection::Primary => write!(f, "PRIMARY"),
+        }
+    }
+}
+
 /// Clipboard content container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardContent {
@@ -48,6 +71,9 @@ pub struct ClipboardContent {
 pub struct ContentMetadata {
     /// MIME type
     pub mime_type: Option<String>,
+    /// Detected kind of content
+    #[serde(default)]
+    pub kind: ContentKind,
     /// Original filename (for files)
     pub filename: Option<String>,
     /// Content size in bytes
@@ -56,13 +82,47 @@ pub struct ContentMetadata {
     pub created_at: u64,
     /// Source device ID
     pub source_device: String,
-    /// Whether content is compressed
-    pub compressed: bool,
+    /// Codec the data is compressed with
+    #[serde(flatten, with = "crate::clipboard::compression::codec_fields")]
+    pub compression: CompressionCodec,
+    /// Expiry timestamp after which the item should be cleared
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Selection the content was copied from
+    #[serde(default)]
+    pub selection: Selection,
+    /// Hybrid clock time the item was picked up on the copying device
+    #[serde(default)]
+    pub clock: Option<HlcTimestamp>,
+}
+
+/// Metadata-only announcement of an item whose body peers fetch on demand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentAnnouncement {
+    /// Type of content
+    pub content_type: ContentType,
+    /// Content metadata
+    pub metadata: ContentMetadata,
+    /// Checksum the body is stored and fetched under
+    pub checksum: String,
+    /// Beginning of the text, for text items
+    pub preview: Option<String>,
+}
+
+impl ContentAnnouncement {
+    /// Check whether the announced content has passed its expiry time
+    pub fn is_expired(&self) -> bool {
+        self.metadata
+            .expires_at
+            .map(|expires_at| expires_at <= chrono::Utc::now().timestamp_millis() as u64)
+            .unwrap_or(false)
+    }
 }
 
 impl ClipboardContent {
     /// Create new text content
     pub fn new_text(text: String, source_device: String) -> Self {
+        let kind = sniff::classify_text(&text);
         let data = text.into_bytes();
         let size = data.len();
         let checksum = Self::calculate_checksum(&data);
@@ -71,19 +131,28 @@ impl ClipboardContent {
             content_type: ContentType::Text,
             data,
             metadata: ContentMetadata {
-                mime_type: Some("text/plain".to_string()),
+                mime_type: Some(sniff::text_mime(kind).to_string()),
+                kind,
                 filename: None,
                 size,
                 created_at: chrono::Utc::now().timestamp_millis() as u64,
                 source_device,
-                compressed: false,
+                compression: CompressionCodec::None,
+                expires_at: None,
+                selection: Selection::Clipboard,
+                clock: None,
             },
             checksum,
         }
     }
 
     /// Create new image content
+    ///
+    /// The MIME type is taken from the image's magic bytes when they are recognized.
     pub fn new_image(image_data: Vec<u8>, mime_type: String, source_device: String) -> Self {
+        let mime_type = sniff::sniff_mime(&image_data)
+            .map(str::to_string)
+            .unwrap_or(mime_type);
         let size = image_data.len();
         let checksum = Self::calculate_checksum(&image_data);
 
@@ -92,23 +161,36 @@ impl ClipboardContent {
             data: image_data,
             metadata: ContentMetadata {
                 mime_type: Some(mime_type),
+                kind: ContentKind::Image,
                 filename: None,
                 size,
                 created_at: chrono::Utc::now().timestamp_millis() as u64,
                 source_device,
-                compressed: false,
+                compression: CompressionCodec::None,
+                expires_at: None,
+                selection: Selection::Clipboard,
+                clock: None,
             },
             checksum,
         }
     }
 
     /// Create new file content
+    ///
+    /// Without a MIME type one is detected from the data or the file name.
     pub fn new_file(
         file_data: Vec<u8>,
         filename: String,
         mime_type: Option<String>,
         source_device: String,
     ) -> Self {
+        let (mime_type, kind) = match mime_type {
+            Some(mime_type) => {
+                let kind = ContentKind::from_mime(&mime_type);
+                (Some(mime_type), kind)
+            }
+            None => Self::detect_file_type(&file_data, &filename),
+        };
         let size = file_data.len();
         let checksum = Self::calculate_checksum(&file_data);
 
@@ -117,16 +199,34 @@ impl ClipboardContent {
             data: file_data,
             metadata: ContentMetadata {
                 mime_type,
+                kind,
                 filename: Some(filename),
                 size,
                 created_at: chrono::Utc::now().timestamp_millis() as u64,
                 source_device,
-                compressed: false,
+                compression: CompressionCodec::None,
+                expires_at: None,
+                selection: Selection::Clipboard,
+                clock: None,
             },
             checksum,
         }
     }
 
+    fn detect_file_type(data: &[u8], filename: &str) -> (Option<String>, ContentKind) {
+        if let Some(mime_type) = sniff::sniff_file_mime(data, filename) {
+            return (Some(mime_type.to_string()), ContentKind::from_mime(mime_type));
+        }
+
+        match std::str::from_utf8(data) {
+            Ok(text) => {
+                let kind = sniff::classify_text(text);
+                (Some(sniff::text_mime(kind).to_string()), kind)
+            }
+            Err(_) => (None, ContentKind::Binary),
+        }
+    }
+
     /// Get content as text (if it's text content)
     pub fn as_text(&self) -> Option<String> {
         if self.content_type == ContentType::Text {
@@ -136,11 +236,62 @@ impl ClipboardContent {
         }
     }
 
+    /// Replace the text of the item, keeping its metadata
+    pub fn set_text(&mut self, text: String) {
+        self.data = text.into_bytes();
+        self.metadata.size = self.data.len();
+        self.checksum = Self::calculate_checksum(&self.data);
+    }
+
+    /// Describe the content without its body, with a preview of up to `preview_chars` characters
+    pub fn announcement(&self, preview_chars: usize) -> ContentAnnouncement {
+        let preview = if self.content_type == ContentType::Text && preview_chars > 0 {
+            self.text_preview(preview_chars)
+        } else {
+            None
+        };
+
+        ContentAnnouncement {
+            content_type: self.content_type.clone(),
+            metadata: self.metadata.clone(),
+            checksum: self.checksum.clone(),
+            preview,
+        }
+    }
+
+    fn text_preview(&self, preview_chars: usize) -> Option<String> {
+        #[cfg(feature = "compression")]
+        if self.metadata.compression != CompressionCodec::None {
+            let mut content = self.clone();
+            content.decompress(self.metadata.size).ok()?;
+            return content.text_preview(preview_chars);
+        }
+
+        if self.metadata.compression != CompressionCodec::None {
+            return None;
+        }
+
+        self.as_text().map(|text| text.chars().take(preview_chars).collect())
+    }
+
     /// Get content as bytes
     pub fn to_bytes(&self) -> Vec<u8> {
         self.data.clone()
     }
 
+    /// Check whether the content has passed its expiry time
+    pub fn is_expired(&self) -> bool {
+        self.expires_in() == Some(std::time::Duration::ZERO)
+    }
+
+    /// Time remaining until the content expires, if it has an expiry
+    pub fn expires_in(&self) -> Option<std::time::Duration> {
+        self.metadata.expires_at.map(|expires_at| {
+            let now = chrono::Utc::now().timestamp_millis() as u64;
+            std::time::Duration::from_millis(expires_at.saturating_sub(now))
+        })
+    }
+
     /// Verify content integrity
     pub fn verify_integrity(&self) -> bool {
         let calculated_checksum = Self::calculate_checksum(&self.data);
@@ -155,24 +306,21 @@ impl ClipboardContent {
         format!("{:x}", hasher.finalize())
     }
 
-    /// Compress content data
+    /// Compress content data with `codec`
+    ///
+    /// Content that is already compressed is left as is.
     #[cfg(feature = "compression")]
-    pub fn compress(&mut self) -> crate::clipboard::Result<()> {
-        use flate2::{write::GzEncoder, Compression};
-        use std::io::Write;
-
-        if self.metadata.compressed {
+    pub fn compress(&mut self, codec: CompressionCodec) -> crate::clipboard::Result<()> {
+        if self.metadata.compression != CompressionCodec::None {
             return Ok(());
         }
 
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(&self.data)?;
-        let compressed_data = encoder.finish()?;
+        let compressed_data = codec.compress(&self.data, self.content_type == ContentType::Text)?;
 
         // Only use compression if it actually reduces size
         if compressed_data.len() < self.data.len() {
             self.data = compressed_data;
-            self.metadata.compressed = true;
+            self.metadata.compression = codec;
             self.checksum = Self::calculate_checksum(&self.data);
         }
 
@@ -180,21 +328,25 @@ impl ClipboardContent {
     }
 
     /// Decompress content data
+    ///
+    /// Items that claim to be larger than `max_size` are rejected before
+    /// anything is decompressed.
     #[cfg(feature = "compression")]
-    pub fn decompress(&mut self) -> crate::clipboard::Result<()> {
-        use flate2::read::GzDecoder;
-        use std::io::Read;
-
-        if !self.metadata.compressed {
+    pub fn decompress(&mut self, max_size: usize) -> crate::clipboard::Result<()> {
+        if self.metadata.compression == CompressionCodec::None {
             return Ok(());
         }
 
-        let mut decoder = GzDecoder::new(&self.data[..]);
-        let mut decompressed_data = Vec::new();
-        decoder.read_to_end(&mut decompressed_data)?;
+        if self.metadata.size > max_size {
+            return Err(crate::clipboard::ClipboardError::ContentTooLarge {
+                size: self.metadata.size,
+                max_size,
+            });
+        }
 
-        self.data = decompressed_data;
-        self.metadata.compressed = false;
+        // The recorded size bounds the output
+        self.data = self.metadata.compression.decompress(&self.data, self.metadata.size)?;
+        self.metadata.compression = CompressionCodec::None;
         self.metadata.size = self.data.len();
         self.checksum = Self::calculate_checksum(&self.data);
 
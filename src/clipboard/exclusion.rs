@@ -0,0 +1,152 @@
+//! Application exclusion list
+//!
+//! The [`ExclusionList`] looks up the application owning the focused window
+//! when a copy is detected, and keeps copies from excluded applications such as
+//! password managers from being synced.
+
+use crate::config::ExclusionConfig;
+use crate::utils::platform::{self, ActiveWindow};
+use log::warn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Lookup of the application owning the focused window
+pub type WindowLookup = Arc<dyn Fn() -> crate::utils::Result<ActiveWindow> + Send + Sync>;
+
+/// Process names and window classes whose copies are not synced
+pub struct ExclusionList {
+    processes: Vec<String>,
+    window_classes: Vec<String>,
+    lookup: WindowLookup,
+    /// Whether the lookup failing has been reported
+    warned: AtomicBool,
+}
+
+impl ExclusionList {
+    /// Build the list from configuration, using the platform's active-window lookup
+    pub fn from_config(config: &ExclusionConfig) -> Self {
+        Self::with_lookup(config, Arc::new(platform::active_window))
+    }
+
+    /// Build the list from configuration with a custom active-window lookup
+    pub fn with_lookup(config: &ExclusionConfig, lookup: WindowLookup) -> Self {
+        let (processes, window_classes) = if config.enabled {
+            (
+                config.processes.iter().map(|name| normalize_process(name)).collect(),
+                config.window_classes.iter().map(|class| class.to_lowercase()).collect(),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        Self {
+            processes,
+            window_classes,
+            lookup,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Check whether no application is excluded
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty() && self.window_classes.is_empty()
+    }
+
+    /// Excluded process name or window class matching a window, if any
+    pub fn matches(&self, window: &ActiveWindow) -> Option<String> {
+        let process = window
+            .process_name
+            .as_deref()
+            .filter(|name| self.processes.contains(&normalize_process(name)));
+        let class = window
+            .window_class
+            .as_deref()
+            .filter(|class| self.window_classes.contains(&class.to_lowercase()));
+
+        process.or(class).map(str::to_string)
+    }
+
+    /// Excluded application owning the focused window, if any
+    ///
+    /// Where the focused window cannot be looked up the list is not enforced,
+    /// which is reported once.
+    pub fn excluded_source(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        match (self.lookup)() {
+            Ok(window) => self.matches(&window),
+            Err(e) => {
+                if !self.warned.swap(true, Ordering::Relaxed) {
+                    warn!("Cannot tell which application copied, the exclusion list is not enforced: {}", e);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Lowercase a process name and strip a Windows `.exe` suffix
+fn normalize_process(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::UtilError;
+
+    fn config() -> ExclusionConfig {
+        ExclusionConfig {
+            enabled: true,
+            processes: vec!["KeePassXC".to_string(), "secrets-tool".to_string()],
+            window_classes: vec!["com.example.Bank".to_string()],
+        }
+    }
+
+    fn window(process_name: Option<&str>, window_class: Option<&str>) -> ActiveWindow {
+        ActiveWindow {
+            pid: Some(42),
+            process_name: process_name.map(str::to_string),
+            window_class: window_class.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_matches_processes_and_window_classes() {
+        let list = ExclusionList::with_lookup(&config(), Arc::new(|| Ok(ActiveWindow::default())));
+
+        assert_eq!(list.matches(&window(Some("keepassxc"), None)).as_deref(), Some("keepassxc"));
+        assert_eq!(list.matches(&window(Some("SECRETS-TOOL.EXE"), None)).as_deref(), Some("SECRETS-TOOL.EXE"));
+        assert!(list.matches(&window(None, Some("com.example.bank"))).is_some());
+        assert!(list.matches(&window(Some("firefox"), Some("Navigator"))).is_none());
+        assert!(list.matches(&ActiveWindow::default()).is_none());
+    }
+
+    #[test]
+    fn test_excluded_source_uses_lookup() {
+        let list = ExclusionList::with_lookup(&config(), Arc::new(|| Ok(window(Some("secrets-tool"), None))));
+        assert_eq!(list.excluded_source().as_deref(), Some("secrets-tool"));
+
+        // Without active-window information copies are not blocked
+        let unsupported = ExclusionList::with_lookup(
+            &config(),
+            Arc::new(|| Err(UtilError::PlatformError("not supported".to_string()))),
+        );
+        assert!(unsupported.excluded_source().is_none());
+        assert!(unsupported.excluded_source().is_none());
+    }
+
+    #[test]
+    fn test_disabled_list_skips_lookup() {
+        let disabled = ExclusionConfig { enabled: false, ..config() };
+        let list = ExclusionList::with_lookup(&disabled, Arc::new(|| panic!("lookup must not run")));
+        assert!(list.is_empty());
+        assert!(list.excluded_source().is_none());
+    }
+}
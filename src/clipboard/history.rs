@@ -0,0 +1,511 @@
+//! Encrypted clipboard history store
+//!
+//! Every record is encrypted with a key derived from the shared secret in
+//! [`SecurityConfig`] and a per-store salt, so nothing is written to disk in
+//! plaintext. The salt and a key verifier live in a small key file next to
+//! the records; wiping the store removes both.
+//!
+//! Re-keying writes the re-encrypted records and the new key file into a
+//! staging directory next to the store and then swaps it in, so a crash
+//! never leaves records and key file out of step. [`ClipboardHistory::open`]
+//! finishes or rolls back a swap that was interrupted.
+
+use crate::clipboard::{ClipboardContent, ClipboardError, Result};
+use crate::config::SecurityConfig;
+use crate::crypto::EncryptionService;
+use log::{debug, info, warn};
+use rand::{RngCore, thread_rng};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Name of the key file inside the history directory
+const KEY_FILE_NAME: &str = "history.key";
+
+/// Suffix of the directory a re-key is staged in
+const STAGING_SUFFIX: &str = "rekey";
+
+/// Suffix the old store is moved to while the staged one is swapped in
+const BACKUP_SUFFIX: &str = "old";
+
+/// File extension of encrypted records
+const RECORD_EXTENSION: &str = "rec";
+
+/// PBKDF2 iterations used for new keys
+const KDF_ITERATIONS: u32 = 100_000;
+
+/// Known plaintext used to verify the derived key when opening the store
+const KEY_VERIFIER: &[u8] = b"crosscopy-history-v1";
+
+/// A single clipboard history entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Unique record ID
+    pub id: String,
+    /// Time the record was stored (milliseconds since epoch)
+    pub recorded_at: u64,
+    /// Stored clipboard content
+    pub content: ClipboardContent,
+}
+
+/// Key material persisted alongside the records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    salt: Vec<u8>,
+    iterations: u32,
+    verifier: Vec<u8>,
+}
+
+/// Encrypted on-disk clipboard history
+pub struct ClipboardHistory {
+    directory: PathBuf,
+    max_items: usize,
+    encryption: EncryptionService,
+    last_recorded_at: AtomicU64,
+}
+
+impl ClipboardHistory {
+    /// Open the history store in `directory`, creating it if needed
+    pub async fn open(directory: &Path, max_items: usize, security: &SecurityConfig) -> Result<Self> {
+        Self::recover_rekey(directory).await?;
+        create_private_dir(directory).await?;
+
+        let key_path = directory.join(KEY_FILE_NAME);
+        let encryption = if tokio::fs::try_exists(&key_path).await? {
+            let key_file: KeyFile = serde_json::from_slice(&tokio::fs::read(&key_path).await?)?;
+            let encryption = EncryptionService::from_config_with_salt(security, &key_file.salt, key_file.iterations)?;
+
+            // Refuse to open the store with the wrong secret instead of failing per record
+            match encryption.decrypt(&key_file.verifier) {
+                Ok(plaintext) if plaintext == KEY_VERIFIER => encryption,
+                _ => {
+                    return Err(ClipboardError::HistoryFailed(
+                        "History key does not match the configured secret".to_string(),
+                    ))
+                }
+            }
+        } else {
+            info!("Creating new clipboard history store in {}", directory.display());
+            let (encryption, key_file) = Self::new_key(security)?;
+            write_private_file(&key_path, &serde_json::to_vec(&key_file)?).await?;
+            encryption
+        };
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            max_items,
+            encryption,
+            last_recorded_at: AtomicU64::new(0),
+        })
+    }
+
+    /// Store a clipboard item, pruning the oldest items beyond `max_items`
+    pub async fn record(&self, content: &ClipboardContent) -> Result<HistoryRecord> {
+        // Keep timestamps strictly increasing so records stored within the same
+        // millisecond still list in insertion order
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let previous = self.last_recorded_at
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now.max(last + 1)))
+            .unwrap_or(now);
+
+        let record = HistoryRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            recorded_at: now.max(previous + 1),
+            content: content.clone(),
+        };
+
+        let encrypted = self.encryption.encrypt(&serde_json::to_vec(&record)?)?;
+        let path = self.record_path(&record);
+        write_private_file(&path, &encrypted).await?;
+        debug!("Stored clipboard history record {}", record.id);
+
+        self.prune().await?;
+        Ok(record)
+    }
+
+    /// Load all records, oldest first
+    pub async fn list(&self) -> Result<Vec<HistoryRecord>> {
+        let mut records = Vec::new();
+
+        for path in self.record_files().await? {
+            let encrypted = tokio::fs::read(&path).await?;
+            records.push(self.decrypt_record(&encrypted)?);
+        }
+
+        records.sort_by_key(|record| record.recorded_at);
+        Ok(records)
+    }
+
+    /// Number of stored records
+    pub async fn len(&self) -> Result<usize> {
+        Ok(self.record_files().await?.len())
+    }
+
+    /// Check whether the history is empty
+    pub async fn is_empty(&self) -> Result<bool> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Re-encrypt every record under a key derived from new security settings
+    ///
+    /// A fresh salt is generated, so rekeying also rotates the store key when
+    /// the shared secret itself is unchanged.
+    pub async fn rekey(&mut self, security: &SecurityConfig) -> Result<()> {
+        info!("Re-keying clipboard history store");
+
+        let encryption = self.stage_rekey(security).await?;
+
+        // Once the old store is moved aside, `open` completes the swap after a crash
+        let backup = sibling_path(&self.directory, BACKUP_SUFFIX);
+        tokio::fs::rename(&self.directory, &backup).await?;
+        tokio::fs::rename(sibling_path(&self.directory, STAGING_SUFFIX), &self.directory).await?;
+        self.encryption = encryption;
+
+        wipe_single_directory(&backup).await?;
+
+        info!("Clipboard history re-keyed");
+        Ok(())
+    }
+
+    /// Write every record re-encrypted under a new key into the staging directory
+    async fn stage_rekey(&self, security: &SecurityConfig) -> Result<EncryptionService> {
+        let staging = sibling_path(&self.directory, STAGING_SUFFIX);
+
+        // Drop leftovers of an earlier re-key that failed before the swap
+        wipe_single_directory(&staging).await?;
+        create_private_dir(&staging).await?;
+
+        let (encryption, key_file) = Self::new_key(security)?;
+
+        for path in self.record_files().await? {
+            let Some(file_name) = path.file_name() else {
+                continue;
+            };
+
+            let record = self.decrypt_record(&tokio::fs::read(&path).await?)?;
+            let encrypted = encryption.encrypt(&serde_json::to_vec(&record)?)?;
+            write_private_file(&staging.join(file_name), &encrypted).await?;
+        }
+
+        // The key file goes in last: a staging directory with a key file is complete
+        write_private_file(&staging.join(KEY_FILE_NAME), &serde_json::to_vec(&key_file)?).await?;
+
+        Ok(encryption)
+    }
+
+    /// Finish or roll back a re-key interrupted by a crash
+    async fn recover_rekey(directory: &Path) -> Result<()> {
+        let staging = sibling_path(directory, STAGING_SUFFIX);
+        let backup = sibling_path(directory, BACKUP_SUFFIX);
+
+        if !tokio::fs::try_exists(directory).await?
+            && tokio::fs::try_exists(&backup).await?
+            && tokio::fs::try_exists(staging.join(KEY_FILE_NAME)).await?
+        {
+            warn!("Completing interrupted re-key of clipboard history in {}", directory.display());
+            tokio::fs::rename(&staging, directory).await?;
+        }
+
+        // Whatever is left is either an incomplete staging directory, while the
+        // old store is still in place, or the old store after a finished swap
+        wipe_single_directory(&staging).await?;
+        wipe_single_directory(&backup).await?;
+
+        Ok(())
+    }
+
+    /// Overwrite and delete every record, the key file and the store directory
+    pub async fn secure_wipe(self) -> Result<()> {
+        Self::wipe_directory(&self.directory).await
+    }
+
+    /// Securely wipe a history directory without opening it
+    ///
+    /// This works even when the secret used to create the store is lost.
+    /// Copies left behind by an interrupted re-key are wiped as well.
+    pub async fn wipe_directory(directory: &Path) -> Result<()> {
+        wipe_single_directory(directory).await?;
+        wipe_single_directory(&sibling_path(directory, STAGING_SUFFIX)).await?;
+        wipe_single_directory(&sibling_path(directory, BACKUP_SUFFIX)).await
+    }
+
+    /// Get the history directory
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn new_key(security: &SecurityConfig) -> Result<(EncryptionService, KeyFile)> {
+        let salt = EncryptionService::generate_salt();
+        let encryption = EncryptionService::from_config_with_salt(security, &salt, KDF_ITERATIONS)?;
+        let verifier = encryption.encrypt(KEY_VERIFIER)?;
+
+        let key_file = KeyFile {
+            salt: salt.to_vec(),
+            iterations: KDF_ITERATIONS,
+            verifier,
+        };
+
+        Ok((encryption, key_file))
+    }
+
+    fn decrypt_record(&self, encrypted: &[u8]) -> Result<HistoryRecord> {
+        let plaintext = self.encryption.decrypt(encrypted)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn record_path(&self, record: &HistoryRecord) -> PathBuf {
+        self.directory
+            .join(format!("{:016}-{}.{}", record.recorded_at, record.id, RECORD_EXTENSION))
+    }
+
+    /// Record file paths, oldest first
+    async fn record_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.directory).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(RECORD_EXTENSION) {
+                files.push(path);
+            }
+        }
+
+        // File names start with a zero-padded timestamp
+        files.sort();
+        Ok(files)
+    }
+
+    async fn prune(&self) -> Result<()> {
+        let files = self.record_files().await?;
+        if files.len() <= self.max_items {
+            return Ok(());
+        }
+
+        let excess = files.len() - self.max_items;
+        for path in files.iter().take(excess) {
+            overwrite_and_remove(path).await?;
+        }
+
+        debug!("Pruned {} clipboard history records", excess);
+        Ok(())
+    }
+}
+
+/// Overwrite and delete every file in a directory, then remove it
+async fn wipe_single_directory(directory: &Path) -> Result<()> {
+    if !tokio::fs::try_exists(directory).await? {
+        return Ok(());
+    }
+
+    info!("Securely wiping clipboard history in {}", directory.display());
+
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_file() {
+            overwrite_and_remove(&path).await?;
+        } else {
+            warn!("Leaving unexpected entry in history directory: {}", path.display());
+        }
+    }
+
+    if let Err(e) = tokio::fs::remove_dir(directory).await {
+        warn!("Failed to remove history directory {}: {}", directory.display(), e);
+    }
+
+    Ok(())
+}
+
+/// Path next to `directory` with `suffix` appended to its name
+fn sibling_path(directory: &Path, suffix: &str) -> PathBuf {
+    let mut name = directory.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", suffix));
+    directory.with_file_name(name)
+}
+
+/// Create a directory readable only by the current user
+async fn create_private_dir(path: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(path).await?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700)).await?;
+    }
+
+    Ok(())
+}
+
+/// Write a file readable only by the current user and flush it to disk
+async fn write_private_file(path: &Path, data: &[u8]) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut file = tokio::fs::File::create(path).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+
+    Ok(())
+}
+
+/// Overwrite a file with random bytes before deleting it
+async fn overwrite_and_remove(path: &Path) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let len = tokio::fs::metadata(path).await?.len() as usize;
+    let mut noise = vec![0u8; len];
+    thread_rng().fill_bytes(&mut noise);
+
+    let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.write_all(&noise).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn security(secret: &str) -> SecurityConfig {
+        SecurityConfig {
+            secret_key: secret.to_string(),
+            ..SecurityConfig::default()
+        }
+    }
+
+    fn text(value: &str) -> ClipboardContent {
+        ClipboardContent::new_text(value.to_string(), "test-device".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_record_and_list() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("secret")).await.unwrap();
+
+        history.record(&text("first")).await.unwrap();
+        history.record(&text("second")).await.unwrap();
+
+        let records = history.list().await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].content.as_text(), Some("first".to_string()));
+        assert_eq!(records[1].content.as_text(), Some("second".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_records_are_not_plaintext() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("secret")).await.unwrap();
+
+        history.record(&text("hunter2-password")).await.unwrap();
+
+        for entry in std::fs::read_dir(&directory).unwrap() {
+            let data = std::fs::read(entry.unwrap().path()).unwrap();
+            let haystack = String::from_utf8_lossy(&data);
+            assert!(!haystack.contains("hunter2-password"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_with_wrong_secret_fails() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("secret")).await.unwrap();
+        history.record(&text("value")).await.unwrap();
+
+        let result = ClipboardHistory::open(&directory, 10, &security("other-secret")).await;
+        assert!(matches!(result, Err(ClipboardError::HistoryFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rekey() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let mut history = ClipboardHistory::open(&directory, 10, &security("old-secret")).await.unwrap();
+        history.record(&text("value")).await.unwrap();
+
+        history.rekey(&security("new-secret")).await.unwrap();
+        drop(history);
+
+        assert!(ClipboardHistory::open(&directory, 10, &security("old-secret")).await.is_err());
+
+        let history = ClipboardHistory::open(&directory, 10, &security("new-secret")).await.unwrap();
+        let records = history.list().await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].content.as_text(), Some("value".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_rekey_rolls_back_before_swap() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("old-secret")).await.unwrap();
+        history.record(&text("value")).await.unwrap();
+
+        // Crash after staging but before the old store was moved aside
+        history.stage_rekey(&security("new-secret")).await.unwrap();
+        drop(history);
+
+        let history = ClipboardHistory::open(&directory, 10, &security("old-secret")).await.unwrap();
+        assert_eq!(history.list().await.unwrap().len(), 1);
+        assert!(!sibling_path(&directory, STAGING_SUFFIX).exists());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_rekey_completes_after_swap_started() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("old-secret")).await.unwrap();
+        history.record(&text("value")).await.unwrap();
+
+        // Crash between the two renames of the swap
+        history.stage_rekey(&security("new-secret")).await.unwrap();
+        std::fs::rename(&directory, sibling_path(&directory, BACKUP_SUFFIX)).unwrap();
+        drop(history);
+
+        let history = ClipboardHistory::open(&directory, 10, &security("new-secret")).await.unwrap();
+        let records = history.list().await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].content.as_text(), Some("value".to_string()));
+        assert!(!sibling_path(&directory, BACKUP_SUFFIX).exists());
+        assert!(!sibling_path(&directory, STAGING_SUFFIX).exists());
+    }
+
+    #[tokio::test]
+    async fn test_prune_to_max_items() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 2, &security("secret")).await.unwrap();
+
+        for value in ["one", "two", "three"] {
+            history.record(&text(value)).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+
+        let records = history.list().await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].content.as_text(), Some("two".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_secure_wipe() {
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path().join("history");
+        let history = ClipboardHistory::open(&directory, 10, &security("secret")).await.unwrap();
+        history.record(&text("value")).await.unwrap();
+
+        history.secure_wipe().await.unwrap();
+        assert!(!directory.exists());
+    }
+}
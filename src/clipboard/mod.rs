@@ -3,11 +3,31 @@
 //! This module provides cross-platform clipboard access and monitoring capabilities.
 //! It detects clipboard changes and manages clipboard content synchronization.
 
+pub mod backend;
+pub mod compression;
 pub mod content;
+pub mod exclusion;
+pub mod filter;
+pub mod history;
 pub mod monitor;
+pub mod policy;
+pub mod sniff;
+pub mod store;
+pub mod transform;
+pub mod watcher;
 
-pub use content::{ClipboardContent, ContentType};
+pub use backend::{ArboardBackend, ClipboardBackend, MemoryClipboard};
+pub use compression::CompressionCodec;
+pub use content::{ClipboardContent, ContentAnnouncement, ContentMetadata, ContentType, Selection};
+pub use exclusion::{ExclusionList, WindowLookup};
+pub use filter::{FilterDecision, FilterPipeline};
+pub use history::{ClipboardHistory, HistoryRecord};
 pub use monitor::ClipboardMonitor;
+pub use policy::SyncPolicies;
+pub use sniff::ContentKind;
+pub use store::ContentStore;
+pub use transform::{ContentTransform, TransformPipeline};
+pub use watcher::{ChangeSignal, ChangeWatcher};
 
 use thiserror::Error;
 
@@ -23,6 +43,24 @@ pub enum ClipboardError {
     #[error("Content too large: {size} bytes (max: {max_size} bytes)")]
     ContentTooLarge { size: usize, max_size: usize },
 
+    #[error("Content does not match checksum {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Invalid content filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Blocked by sync policy: {0}")]
+    PolicyDenied(String),
+
+    #[error("Compression error: {0}")]
+    Compression(String),
+
+    #[error("History store error: {0}")]
+    HistoryFailed(String),
+
+    #[error("Cryptography error: {0}")]
+    Crypto(#[from] crate::crypto::CryptoError),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
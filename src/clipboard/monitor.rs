@@ -1,81 +1,257 @@
 //! Clipboard monitoring implementation
 
-use crate::clipboard::{ClipboardContent, Result};
-use crate::config::ClipboardConfig;
-use crate::events::{Event, EventBus};
-use arboard::Clipboard;
+use crate::clipboard::backend::{ArboardBackend, ClipboardBackend, MIME_IMAGE, MIME_TEXT};
+use crate::clipboard::{
+    ClipboardContent, ClipboardError, ContentType, ExclusionList, FilterDecision, FilterPipeline, Result, Selection,
+    SyncPolicies, TransformPipeline,
+};
+use crate::config::{ClipboardConfig, SelectionTarget};
+use crate::events::{Event, EventBus, EventWithMetadata, HopStage, TraceContext};
+use crate::network::ClipboardOrdering;
 use log::{debug, error, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tokio::time::interval;
 
 /// Clipboard monitor that watches for clipboard changes
 pub struct ClipboardMonitor {
-    clipboard: Arc<RwLock<Clipboard>>,
+    clipboard: Arc<SelectionChannel>,
+    primary: Option<Arc<SelectionChannel>>,
     config: ClipboardConfig,
+    stages: SendStages,
+    ordering: Option<Arc<ClipboardOrdering>>,
     event_bus: Arc<EventBus>,
-    last_content_hash: Arc<RwLock<Option<String>>>,
-    last_update: Arc<RwLock<Instant>>,
     running: Arc<RwLock<bool>>,
     device_system: String,
+    /// Device name recorded in the traces of local copies
+    device_name: String,
+}
+
+/// Checks and transformations a local item passes before it is reported
+#[derive(Clone)]
+struct SendStages {
+    exclusions: Arc<ExclusionList>,
+    filters: Arc<FilterPipeline>,
+    transforms: Arc<TransformPipeline>,
+    policies: Arc<SyncPolicies>,
+    concealed: Arc<ConcealedCheck>,
+}
+
+/// Handling of items whose concealed-content hints cannot be read
+struct ConcealedCheck {
+    /// Treat such items as concealed
+    skip_unverified: bool,
+    /// Whether the hints being unreadable has been reported
+    warned: AtomicBool,
+}
+
+/// Monitoring state of a single system selection
+struct SelectionChannel {
+    selection: Selection,
+    backend: Arc<dyn ClipboardBackend>,
+    cooldown: Duration,
+    last_content_hash: RwLock<Option<String>>,
+    last_change_count: RwLock<Option<u64>>,
+    last_update: RwLock<Instant>,
+}
+
+impl SelectionChannel {
+    fn new(selection: Selection, backend: Arc<dyn ClipboardBackend>, cooldown: Duration) -> Self {
+        Self {
+            selection,
+            backend,
+            cooldown,
+            last_content_hash: RwLock::new(None),
+            last_change_count: RwLock::new(None),
+            last_update: RwLock::new(Instant::now()),
+        }
+    }
+
+    async fn set(&self, mime_type: &str, data: &[u8]) -> Result<()> {
+        self.backend.set(mime_type, data)?;
+
+        // Update last content hash to prevent echo
+        let content_hash = ClipboardMonitor::calculate_content_hash(data);
+        *self.last_content_hash.write().await = Some(content_hash);
+        *self.last_update.write().await = Instant::now();
+
+        Ok(())
+    }
 }
 
 impl ClipboardMonitor {
-    /// Create a new clipboard monitor
+    /// Create a new clipboard monitor for the system clipboard
+    ///
+    /// The PRIMARY selection is monitored as well when enabled in the config.
     pub fn new(
         config: ClipboardConfig,
         event_bus: Arc<EventBus>,
     ) -> Result<Self> {
-        let clipboard = Clipboard::new()
-            .map_err(|e| crate::clipboard::ClipboardError::AccessFailed(e.to_string()))?;
+        let open = |selection| {
+            if config.watch_changes {
+                ArboardBackend::with_change_watcher(selection)
+            } else {
+                ArboardBackend::for_selection(selection)
+            }
+        };
+
+        let backend = open(Selection::Clipboard)?;
+        let primary = config.primary.enabled.then(|| open(Selection::Primary));
 
+        let mut monitor = Self::with_backend(config, event_bus, Arc::new(backend))?;
+        match primary {
+            Some(Ok(primary)) => monitor.set_primary_backend(Arc::new(primary)),
+            Some(Err(e)) => warn!("PRIMARY selection unavailable, syncing CLIPBOARD only: {}", e),
+            None => {}
+        }
+
+        Ok(monitor)
+    }
+
+    /// Create a new clipboard monitor on top of a specific backend
+    pub fn with_backend(
+        config: ClipboardConfig,
+        event_bus: Arc<EventBus>,
+        backend: Arc<dyn ClipboardBackend>,
+    ) -> Result<Self> {
         let system_info = crate::utils::platform::get_detailed_system_info();
+        let exclusions = ExclusionList::from_config(&config.exclusions);
+        let filters = FilterPipeline::from_config(&config.filters)?;
+        let policies = SyncPolicies::from_config(&config.policies);
+        let transforms = TransformPipeline::from_config(&config.transforms);
+        let concealed = ConcealedCheck {
+            skip_unverified: config.concealed.skip_unverified,
+            warned: AtomicBool::new(false),
+        };
+
+        debug!("Using {} clipboard backend", backend.name());
 
         Ok(Self {
-            clipboard: Arc::new(RwLock::new(clipboard)),
+            clipboard: Arc::new(SelectionChannel::new(Selection::Clipboard, backend, config.cooldown_duration())),
+            primary: None,
             config,
+            stages: SendStages {
+                exclusions: Arc::new(exclusions),
+                filters: Arc::new(filters),
+                transforms: Arc::new(transforms),
+                policies: Arc::new(policies),
+                concealed: Arc::new(concealed),
+            },
+            ordering: None,
             event_bus,
-            last_content_hash: Arc::new(RwLock::new(None)),
-            last_update: Arc::new(RwLock::new(Instant::now())),
             running: Arc::new(RwLock::new(false)),
             device_system: system_info.device_system,
+            device_name: system_info.device_name,
         })
     }
 
+    /// Monitor the PRIMARY selection through `backend`, with its own cooldown
+    pub fn set_primary_backend(&mut self, backend: Arc<dyn ClipboardBackend>) {
+        debug!("Using {} backend for the PRIMARY selection", backend.name());
+        self.primary = Some(Arc::new(SelectionChannel::new(
+            Selection::Primary,
+            backend,
+            self.config.primary.cooldown_duration(),
+        )));
+    }
+
+    /// Stamp local copies when they are picked up and skip received items
+    ///
+    /// Without an ordering every changed item is reported, unstamped.
+    pub fn set_ordering(&mut self, ordering: Arc<ClipboardOrdering>) {
+        self.ordering = Some(ordering);
+    }
+
+    /// Name this device under in the traces of local copies, instead of the host name
+    pub fn set_device_name(&mut self, device_name: impl Into<String>) {
+        self.device_name = device_name.into();
+    }
+
+    /// Check copies against `exclusions` instead of the configured list
+    pub fn set_exclusions(&mut self, exclusions: ExclusionList) {
+        self.stages.exclusions = Arc::new(exclusions);
+    }
+
+    /// Transform local items with `transforms` instead of the configured pipeline
+    pub fn set_transforms(&mut self, transforms: Arc<TransformPipeline>) {
+        self.stages.transforms = transforms;
+    }
+
     /// Start monitoring clipboard changes
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting clipboard monitor");
         *self.running.write().await = true;
 
-        let clipboard = self.clipboard.clone();
+        self.spawn_channel(self.clipboard.clone());
+        if let Some(primary) = &self.primary {
+            self.spawn_channel(primary.clone());
+        }
+
+        Ok(())
+    }
+
+    fn spawn_channel(&self, channel: Arc<SelectionChannel>) {
         let config = self.config.clone();
+        let stages = self.stages.clone();
+        let ordering = self.ordering.clone();
         let event_bus = self.event_bus.clone();
-        let last_content_hash = self.last_content_hash.clone();
-        let last_update = self.last_update.clone();
         let running = self.running.clone();
         let device_system = self.device_system.clone();
+        let device_name = self.device_name.clone();
+
+        let signal = channel.backend.change_signal();
+        match &signal {
+            Some(_) => info!("Watching {} via {} for change notifications", channel.selection, channel.backend.name()),
+            None => debug!(
+                "Polling {} via {} every {:?} to {:?}",
+                channel.selection,
+                channel.backend.name(),
+                config.poll_interval(),
+                config.max_poll_interval()
+            ),
+        }
 
         tokio::spawn(async move {
-            let mut interval = interval(Duration::from_millis(100));
+            let mut poll_interval = config.poll_interval();
 
             while *running.read().await {
-                interval.tick().await;
+                match signal.as_ref().filter(|signal| signal.is_active()) {
+                    // Backends with notifications are still checked at the idle
+                    // interval in case a notification is missed
+                    Some(signal) => {
+                        tokio::select! {
+                            _ = signal.changed() => {}
+                            _ = tokio::time::sleep(config.max_poll_interval()) => {}
+                        }
+                    }
+                    None => tokio::time::sleep(poll_interval).await,
+                }
+
+                if !*running.read().await {
+                    break;
+                }
 
-                if let Err(e) = Self::check_clipboard_change(
-                    &clipboard,
+                let result = Self::check_clipboard_change(
+                    &channel,
                     &config,
+                    &stages,
+                    ordering.as_deref(),
                     &event_bus,
-                    &last_content_hash,
-                    &last_update,
                     &device_system,
-                ).await {
-                    error!("Error checking clipboard: {}", e);
-                }
+                    &device_name,
+                ).await;
+
+                let changed = match result {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        error!("Error checking {}: {}", channel.selection, e);
+                        false
+                    }
+                };
+                poll_interval = Self::next_poll_interval(poll_interval, changed, &config);
             }
         });
-
-        Ok(())
     }
 
     /// Stop monitoring clipboard changes
@@ -85,128 +261,346 @@ impl ClipboardMonitor {
         Ok(())
     }
 
-    /// Update clipboard with new content
+    /// Update clipboard with new text content
     pub async fn update_clipboard(&self, content: Vec<u8>) -> Result<()> {
         debug!("Updating clipboard with {} bytes", content.len());
 
-        // Convert bytes to string (assuming text content for now)
-        let text = String::from_utf8(content)
-            .map_err(|e| crate::clipboard::ClipboardError::AccessFailed(e.to_string()))?;
+        // Raw payloads are treated as text
+        String::from_utf8(content.clone())
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))?;
 
-        let mut clipboard = self.clipboard.write().await;
-        clipboard.set_text(&text)
-            .map_err(|e| crate::clipboard::ClipboardError::AccessFailed(e.to_string()))?;
+        self.clipboard.set(MIME_TEXT, &content).await
+    }
 
-        // Update last content hash to prevent echo
-        let content_hash = Self::calculate_content_hash(&text.as_bytes());
-        *self.last_content_hash.write().await = Some(content_hash);
-        *self.last_update.write().await = Instant::now();
+    /// Apply clipboard content received from a peer
+    ///
+    /// The content goes to the local selections configured for the selection
+    /// it was copied from.
+    pub async fn apply_content(&self, content: &ClipboardContent) -> Result<()> {
+        let channels = self.target_channels(content.metadata.selection);
+        if channels.is_empty() {
+            debug!("No local target for {} content, ignoring", content.metadata.selection);
+            return Ok(());
+        }
+
+        #[cfg_attr(not(feature = "compression"), allow(unused_mut))]
+        let mut content = content.clone();
+
+        #[cfg(feature = "compression")]
+        content.decompress(self.config.max_content_size)?;
+
+        #[cfg(not(feature = "compression"))]
+        if content.metadata.compression != crate::clipboard::CompressionCodec::None {
+            return Err(ClipboardError::UnsupportedContentType);
+        }
+
+        let mime_type = match (&content.content_type, &content.metadata.mime_type) {
+            (ContentType::Text, _) => MIME_TEXT.to_string(),
+            (_, Some(mime_type)) => mime_type.clone(),
+            (ContentType::Image, None) => MIME_IMAGE.to_string(),
+            _ => return Err(ClipboardError::UnsupportedContentType),
+        };
+
+        for channel in channels {
+            debug!("Applying {} content to {} ({} bytes)", mime_type, channel.selection, content.data.len());
+            channel.set(&mime_type, &content.data).await?;
+
+            if let Some(expires_in) = content.expires_in() {
+                Self::spawn_expiry(
+                    channel.backend.clone(),
+                    mime_type.clone(),
+                    Self::calculate_content_hash(&content.data),
+                    expires_in,
+                );
+            }
+        }
 
         Ok(())
     }
 
+    /// Local selections that content copied from `selection` is applied to
+    fn target_channels(&self, selection: Selection) -> Vec<&Arc<SelectionChannel>> {
+        let target = match selection {
+            Selection::Clipboard => self.config.primary.clipboard_target,
+            Selection::Primary => self.config.primary.primary_target,
+        };
+
+        let to_clipboard = matches!(target, SelectionTarget::Clipboard | SelectionTarget::Both);
+        let to_primary = matches!(target, SelectionTarget::Primary | SelectionTarget::Both);
+
+        let mut channels = Vec::new();
+        if to_clipboard {
+            channels.push(&self.clipboard);
+        }
+        if let Some(primary) = self.primary.as_ref().filter(|_| to_primary) {
+            channels.push(primary);
+        }
+        channels
+    }
+
+    /// Clear the clipboard after `expires_in` if it still holds `data`
+    pub fn schedule_expiry(&self, data: Vec<u8>, expires_in: Duration) {
+        Self::spawn_expiry(
+            self.clipboard.backend.clone(),
+            MIME_TEXT.to_string(),
+            Self::calculate_content_hash(&data),
+            expires_in,
+        );
+    }
+
+    /// Get the clipboard backend
+    pub fn backend(&self) -> &Arc<dyn ClipboardBackend> {
+        &self.clipboard.backend
+    }
+
+    /// Get the PRIMARY selection backend, if PRIMARY is monitored
+    pub fn primary_backend(&self) -> Option<&Arc<dyn ClipboardBackend>> {
+        self.primary.as_ref().map(|channel| &channel.backend)
+    }
+
+    fn spawn_expiry(
+        backend: Arc<dyn ClipboardBackend>,
+        mime_type: String,
+        content_hash: String,
+        expires_in: Duration,
+    ) {
+        tokio::spawn(async move {
+            tokio::time::sleep(expires_in).await;
+
+            let unchanged = backend
+                .get(&mime_type)
+                .ok()
+                .flatten()
+                .map(|data| Self::calculate_content_hash(&data) == content_hash)
+                .unwrap_or(false);
+
+            if unchanged {
+                info!("Clearing expired clipboard content");
+                if let Err(e) = backend.clear() {
+                    warn!("Failed to clear expired clipboard content: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Check a selection once, returning whether its content changed
     async fn check_clipboard_change(
-        clipboard: &Arc<RwLock<Clipboard>>,
+        channel: &SelectionChannel,
         config: &ClipboardConfig,
+        stages: &SendStages,
+        ordering: Option<&ClipboardOrdering>,
         event_bus: &Arc<EventBus>,
-        last_content_hash: &Arc<RwLock<Option<String>>>,
-        last_update: &Arc<RwLock<Instant>>,
         device_system: &str,
-    ) -> Result<()> {
+        device_name: &str,
+    ) -> Result<bool> {
+        let backend = &channel.backend;
+
+        // Wait out the cooldown period rather than skipping, so a pushed
+        // change notification is not lost
+        let elapsed = channel.last_update.read().await.elapsed();
+        if elapsed < channel.cooldown {
+            tokio::time::sleep(channel.cooldown - elapsed).await;
+        }
         let now = Instant::now();
 
-        // Check cooldown period
-        {
-            let last_update_time = *last_update.read().await;
-            if now.duration_since(last_update_time) < config.cooldown_duration() {
-                return Ok(());
+        // Hold the register while reading so a received item cannot be applied
+        // between reading a local copy and stamping it. PRIMARY is not ordered,
+        // selecting text must not supersede copies from other devices.
+        let ordering = ordering.filter(|_| channel.selection == Selection::Clipboard);
+        let mut register = match ordering {
+            Some(ordering) => Some(ordering.lock().await),
+            None => None,
+        };
+
+        // Skip reading the content when the backend reports no change
+        if let Some(change_count) = backend.change_count() {
+            let mut last_change_count = channel.last_change_count.write().await;
+            if *last_change_count == Some(change_count) {
+                return Ok(false);
             }
+            *last_change_count = Some(change_count);
         }
 
         // Try to get different types of clipboard content
-        let clipboard_content = {
-            let mut clipboard = clipboard.write().await;
-
+        let clipboard_content = if let Some(text) = backend.get(MIME_TEXT)? {
             // Try text first
-            if let Ok(text) = clipboard.get_text() {
-                Some(ClipboardContent::new_text(text, device_system.to_string()))
-            } else if config.sync_images {
-                // Try image content
-                if let Ok(image) = clipboard.get_image() {
-                    let image_data = Self::convert_image_to_bytes(image)?;
-                    Some(ClipboardContent::new_image(
-                        image_data,
-                        "image/png".to_string(),
-                        device_system.to_string(),
-                    ))
-                } else {
-                    None
-                }
+            String::from_utf8(text)
+                .ok()
+                .map(|text| ClipboardContent::new_text(text, device_system.to_string()))
+        } else if config.sync_images {
+            // Try image content
+            backend.get(MIME_IMAGE)?.map(|image_data| {
+                ClipboardContent::new_image(
+                    image_data,
+                    MIME_IMAGE.to_string(),
+                    device_system.to_string(),
+                )
+            })
+        } else {
+            None
+        };
+
+        let Some(mut content) = clipboard_content else {
+            return Ok(false);
+        };
+        content.metadata.selection = channel.selection;
+
+        // Check if content has changed by comparing content hashes
+        let current_hash = Self::calculate_content_hash(&content.data);
+        if channel.last_content_hash.read().await.as_deref() == Some(current_hash.as_str()) {
+            return Ok(false);
+        }
+
+        debug!("{} content changed: {} bytes", channel.selection, content.metadata.size);
+
+        if let (Some(ordering), Some(register)) = (ordering, register.as_mut()) {
+            // Items received from peers are already current everywhere
+            if register.current().is_some_and(|(_, checksum)| checksum == content.checksum) {
+                debug!("{} holds the current item, not reporting it", channel.selection);
+                *channel.last_content_hash.write().await = Some(current_hash);
+                return Ok(false);
+            }
+
+            let stamp = ordering.clock().now();
+            register.offer(&stamp, &content.checksum);
+            content.metadata.clock = Some(stamp);
+        }
+        drop(register);
+
+        // Remember the content even if it is filtered out so it is not re-inspected
+        *channel.last_content_hash.write().await = Some(current_hash.clone());
+        *channel.last_update.write().await = now;
+
+        // Check content size limit; an oversized item is not a change worth
+        // polling faster for
+        if content.metadata.size > config.max_content_size {
+            warn!(
+                "Clipboard content too large: {} bytes (max: {} bytes)",
+                content.metadata.size,
+                config.max_content_size
+            );
+            return Ok(false);
+        }
+
+        // Password managers mark secrets as concealed; never sync or store them
+        if Self::is_concealed(backend, &stages.concealed).await {
+            info!("Clipboard content marked as concealed, not syncing");
+            return Ok(true);
+        }
+
+        // Copies made in excluded applications such as password managers never leave the device
+        if let Some(source) = Self::excluded_source(&stages.exclusions).await {
+            info!("Clipboard content copied in excluded application '{}', not syncing", source);
+            return Ok(true);
+        }
+
+        // Run sensitive content filters before anything leaves the device
+        let final_content = match stages.filters.apply(content) {
+            FilterDecision::Pass(content) => content,
+            FilterDecision::Redacted(content) => {
+                info!("Redacted sensitive clipboard content before sync");
+                content
+            }
+            FilterDecision::Expiring { mut content, expires_in } => {
+                info!("Syncing sensitive clipboard content with {}s expiry", expires_in.as_secs());
+                let expires_at = chrono::Utc::now().timestamp_millis() as u64 + expires_in.as_millis() as u64;
+                content.metadata.expires_at = Some(expires_at);
+                Self::spawn_expiry(backend.clone(), MIME_TEXT.to_string(), current_hash, expires_in);
+                content
+            }
+            FilterDecision::Blocked { filter } => {
+                info!("Clipboard content blocked by filter '{}', not syncing", filter);
+                return Ok(true);
+            }
+        };
+
+        // Clean up text before it is checksummed and compressed for sending
+        #[cfg_attr(not(feature = "compression"), allow(unused_mut))]
+        let mut final_content = stages.transforms.apply_on_send(final_content);
+
+        // Sync policies for this kind of content may keep it on the device
+        if let Err(e) = stages.policies.check_send(&final_content.content_type, &final_content.metadata) {
+            info!("Not syncing clipboard content: {}", e);
+            return Ok(true);
+        }
+
+        // Compress content if enabled and above threshold, unless a policy picks the codec
+        #[cfg(feature = "compression")]
+        if config.enable_compression && final_content.metadata.size > config.compression_threshold {
+            let codec = stages
+                .policies
+                .compression(&final_content.content_type, &final_content.metadata)
+                .unwrap_or_else(|| config.preferred_codec());
+            if let Err(e) = final_content.compress(codec) {
+                warn!("Failed to compress clipboard content: {}", e);
             } else {
-                None
+                debug!("Compressed content from {} to {} bytes with {}",
+                       final_content.metadata.size, final_content.data.len(), final_content.metadata.compression);
             }
+        }
+
+        // Emit clipboard changed event, starting the item's trace
+        let event = Event::ClipboardChanged {
+            content: final_content.clone(),
+            device_system: device_system.to_string(),
         };
+        let trace = TraceContext::start(HopStage::Copied, device_name);
+        debug!("Clipboard change {} traced as {}", final_content.checksum, trace.trace_id);
 
-        if let Some(content) = clipboard_content {
-            // Check if content has changed by comparing content hashes
-            let current_hash = Self::calculate_content_hash(&content.data);
-            let should_process = {
-                let last_hash_guard = last_content_hash.read().await;
-                match &*last_hash_guard {
-                    Some(last_hash) => last_hash != &current_hash,
-                    None => true,
-                }
-            };
-
-            if should_process {
-                debug!("Clipboard content changed: {} bytes", content.metadata.size);
-
-                // Check content size limit
-                if content.metadata.size > config.max_content_size {
-                    warn!(
-                        "Clipboard content too large: {} bytes (max: {} bytes)",
-                        content.metadata.size,
-                        config.max_content_size
-                    );
-                    return Ok(());
-                }
+        let event = EventWithMetadata::new(event, "clipboard".to_string()).with_trace(Some(trace));
+        if let Err(e) = event_bus.emit_without_waiting(event) {
+            error!("Failed to emit clipboard changed event: {}", e);
+        }
 
-                // Compress content if enabled and above threshold
-                let mut final_content = content;
-                #[cfg(feature = "compression")]
-                if config.enable_compression && final_content.metadata.size > config.compression_threshold {
-                    if let Err(e) = final_content.compress() {
-                        warn!("Failed to compress clipboard content: {}", e);
-                    } else {
-                        debug!("Compressed content from {} to {} bytes",
-                               final_content.metadata.size, final_content.data.len());
-                    }
-                }
+        Ok(true)
+    }
 
-                // Emit clipboard changed event
-                let event = Event::ClipboardChanged {
-                    content: final_content.clone(),
-                    device_system: device_system.to_string(),
-                };
+    /// Next polling interval: reset while the clipboard is busy, back off while idle
+    fn next_poll_interval(current: Duration, changed: bool, config: &ClipboardConfig) -> Duration {
+        if changed {
+            config.poll_interval()
+        } else {
+            (current * 2).min(config.max_poll_interval())
+        }
+    }
 
-                if let Err(e) = event_bus.emit(event).await {
-                    error!("Failed to emit clipboard changed event: {}", e);
-                }
+    /// Check the backend's concealed-content markers for the current clipboard
+    ///
+    /// Where the markers cannot be read the item counts as concealed only if
+    /// configured so; either way this is reported once.
+    async fn is_concealed(backend: &Arc<dyn ClipboardBackend>, check: &ConcealedCheck) -> bool {
+        let backend = backend.clone();
+        let error = match tokio::task::spawn_blocking(move || backend.is_concealed()).await {
+            Ok(Ok(concealed)) => return concealed,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
 
-                // Update last content hash and timestamp
-                *last_content_hash.write().await = Some(current_hash);
-                *last_update.write().await = now;
+        if !check.warned.swap(true, Ordering::Relaxed) {
+            if check.skip_unverified {
+                warn!("Cannot read concealed clipboard hints, not syncing items that cannot be checked: {}", error);
+            } else {
+                warn!("Cannot read concealed clipboard hints, password manager secrets may be synced: {}", error);
             }
         }
 
-        Ok(())
+        check.skip_unverified
     }
 
-    /// Convert arboard image to bytes
-    fn convert_image_to_bytes(image: arboard::ImageData) -> Result<Vec<u8>> {
-        // This is a simplified conversion - in a real implementation,
-        // you would properly encode the image data to PNG or another format
-        Ok(image.bytes.into_owned())
+    /// Look up whether an excluded application owns the focused window
+    async fn excluded_source(exclusions: &Arc<ExclusionList>) -> Option<String> {
+        if exclusions.is_empty() {
+            return None;
+        }
+
+        let exclusions = exclusions.clone();
+        match tokio::task::spawn_blocking(move || exclusions.excluded_source()).await {
+            Ok(source) => source,
+            Err(e) => {
+                warn!("Active window lookup failed: {}", e);
+                None
+            }
+        }
     }
 
     /// Calculate a hash of content data for comparison
@@ -223,10 +617,31 @@ impl ClipboardMonitor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clipboard::MemoryClipboard;
     use crate::config::ClipboardConfig;
     use crate::events::EventBus;
 
+    fn test_config() -> ClipboardConfig {
+        ClipboardConfig {
+            cooldown_millis: 0,
+            ..ClipboardConfig::default()
+        }
+    }
+
+    async fn next_change(event_bus: &EventBus) -> Option<ClipboardContent> {
+        for _ in 0..20 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            while let Some(event) = event_bus.poll_event().await {
+                if let Event::ClipboardChanged { content, .. } = event {
+                    return Some(content);
+                }
+            }
+        }
+        None
+    }
+
     #[tokio::test]
+    #[ignore = "requires a display server"]
     async fn test_clipboard_monitor_creation() {
         let config = ClipboardConfig::default();
         let event_bus = Arc::new(EventBus::new());
@@ -234,4 +649,265 @@ mod tests {
         let monitor = ClipboardMonitor::new(config, event_bus);
         assert!(monitor.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_monitor_emits_change_from_backend() {
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(test_config(), event_bus.clone(), clipboard.clone()).unwrap();
+        monitor.start().await.unwrap();
+
+        clipboard.set_text("hello from the mock");
+        let content = next_change(&event_bus).await.expect("no change emitted");
+        assert_eq!(content.as_text().unwrap(), "hello from the mock");
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pushed_change_skips_polling_interval() {
+        let config = ClipboardConfig {
+            poll_interval_millis: 60_000,
+            max_poll_interval_millis: 60_000,
+            ..test_config()
+        };
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(config, event_bus.clone(), clipboard.clone()).unwrap();
+        monitor.start().await.unwrap();
+
+        clipboard.set_text("pushed");
+        let content = next_change(&event_bus).await.expect("pushed change not picked up");
+        assert_eq!(content.as_text().unwrap(), "pushed");
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[test]
+    fn test_adaptive_poll_interval() {
+        let config = ClipboardConfig {
+            poll_interval_millis: 100,
+            max_poll_interval_millis: 350,
+            ..ClipboardConfig::default()
+        };
+
+        let mut interval = config.poll_interval();
+        interval = ClipboardMonitor::next_poll_interval(interval, false, &config);
+        assert_eq!(interval, Duration::from_millis(200));
+        interval = ClipboardMonitor::next_poll_interval(interval, false, &config);
+        assert_eq!(interval, Duration::from_millis(350));
+        interval = ClipboardMonitor::next_poll_interval(interval, false, &config);
+        assert_eq!(interval, Duration::from_millis(350));
+
+        interval = ClipboardMonitor::next_poll_interval(interval, true, &config);
+        assert_eq!(interval, Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_copies_from_excluded_applications_are_not_emitted() {
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(test_config(), event_bus.clone(), clipboard.clone()).unwrap();
+
+        let focused = Arc::new(std::sync::Mutex::new("secrets-tool"));
+        let lookup_focused = focused.clone();
+        let config = crate::config::ExclusionConfig {
+            enabled: true,
+            processes: vec!["secrets-tool".to_string()],
+            window_classes: Vec::new(),
+        };
+        monitor.set_exclusions(ExclusionList::with_lookup(
+            &config,
+            Arc::new(move || {
+                Ok(crate::utils::platform::ActiveWindow {
+                    pid: Some(1),
+                    process_name: Some(lookup_focused.lock().unwrap().to_string()),
+                    window_class: None,
+                })
+            }),
+        ));
+        monitor.start().await.unwrap();
+
+        clipboard.set_text("hunter2");
+        assert!(next_change(&event_bus).await.is_none());
+
+        *focused.lock().unwrap() = "editor";
+        clipboard.set_text("hello");
+        let content = next_change(&event_bus).await.expect("copy from editor not emitted");
+        assert_eq!(content.as_text().unwrap(), "hello");
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_applied_content_is_not_echoed() {
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(test_config(), event_bus.clone(), clipboard.clone()).unwrap();
+        monitor.start().await.unwrap();
+
+        let remote = ClipboardContent::new_text("from a peer".to_string(), "remote".to_string());
+        monitor.apply_content(&remote).await.unwrap();
+
+        assert_eq!(clipboard.text().as_deref(), Some("from a peer"));
+        assert!(next_change(&event_bus).await.is_none());
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_primary_selection_is_a_separate_channel() {
+        let mut config = test_config();
+        config.primary.enabled = true;
+        config.primary.cooldown_millis = 0;
+
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let primary = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(config, event_bus.clone(), clipboard.clone()).unwrap();
+        monitor.set_primary_backend(primary.clone());
+        monitor.start().await.unwrap();
+
+        primary.set_text("selected text");
+        let content = next_change(&event_bus).await.expect("PRIMARY change not emitted");
+        assert_eq!(content.metadata.selection, Selection::Primary);
+        assert_eq!(clipboard.text(), None);
+
+        clipboard.set_text("copied text");
+        let content = next_change(&event_bus).await.expect("CLIPBOARD change not emitted");
+        assert_eq!(content.metadata.selection, Selection::Clipboard);
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_received_content_targets_selections() {
+        let mut config = test_config();
+        config.primary.clipboard_target = SelectionTarget::Both;
+        config.primary.primary_target = SelectionTarget::Primary;
+
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let primary = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(config, event_bus, clipboard.clone()).unwrap();
+
+        // Without a PRIMARY channel, PRIMARY-only items have nowhere to go
+        let mut selected = ClipboardContent::new_text("selected".to_string(), "remote".to_string());
+        selected.metadata.selection = Selection::Primary;
+        monitor.apply_content(&selected).await.unwrap();
+        assert_eq!(clipboard.text(), None);
+
+        monitor.set_primary_backend(primary.clone());
+        monitor.apply_content(&selected).await.unwrap();
+        assert_eq!(primary.text().as_deref(), Some("selected"));
+        assert_eq!(clipboard.text(), None);
+
+        let copied = ClipboardContent::new_text("copied".to_string(), "remote".to_string());
+        monitor.apply_content(&copied).await.unwrap();
+        assert_eq!(clipboard.text().as_deref(), Some("copied"));
+        assert_eq!(primary.text().as_deref(), Some("copied"));
+    }
+
+    #[tokio::test]
+    async fn test_concealed_content_is_skipped() {
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let mut monitor = ClipboardMonitor::with_backend(test_config(), event_bus.clone(), clipboard.clone()).unwrap();
+        monitor.start().await.unwrap();
+
+        clipboard.set_formats(&[
+            (MIME_TEXT, b"hunter2"),
+            (crate::utils::platform::KDE_PASSWORD_MANAGER_HINT, b"secret"),
+        ]);
+        assert!(next_change(&event_bus).await.is_none());
+
+        monitor.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_content_is_remembered() {
+        let config = ClipboardConfig {
+            max_content_size: 4,
+            ..test_config()
+        };
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let monitor = ClipboardMonitor::with_backend(config, event_bus.clone(), clipboard.clone()).unwrap();
+
+        clipboard.set(MIME_TEXT, b"too large").unwrap();
+        let changed = ClipboardMonitor::check_clipboard_change(
+            &monitor.clipboard,
+            &monitor.config,
+            &monitor.stages,
+            None,
+            &event_bus,
+            "test-device",
+            "test-device",
+        ).await.unwrap();
+
+        // Not reported as a change, and not inspected again on the next tick
+        assert!(!changed);
+        assert!(monitor.clipboard.last_content_hash.read().await.is_some());
+        assert!(event_bus.poll_event().await.is_none());
+    }
+
+    /// Memory clipboard whose concealed-content hints cannot be read
+    struct UnreadableHints(MemoryClipboard);
+
+    impl ClipboardBackend for UnreadableHints {
+        fn name(&self) -> &str {
+            "unreadable-hints"
+        }
+
+        fn formats(&self) -> Result<Vec<String>> {
+            self.0.formats()
+        }
+
+        fn get(&self, mime_type: &str) -> Result<Option<Vec<u8>>> {
+            self.0.get(mime_type)
+        }
+
+        fn set(&self, mime_type: &str, data: &[u8]) -> Result<()> {
+            self.0.set(mime_type, data)
+        }
+
+        fn clear(&self) -> Result<()> {
+            self.0.clear()
+        }
+
+        fn is_concealed(&self) -> Result<bool> {
+            Err(ClipboardError::AccessFailed("xclip not found".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unverified_concealed_content() {
+        for skip_unverified in [false, true] {
+            let mut config = test_config();
+            config.concealed.skip_unverified = skip_unverified;
+
+            let event_bus = Arc::new(EventBus::new());
+            let clipboard = Arc::new(UnreadableHints(MemoryClipboard::new()));
+            let mut monitor = ClipboardMonitor::with_backend(config, event_bus.clone(), clipboard.clone()).unwrap();
+            monitor.start().await.unwrap();
+
+            clipboard.set(MIME_TEXT, b"hunter2").unwrap();
+            assert_eq!(next_change(&event_bus).await.is_some(), !skip_unverified);
+
+            monitor.stop().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_content_is_cleared() {
+        let event_bus = Arc::new(EventBus::new());
+        let clipboard = Arc::new(MemoryClipboard::new());
+        let monitor = ClipboardMonitor::with_backend(test_config(), event_bus, clipboard.clone()).unwrap();
+
+        monitor.update_clipboard(b"short lived".to_vec()).await.unwrap();
+        monitor.schedule_expiry(b"short lived".to_vec(), Duration::from_millis(50));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(clipboard.text(), None);
+    }
 }
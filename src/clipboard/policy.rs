@@ -0,0 +1,283 @@
+//! Per-content-type sync policies
+//!
+//! [`SyncPolicies`] picks the first configured policy matching an item and
+//! decides whether it may be sent, whether a received item may be applied,
+//! which peers it is exchanged with and how it is compressed.
+
+use crate::clipboard::{ClipboardError, CompressionCodec, ContentMetadata, ContentType, Result};
+use crate::config::{PolicyConfig, SyncPolicy};
+use std::collections::HashMap;
+
+/// Ordered set of sync policies
+#[derive(Debug, Clone, Default)]
+pub struct SyncPolicies {
+    rules: Vec<SyncPolicy>,
+    groups: HashMap<String, Vec<String>>,
+    devices: HashMap<String, String>,
+}
+
+impl SyncPolicies {
+    /// Build the policies from configuration
+    pub fn from_config(config: &PolicyConfig) -> Self {
+        Self {
+            rules: config.rules.clone(),
+            groups: config.groups.clone(),
+            devices: config.devices.clone(),
+        }
+    }
+
+    /// First policy matching an item, if any
+    pub fn policy_for(&self, content_type: &ContentType, metadata: &ContentMetadata) -> Option<&SyncPolicy> {
+        self.rules.iter().find(|policy| policy_matches(policy, content_type, metadata))
+    }
+
+    /// Check whether a local item may be sent
+    pub fn check_send(&self, content_type: &ContentType, metadata: &ContentMetadata) -> Result<()> {
+        let Some(policy) = self.policy_for(content_type, metadata) else {
+            return Ok(());
+        };
+
+        check_common(policy, metadata)?;
+        if !policy.direction.sends() {
+            return Err(denied(policy, "is receive-only"));
+        }
+        Ok(())
+    }
+
+    /// Check whether an item received from a peer may be applied
+    pub fn check_receive(&self, content_type: &ContentType, metadata: &ContentMetadata, peer_id: &str) -> Result<()> {
+        let Some(policy) = self.policy_for(content_type, metadata) else {
+            return Ok(());
+        };
+
+        check_common(policy, metadata)?;
+        if !policy.direction.receives() {
+            return Err(denied(policy, "is send-only"));
+        }
+        if !self.targets(policy, peer_id) {
+            return Err(denied(policy, &format!("does not include peer {}", peer_id)));
+        }
+        Ok(())
+    }
+
+    /// Whether an item is limited to specific devices rather than broadcast
+    pub fn is_targeted(&self, content_type: &ContentType, metadata: &ContentMetadata) -> bool {
+        self.policy_for(content_type, metadata)
+            .is_some_and(|policy| !policy.targets.is_empty())
+    }
+
+    /// Whether an item may be exchanged with a peer
+    pub fn allows_peer(&self, content_type: &ContentType, metadata: &ContentMetadata, peer_id: &str) -> bool {
+        self.policy_for(content_type, metadata)
+            .map_or(true, |policy| self.targets(policy, peer_id))
+    }
+
+    /// Codec overriding the negotiated one for an item
+    pub fn compression(&self, content_type: &ContentType, metadata: &ContentMetadata) -> Option<CompressionCodec> {
+        self.policy_for(content_type, metadata)
+            .and_then(|policy| policy.compression)
+    }
+
+    /// Whether a policy targets a peer
+    ///
+    /// Device names only match through the peer IDs they are bound to, never
+    /// through the name a peer reports about itself.
+    fn targets(&self, policy: &SyncPolicy, peer_id: &str) -> bool {
+        if policy.targets.is_empty() {
+            return true;
+        }
+
+        let is_device = |name: &str| name == peer_id || self.devices.get(name).is_some_and(|bound| bound == peer_id);
+        policy.targets.iter().any(|target| {
+            is_device(target)
+                || self
+                    .groups
+                    .get(target)
+                    .is_some_and(|members| members.iter().any(|member| is_device(member)))
+        })
+    }
+}
+
+fn policy_matches(policy: &SyncPolicy, content_type: &ContentType, metadata: &ContentMetadata) -> bool {
+    policy.content_type.as_ref().map_or(true, |expected| expected == content_type)
+        && policy.kind.map_or(true, |kind| kind == metadata.kind)
+        && policy.mime.as_deref().map_or(true, |pattern| {
+            metadata
+                .mime_type
+                .as_deref()
+                .is_some_and(|mime| mime_matches(pattern, mime))
+        })
+}
+
+/// Match a MIME type against a pattern such as `image/png`, `image/*` or `*`
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let mime = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    if pattern == "*" || pattern == "*/*" {
+        return true;
+    }
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime.split('/').next() == Some(prefix),
+        None => pattern == mime,
+    }
+}
+
+fn check_common(policy: &SyncPolicy, metadata: &ContentMetadata) -> Result<()> {
+    if !policy.enabled {
+        return Err(denied(policy, "disables sync"));
+    }
+    if let Some(max_size) = policy.max_size.filter(|max_size| metadata.size > *max_size) {
+        return Err(denied(
+            policy,
+            &format!("limits items to {} bytes, item has {}", max_size, metadata.size),
+        ));
+    }
+    Ok(())
+}
+
+fn denied(policy: &SyncPolicy, reason: &str) -> ClipboardError {
+    let name = if policy.name.is_empty() { "unnamed" } else { &policy.name };
+    ClipboardError::PolicyDenied(format!("policy '{}' {}", name, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::{ClipboardContent, ContentKind};
+    use crate::config::SyncDirection;
+
+    fn policy(name: &str) -> SyncPolicy {
+        SyncPolicy {
+            name: name.to_string(),
+            content_type: None,
+            mime: None,
+            kind: None,
+            enabled: true,
+            max_size: None,
+            direction: SyncDirection::Both,
+            targets: Vec::new(),
+            compression: None,
+        }
+    }
+
+    fn image(size: usize) -> ClipboardContent {
+        ClipboardContent::new_image(vec![0; size], "image/png".to_string(), "laptop".to_string())
+    }
+
+    fn text(text: &str) -> ClipboardContent {
+        ClipboardContent::new_text(text.to_string(), "laptop".to_string())
+    }
+
+    #[test]
+    fn test_first_matching_policy_applies() {
+        let config = PolicyConfig {
+            rules: vec![
+                SyncPolicy { mime: Some("image/*".to_string()), max_size: Some(100), ..policy("small-images") },
+                SyncPolicy { kind: Some(ContentKind::Url), enabled: false, ..policy("no-urls") },
+                SyncPolicy { content_type: Some(ContentType::Image), enabled: false, ..policy("never-reached") },
+            ],
+            ..PolicyConfig::default()
+        };
+        let policies = SyncPolicies::from_config(&config);
+
+        let small = image(50);
+        let large = image(500);
+        assert!(policies.check_send(&small.content_type, &small.metadata).is_ok());
+        assert!(matches!(
+            policies.check_send(&large.content_type, &large.metadata),
+            Err(ClipboardError::PolicyDenied(_))
+        ));
+
+        let url = text("https://example.com/page");
+        assert!(policies.check_send(&url.content_type, &url.metadata).is_err());
+
+        // Items no policy matches follow the global settings
+        let plain = text("hello");
+        assert!(policies.policy_for(&plain.content_type, &plain.metadata).is_none());
+        assert!(policies.check_send(&plain.content_type, &plain.metadata).is_ok());
+    }
+
+    #[test]
+    fn test_direction_is_enforced() {
+        let config = PolicyConfig {
+            rules: vec![SyncPolicy { direction: SyncDirection::ReceiveOnly, ..policy("inbound") }],
+            ..PolicyConfig::default()
+        };
+        let policies = SyncPolicies::from_config(&config);
+        let item = text("hello");
+
+        assert!(policies.check_send(&item.content_type, &item.metadata).is_err());
+        assert!(policies.check_receive(&item.content_type, &item.metadata, "peer").is_ok());
+    }
+
+    #[test]
+    fn test_targets_match_devices_and_groups() {
+        let config = PolicyConfig {
+            rules: vec![SyncPolicy {
+                content_type: Some(ContentType::Image),
+                targets: vec!["workstations".to_string(), "12D3KooWPeer".to_string()],
+                compression: Some(CompressionCodec::None),
+                ..policy("images")
+            }],
+            groups: HashMap::from([(
+                "workstations".to_string(),
+                vec!["desktop".to_string(), "laptop".to_string()],
+            )]),
+            devices: HashMap::from([("desktop".to_string(), "12D3KooWDesktop".to_string())]),
+        };
+        let policies = SyncPolicies::from_config(&config);
+        let item = image(10);
+        let (content_type, metadata) = (&item.content_type, &item.metadata);
+
+        assert!(policies.is_targeted(content_type, metadata));
+        assert!(policies.allows_peer(content_type, metadata, "12D3KooWDesktop"));
+        assert!(policies.allows_peer(content_type, metadata, "12D3KooWPeer"));
+        assert!(!policies.allows_peer(content_type, metadata, "12D3KooWPhone"));
+        assert!(policies.check_receive(content_type, metadata, "12D3KooWPhone").is_err());
+        assert_eq!(policies.compression(content_type, metadata), Some(CompressionCodec::None));
+
+        let plain = text("hello");
+        assert!(!policies.is_targeted(&plain.content_type, &plain.metadata));
+        assert!(policies.allows_peer(&plain.content_type, &plain.metadata, "12D3KooWPhone"));
+    }
+
+    #[test]
+    fn test_policies_from_toml() {
+        let config: PolicyConfig = toml::from_str(
+            r#"
+            [groups]
+            laptops = ["work-laptop", "home-laptop"]
+
+            [devices]
+            work-laptop = "12D3KooWWorkLaptop"
+
+            [[rules]]
+            name = "images"
+            mime = "image/*"
+            max_size = 5242880
+            direction = "send_only"
+            targets = ["laptops"]
+            compression = "none"
+            "#,
+        )
+        .unwrap();
+
+        let rule = &config.rules[0];
+        assert!(rule.enabled);
+        assert_eq!(rule.direction, SyncDirection::SendOnly);
+        assert_eq!(rule.compression, Some(CompressionCodec::None));
+        assert_eq!(config.groups["laptops"].len(), 2);
+        assert_eq!(config.devices["work-laptop"], "12D3KooWWorkLaptop");
+    }
+
+    #[test]
+    fn test_mime_patterns() {
+        assert!(mime_matches("image/*", "image/png"));
+        assert!(mime_matches("IMAGE/PNG", "image/png"));
+        assert!(mime_matches("text/plain", "text/plain; charset=utf-8"));
+        assert!(mime_matches("*", "application/json"));
+        assert!(!mime_matches("image/*", "text/plain"));
+        assert!(!mime_matches("image/png", "image/jpeg"));
+    }
+}
@@ -0,0 +1,380 @@
+//! Content type detection
+//!
+//! Binary data is identified by its magic bytes and text by a few cheap
+//! heuristics. The result fills [`ContentMetadata::mime_type`] and
+//! [`ContentMetadata::kind`] so receivers and policies can act on what an
+//! item actually is.
+//!
+//! [`ContentMetadata::mime_type`]: crate::clipboard::ContentMetadata::mime_type
+//! [`ContentMetadata::kind`]: crate::clipboard::ContentMetadata::kind
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// What an item is, beyond its MIME type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    /// Not detected, e.g. sent by an older peer
+    #[default]
+    Unknown,
+    /// Plain prose or anything without a more specific kind
+    Text,
+    /// A single URL
+    Url,
+    /// A JSON document
+    Json,
+    /// Source code
+    Code,
+    /// A hex color such as `#1e90ff`
+    Color,
+    Image,
+    Audio,
+    Video,
+    /// PDF and similar documents
+    Document,
+    /// ZIP, gzip and other archives
+    Archive,
+    Binary,
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContentKind::Unknown => "unknown",
+            ContentKind::Text => "text",
+            ContentKind::Url => "url",
+            ContentKind::Json => "json",
+            ContentKind::Code => "code",
+            ContentKind::Color => "color",
+            ContentKind::Image => "image",
+            ContentKind::Audio => "audio",
+            ContentKind::Video => "video",
+            ContentKind::Document => "document",
+            ContentKind::Archive => "archive",
+            ContentKind::Binary => "binary",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ContentKind {
+    /// Kind implied by a MIME type
+    pub fn from_mime(mime_type: &str) -> Self {
+        match mime_type {
+            "application/json" => ContentKind::Json,
+            "text/uri-list" => ContentKind::Url,
+            "application/pdf" => ContentKind::Document,
+            "application/zip" | "application/gzip" | "application/x-7z-compressed" | "application/vnd.rar"
+            | "application/x-tar" | "application/x-bzip2" | "application/x-xz" | "application/zstd" => {
+                ContentKind::Archive
+            }
+            _ if mime_type.starts_with("image/") => ContentKind::Image,
+            _ if mime_type.starts_with("audio/") => ContentKind::Audio,
+            _ if mime_type.starts_with("video/") => ContentKind::Video,
+            _ if mime_type.starts_with("text/") => ContentKind::Text,
+            _ => ContentKind::Binary,
+        }
+    }
+
+    /// Whether the kind describes text content
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            ContentKind::Text | ContentKind::Url | ContentKind::Json | ContentKind::Code | ContentKind::Color
+        )
+    }
+}
+
+/// Detect a MIME type from magic bytes
+pub fn sniff_mime(data: &[u8]) -> Option<&'static str> {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| data.get(offset..offset + magic.len()) == Some(magic);
+
+    let mime = if starts(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if starts(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        "image/gif"
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if is_bmp(data) {
+        "image/bmp"
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        "image/tiff"
+    } else if starts(b"\0\0\x01\0") {
+        "image/x-icon"
+    } else if at(4, b"ftypavif") {
+        "image/avif"
+    } else if at(4, b"ftypheic") || at(4, b"ftypheix") || at(4, b"ftypmif1") {
+        "image/heic"
+    } else if starts(b"%PDF-") {
+        "application/pdf"
+    } else if starts(b"PK\x03\x04") || starts(b"PK\x05\x06") {
+        "application/zip"
+    } else if starts(b"\x1f\x8b") {
+        "application/gzip"
+    } else if starts(b"7z\xbc\xaf\x27\x1c") {
+        "application/x-7z-compressed"
+    } else if starts(b"Rar!\x1a\x07") {
+        "application/vnd.rar"
+    } else if starts(b"BZh") {
+        "application/x-bzip2"
+    } else if starts(b"\xfd7zXZ\0") {
+        "application/x-xz"
+    } else if starts(b"\x28\xb5\x2f\xfd") {
+        "application/zstd"
+    } else if at(257, b"ustar") {
+        "application/x-tar"
+    } else if starts(b"ID3") || starts(b"\xff\xfb") {
+        "audio/mpeg"
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if starts(b"OggS") {
+        "audio/ogg"
+    } else if starts(b"fLaC") {
+        "audio/flac"
+    } else if at(4, b"ftyp") {
+        "video/mp4"
+    } else if starts(b"\x1a\x45\xdf\xa3") {
+        "video/webm"
+    } else {
+        return None;
+    };
+
+    Some(mime)
+}
+
+/// Types whose magic bytes are short enough to turn up at the start of unrelated data
+const WEAK_MAGIC: &[&str] = &["image/bmp", "image/x-icon", "audio/mpeg", "application/x-bzip2"];
+
+/// Detect the MIME type of a named file
+///
+/// Magic bytes win over the extension, unless they are too short to be
+/// trusted and the extension is recognized.
+pub fn sniff_file_mime(data: &[u8], filename: &str) -> Option<&'static str> {
+    match sniff_mime(data) {
+        Some(mime) if WEAK_MAGIC.contains(&mime) => mime_from_filename(filename).or(Some(mime)),
+        Some(mime) => Some(mime),
+        None => mime_from_filename(filename),
+    }
+}
+
+/// Check the BMP file header: the magic, the file size it records and the reserved bytes
+fn is_bmp(data: &[u8]) -> bool {
+    let Some(header) = data.get(..14) else {
+        return false;
+    };
+
+    let file_size = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    header.starts_with(b"BM") && file_size == data.len() && header[6..10] == [0; 4]
+}
+
+/// Guess a MIME type from a file name's extension
+pub fn mime_from_filename(filename: &str) -> Option<&'static str> {
+    let extension = filename.rsplit_once('.')?.1.to_ascii_lowercase();
+
+    let mime = match extension.as_str() {
+        "txt" | "log" | "md" => "text/plain",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        _ => return None,
+    };
+
+    Some(mime)
+}
+
+/// Texts larger than this are classified from their first bytes only
+const TEXT_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Classify text content
+pub fn classify_text(text: &str) -> ContentKind {
+    let trimmed = text.trim();
+
+    if is_color(trimmed) {
+        ContentKind::Color
+    } else if is_url(trimmed) {
+        ContentKind::Url
+    } else if is_json(trimmed) {
+        ContentKind::Json
+    } else if looks_like_code(trimmed) {
+        ContentKind::Code
+    } else {
+        ContentKind::Text
+    }
+}
+
+/// MIME type for a text kind
+pub fn text_mime(kind: ContentKind) -> &'static str {
+    match kind {
+        ContentKind::Url => "text/uri-list",
+        ContentKind::Json => "application/json",
+        _ => "text/plain",
+    }
+}
+
+fn is_color(text: &str) -> bool {
+    static COLOR: OnceLock<Regex> = OnceLock::new();
+    COLOR
+        .get_or_init(|| Regex::new(r"^#(?:[0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$").unwrap())
+        .is_match(text)
+}
+
+fn is_url(text: &str) -> bool {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    URL.get_or_init(|| {
+        Regex::new(r"^(?i:(?:https?|ftp|file)://[^\s/?#]*[^\s]*|mailto:[^\s@]+@[^\s@]+|www\.[^\s/]+\.[a-z]{2,}[^\s]*)$").unwrap()
+    })
+    .is_match(text)
+}
+
+fn is_json(text: &str) -> bool {
+    let bracketed = (text.starts_with('{') && text.ends_with('}')) || (text.starts_with('[') && text.ends_with(']'));
+    if !bracketed {
+        return false;
+    }
+
+    // Parsing huge documents just to label them is not worth it
+    if text.len() > TEXT_SAMPLE_SIZE * 16 {
+        return true;
+    }
+    serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+fn looks_like_code(text: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "fn ", "let ", "const ", "var ", "function ", "def ", "class ", "import ", "from ", "return ", "pub ",
+        "impl ", "struct ", "#include", "package ", "func ", "public ", "private ", "if (", "for (", "while (",
+        "SELECT ",
+    ];
+
+    let sample = match text.char_indices().nth(TEXT_SAMPLE_SIZE) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    };
+
+    let lines: Vec<&str> = sample.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    if lines.is_empty() {
+        return false;
+    }
+
+    let code_lines = lines
+        .iter()
+        .filter(|line| {
+            line.ends_with(';')
+                || line.ends_with('{')
+                || *line == &"}"
+                || line.ends_with("):")
+                || line.starts_with("//")
+                || KEYWORDS.iter().any(|keyword| line.starts_with(keyword))
+        })
+        .count();
+
+    // A single line needs a strong signal, longer snippets a fair share of code-like lines
+    if lines.len() == 1 {
+        code_lines == 1 && (lines[0].ends_with(';') || lines[0].ends_with('{'))
+    } else {
+        code_lines * 3 >= lines.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_bytes() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff_mime(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff_mime(b"GIF89a\x01\0"), Some("image/gif"));
+        assert_eq!(sniff_mime(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_mime(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff_mime(b"PK\x03\x04\x14\0"), Some("application/zip"));
+        assert_eq!(sniff_mime(b"\0\0\0\x20ftypisom"), Some("video/mp4"));
+        assert_eq!(sniff_mime(b"plain text"), None);
+        assert_eq!(sniff_mime(b""), None);
+
+        // BMP headers record the file size and zeroed reserved bytes
+        let mut bmp = b"BM          ".to_vec();
+        bmp.resize(30, 0);
+        assert_eq!(sniff_mime(&bmp), Some("image/bmp"));
+        assert_eq!(sniff_mime(&bmp[..29]), None);
+        assert_eq!(sniff_mime(b"BMW service record for the 2019 model"), None);
+    }
+
+    #[test]
+    fn test_extension_beats_weak_magic() {
+        assert_eq!(sniff_file_mime(b"ID3 tags to fix before release", "todo.txt"), Some("text/plain"));
+        assert_eq!(sniff_file_mime(b"ID3      ", "track"), Some("audio/mpeg"));
+        assert_eq!(sniff_file_mime(b"%PDF-1.7
+", "scan.txt"), Some("application/pdf"));
+        assert_eq!(sniff_file_mime(b"plain text", "notes.md"), Some("text/plain"));
+    }
+
+    #[test]
+    fn test_text_heuristics() {
+        assert_eq!(classify_text("#1e90ff"), ContentKind::Color);
+        assert_eq!(classify_text("  #FFF\n"), ContentKind::Color);
+        assert_eq!(classify_text("https://example.com/path?q=1"), ContentKind::Url);
+        assert_eq!(classify_text("www.example.org"), ContentKind::Url);
+        assert_eq!(classify_text("see https://example.com for details"), ContentKind::Text);
+        assert_eq!(classify_text(r#"{"name": "crosscopy", "tags": [1, 2]}"#), ContentKind::Json);
+        assert_eq!(classify_text("{not json}"), ContentKind::Text);
+        assert_eq!(
+            classify_text("fn main() {\n    println!(\"hi\");\n}\n"),
+            ContentKind::Code
+        );
+        assert_eq!(classify_text("def greet(name):\n    return f\"hi {name}\"\n"), ContentKind::Code);
+        assert_eq!(
+            classify_text("Meeting notes\nWe agreed to ship on Friday.\nAlice will follow up."),
+            ContentKind::Text
+        );
+    }
+
+    #[test]
+    fn test_constructors_fill_metadata() {
+        use crate::clipboard::ClipboardContent;
+
+        let url = ClipboardContent::new_text("https://example.com".to_string(), "test".to_string());
+        assert_eq!(url.metadata.kind, ContentKind::Url);
+        assert_eq!(url.metadata.mime_type.as_deref(), Some("text/uri-list"));
+
+        // Images are labelled by their actual format
+        let jpeg = ClipboardContent::new_image(b"\xff\xd8\xff\xe0".to_vec(), "image/png".to_string(), "test".to_string());
+        assert_eq!(jpeg.metadata.mime_type.as_deref(), Some("image/jpeg"));
+
+        let pdf = ClipboardContent::new_file(b"%PDF-1.4".to_vec(), "scan".to_string(), None, "test".to_string());
+        assert_eq!(pdf.metadata.mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(pdf.metadata.kind, ContentKind::Document);
+
+        let json = ClipboardContent::new_file(b"[1, 2]".to_vec(), "data".to_string(), None, "test".to_string());
+        assert_eq!(json.metadata.kind, ContentKind::Json);
+
+        let blob = ClipboardContent::new_file(vec![0, 159, 146, 150], "blob".to_string(), None, "test".to_string());
+        assert_eq!(blob.metadata.mime_type, None);
+        assert_eq!(blob.metadata.kind, ContentKind::Binary);
+    }
+
+    #[test]
+    fn test_kind_from_mime() {
+        assert_eq!(ContentKind::from_mime("image/webp"), ContentKind::Image);
+        assert_eq!(ContentKind::from_mime("application/zip"), ContentKind::Archive);
+        assert_eq!(ContentKind::from_mime("application/octet-stream"), ContentKind::Binary);
+        assert_eq!(mime_from_filename("Report.PDF"), Some("application/pdf"));
+        assert_eq!(mime_from_filename("README"), None);
+    }
+}
@@ -0,0 +1,169 @@
+//! Content-addressed cache of clipboard items
+//!
+//! Items are keyed by their SHA-256 `checksum`, so a body announced by one
+//! peer and fetched by another ends up under the same key on both sides.
+
+use crate::clipboard::{ClipboardContent, ClipboardError, Result};
+use log::debug;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::RwLock;
+
+/// Bounded, least-recently-used cache of clipboard items by checksum
+pub struct ContentStore {
+    max_bytes: usize,
+    inner: RwLock<StoreInner>,
+}
+
+#[derive(Default)]
+struct StoreInner {
+    items: HashMap<String, ClipboardContent>,
+    /// Checksums from least to most recently used
+    order: VecDeque<String>,
+    total_bytes: usize,
+}
+
+impl StoreInner {
+    fn touch(&mut self, checksum: &str) {
+        if let Some(position) = self.order.iter().position(|c| c == checksum) {
+            if let Some(checksum) = self.order.remove(position) {
+                self.order.push_back(checksum);
+            }
+        }
+    }
+}
+
+impl ContentStore {
+    /// Create a store holding at most `max_bytes` of item data
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            inner: RwLock::new(StoreInner::default()),
+        }
+    }
+
+    /// Store an item after verifying its checksum, returning the checksum
+    pub async fn insert(&self, content: ClipboardContent) -> Result<String> {
+        if !content.verify_integrity() {
+            return Err(ClipboardError::ChecksumMismatch(content.checksum));
+        }
+
+        let size = content.data.len();
+        if size > self.max_bytes {
+            return Err(ClipboardError::ContentTooLarge {
+                size,
+                max_size: self.max_bytes,
+            });
+        }
+
+        let checksum = content.checksum.clone();
+        let mut inner = self.inner.write().await;
+
+        if inner.items.contains_key(&checksum) {
+            inner.touch(&checksum);
+            return Ok(checksum);
+        }
+
+        // Evict least recently used items until the new one fits
+        while inner.total_bytes + size > self.max_bytes {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = inner.items.remove(&oldest) {
+                inner.total_bytes -= evicted.data.len();
+                debug!("Evicted {} from content store", oldest);
+            }
+        }
+
+        inner.total_bytes += size;
+        inner.order.push_back(checksum.clone());
+        inner.items.insert(checksum.clone(), content);
+
+        Ok(checksum)
+    }
+
+    /// Get an item by checksum
+    pub async fn get(&self, checksum: &str) -> Option<ClipboardContent> {
+        let mut inner = self.inner.write().await;
+        let content = inner.items.get(checksum).cloned();
+        if content.is_some() {
+            inner.touch(checksum);
+        }
+        content
+    }
+
+    /// Run `f` on a cached item without copying it
+    pub async fn with_content<R>(&self, checksum: &str, f: impl FnOnce(&ClipboardContent) -> R) -> Option<R> {
+        self.inner.read().await.items.get(checksum).map(f)
+    }
+
+    /// Check whether an item is cached
+    pub async fn contains(&self, checksum: &str) -> bool {
+        self.inner.read().await.items.contains_key(checksum)
+    }
+
+    /// Number of cached items
+    pub async fn len(&self) -> usize {
+        self.inner.read().await.items.len()
+    }
+
+    /// Check whether the store is empty
+    pub async fn is_empty(&self) -> bool {
+        self.inner.read().await.items.is_empty()
+    }
+
+    /// Total size of cached item data in bytes
+    pub async fn total_bytes(&self) -> usize {
+        self.inner.read().await.total_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str) -> ClipboardContent {
+        ClipboardContent::new_text(text.to_string(), "test-device".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_store_is_content_addressed() {
+        let store = ContentStore::new(1024);
+        let content = item("hello");
+
+        let checksum = store.insert(content.clone()).await.unwrap();
+        assert_eq!(checksum, content.checksum);
+
+        // Inserting the same content again does not duplicate it
+        store.insert(item("hello")).await.unwrap();
+        assert_eq!(store.len().await, 1);
+        assert_eq!(store.get(&checksum).await.unwrap().as_text().unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_corrupted_content() {
+        let store = ContentStore::new(1024);
+        let mut content = item("hello");
+        content.data = b"tampered".to_vec();
+
+        assert!(matches!(store.insert(content).await, Err(ClipboardError::ChecksumMismatch(_))));
+        assert!(store.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_store_evicts_least_recently_used() {
+        let store = ContentStore::new(10);
+        let first = store.insert(item("aaaa")).await.unwrap();
+        let second = store.insert(item("bbbb")).await.unwrap();
+
+        // Touch the first item so the second one is evicted
+        store.get(&first).await.unwrap();
+        let third = store.insert(item("cccc")).await.unwrap();
+
+        assert!(store.contains(&first).await);
+        assert!(!store.contains(&second).await);
+        assert!(store.contains(&third).await);
+        assert_eq!(store.total_bytes().await, 8);
+
+        assert!(store.insert(item("way too large")).await.is_err());
+    }
+}
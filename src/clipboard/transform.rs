@@ -0,0 +1,355 @@
+//! Text transformations applied to synced items
+//!
+//! The [`TransformPipeline`] cleans up text items before they are sent and
+//! after they are received, e.g. trimming trailing whitespace or stripping
+//! tracking parameters from URLs. Transformed items get a fresh checksum, so
+//! integrity checks hold for the transformed text.
+
+use crate::clipboard::{ClipboardContent, CompressionCodec, ContentType};
+use crate::config::{BuiltinTransform, LineEnding, SyncDirection, TransformConfig};
+use regex::Regex;
+
+/// Query parameters removed by [`StripTrackingParams`]; `utm_*` is removed as well
+pub const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "gclsrc", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi",
+    "mkt_tok",
+];
+
+/// Transformation of clipboard text
+pub trait ContentTransform: Send + Sync {
+    /// Transform the text
+    fn transform(&self, text: &str) -> String;
+
+    /// Get transform name for logging
+    fn name(&self) -> &str;
+}
+
+/// Ordered set of transformations, each applied on send, receive or both
+#[derive(Default)]
+pub struct TransformPipeline {
+    steps: Vec<(SyncDirection, Box<dyn ContentTransform>)>,
+}
+
+impl TransformPipeline {
+    /// Create an empty pipeline that leaves items unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a pipeline from configuration
+    pub fn from_config(config: &TransformConfig) -> Self {
+        let mut pipeline = Self::new();
+
+        for step in &config.steps {
+            let transform: Box<dyn ContentTransform> = match step.transform {
+                BuiltinTransform::TrimTrailingWhitespace => Box::new(TrimTrailingWhitespace),
+                BuiltinTransform::NormalizeLineEndings => Box::new(NormalizeLineEndings::new(config.line_ending)),
+                BuiltinTransform::ReplaceSmartQuotes => Box::new(ReplaceSmartQuotes),
+                BuiltinTransform::StripTrackingParams => {
+                    Box::new(StripTrackingParams::new(config.tracking_params.iter().cloned()))
+                }
+            };
+            pipeline.add_transform(step.direction, transform);
+        }
+
+        pipeline
+    }
+
+    /// Append a transformation applied in `direction`
+    pub fn add_transform(&mut self, direction: SyncDirection, transform: Box<dyn ContentTransform>) {
+        self.steps.push((direction, transform));
+    }
+
+    /// Number of transformations in the pipeline
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Check whether the pipeline has no transformations
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Transform a local item before it is sent
+    pub fn apply_on_send(&self, content: ClipboardContent) -> ClipboardContent {
+        self.apply(content, SyncDirection::sends)
+    }
+
+    /// Transform a received item before it is applied
+    ///
+    /// Compressed items are left unchanged; decompress them first.
+    pub fn apply_on_receive(&self, content: ClipboardContent) -> ClipboardContent {
+        self.apply(content, SyncDirection::receives)
+    }
+
+    fn apply(&self, mut content: ClipboardContent, applies: fn(&SyncDirection) -> bool) -> ClipboardContent {
+        if content.content_type != ContentType::Text || content.metadata.compression != CompressionCodec::None {
+            return content;
+        }
+        let Some(original) = content.as_text() else {
+            return content;
+        };
+
+        let text = self
+            .steps
+            .iter()
+            .filter(|(direction, _)| applies(direction))
+            .fold(original.clone(), |text, (_, transform)| transform.transform(&text));
+
+        if text != original {
+            content.set_text(text);
+        }
+        content
+    }
+}
+
+/// Removes whitespace at the end of every line and of the text, keeping line endings
+pub struct TrimTrailingWhitespace;
+
+impl ContentTransform for TrimTrailingWhitespace {
+    fn transform(&self, text: &str) -> String {
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| match line.strip_suffix('\r') {
+                Some(line) => format!("{}\r", line.trim_end()),
+                None => line.trim_end().to_string(),
+            })
+            .collect();
+        lines.join("\n").trim_end().to_string()
+    }
+
+    fn name(&self) -> &str {
+        "trim_trailing_whitespace"
+    }
+}
+
+/// Converts CRLF, CR and LF line endings to one style
+pub struct NormalizeLineEndings {
+    line_ending: LineEnding,
+}
+
+impl NormalizeLineEndings {
+    pub fn new(line_ending: LineEnding) -> Self {
+        Self { line_ending }
+    }
+}
+
+impl ContentTransform for NormalizeLineEndings {
+    fn transform(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.line_ending.as_str() {
+            "\n" => text,
+            separator => text.replace('\n', separator),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "normalize_line_endings"
+    }
+}
+
+/// Replaces typographic quotes with ASCII quotes
+pub struct ReplaceSmartQuotes;
+
+impl ContentTransform for ReplaceSmartQuotes {
+    fn transform(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+                '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+                c => c,
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "replace_smart_quotes"
+    }
+}
+
+/// Removes tracking query parameters from URLs in the text
+pub struct StripTrackingParams {
+    url: Regex,
+    params: Vec<String>,
+}
+
+impl StripTrackingParams {
+    /// Strip [`TRACKING_PARAMS`], `utm_*` and `extra` parameters
+    pub fn new(extra: impl IntoIterator<Item = String>) -> Self {
+        let params = TRACKING_PARAMS
+            .iter()
+            .map(|param| param.to_string())
+            .chain(extra.into_iter().map(|param| param.to_ascii_lowercase()))
+            .collect();
+
+        Self {
+            url: Regex::new(r#"https?://[^\s<>"']+"#).expect("URL pattern is valid"),
+            params,
+        }
+    }
+
+    fn is_tracking(&self, pair: &str) -> bool {
+        let key = pair.split('=').next().unwrap_or_default().to_ascii_lowercase();
+        key.starts_with("utm_") || self.params.contains(&key)
+    }
+
+    fn strip(&self, url: &str) -> String {
+        let (url, fragment) = match url.find('#') {
+            Some(index) => url.split_at(index),
+            None => (url, ""),
+        };
+        let Some((base, query)) = url.split_once('?') else {
+            return format!("{}{}", url, fragment);
+        };
+
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty() && !self.is_tracking(pair))
+            .collect();
+        if kept.is_empty() {
+            format!("{}{}", base, fragment)
+        } else {
+            format!("{}?{}{}", base, kept.join("&"), fragment)
+        }
+    }
+}
+
+impl ContentTransform for StripTrackingParams {
+    fn transform(&self, text: &str) -> String {
+        self.url
+            .replace_all(text, |captures: &regex::Captures| {
+                // Punctuation ending a sentence is not part of the URL
+                let url = &captures[0];
+                let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+                format!("{}{}", self.strip(trimmed), &url[trimmed.len()..])
+            })
+            .into_owned()
+    }
+
+    fn name(&self) -> &str {
+        "strip_tracking_params"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TransformStep;
+
+    fn text(text: &str) -> ClipboardContent {
+        ClipboardContent::new_text(text.to_string(), "laptop".to_string())
+    }
+
+    fn step(transform: BuiltinTransform, direction: SyncDirection) -> TransformStep {
+        TransformStep { transform, direction }
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace_keeps_line_endings() {
+        let trim = TrimTrailingWhitespace;
+        assert_eq!(trim.transform("a  \r\nb\t\nc \n\n"), "a\r\nb\nc");
+        assert_eq!(trim.transform("  indented"), "  indented");
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        let text = "a\r\nb\rc\nd";
+        assert_eq!(NormalizeLineEndings::new(LineEnding::Lf).transform(text), "a\nb\nc\nd");
+        assert_eq!(NormalizeLineEndings::new(LineEnding::Crlf).transform(text), "a\r\nb\r\nc\r\nd");
+
+        let native = if cfg!(windows) { "a\r\nb" } else { "a\nb" };
+        assert_eq!(NormalizeLineEndings::new(LineEnding::Native).transform("a\r\nb"), native);
+    }
+
+    #[test]
+    fn test_replace_smart_quotes() {
+        assert_eq!(
+            ReplaceSmartQuotes.transform("\u{201C}It\u{2019}s fine\u{201D}, they said"),
+            "\"It's fine\", they said"
+        );
+    }
+
+    #[test]
+    fn test_strip_tracking_params() {
+        let strip = StripTrackingParams::new(vec!["Ref".to_string()]);
+        assert_eq!(
+            strip.transform("See https://example.com/a?id=7&utm_source=x&fbclid=y#top and https://example.com/b?utm_medium=z."),
+            "See https://example.com/a?id=7#top and https://example.com/b."
+        );
+        assert_eq!(strip.transform("https://example.com/?ref=feed"), "https://example.com/");
+        assert_eq!(strip.transform("https://example.com/?q=1"), "https://example.com/?q=1");
+    }
+
+    #[test]
+    fn test_pipeline_applies_steps_in_order_per_direction() {
+        let config = TransformConfig {
+            steps: vec![
+                step(BuiltinTransform::NormalizeLineEndings, SyncDirection::ReceiveOnly),
+                step(BuiltinTransform::TrimTrailingWhitespace, SyncDirection::Both),
+                step(BuiltinTransform::ReplaceSmartQuotes, SyncDirection::SendOnly),
+            ],
+            line_ending: LineEnding::Crlf,
+            tracking_params: Vec::new(),
+        };
+        let pipeline = TransformPipeline::from_config(&config);
+        assert_eq!(pipeline.len(), 3);
+
+        let original = text("\u{2018}a\u{2019}  \nb  \n");
+        let sent = pipeline.apply_on_send(original.clone());
+        assert_eq!(sent.as_text().unwrap(), "'a'\nb");
+        assert_eq!(sent.metadata.size, sent.data.len());
+        assert_eq!(sent.metadata.created_at, original.metadata.created_at);
+        assert!(sent.verify_integrity());
+        assert_ne!(sent.checksum, original.checksum);
+
+        let received = pipeline.apply_on_receive(original);
+        assert_eq!(received.as_text().unwrap(), "\u{2018}a\u{2019}\r\nb");
+        assert!(received.verify_integrity());
+    }
+
+    #[test]
+    fn test_custom_transform_and_non_text_items() {
+        struct Uppercase;
+        impl ContentTransform for Uppercase {
+            fn transform(&self, text: &str) -> String {
+                text.to_uppercase()
+            }
+
+            fn name(&self) -> &str {
+                "uppercase"
+            }
+        }
+
+        let mut pipeline = TransformPipeline::new();
+        assert!(pipeline.is_empty());
+        pipeline.add_transform(SyncDirection::Both, Box::new(Uppercase));
+
+        assert_eq!(pipeline.apply_on_receive(text("hello")).as_text().unwrap(), "HELLO");
+
+        let image = ClipboardContent::new_image(b"hello".to_vec(), "image/png".to_string(), "laptop".to_string());
+        assert_eq!(pipeline.apply_on_send(image.clone()).data, image.data);
+    }
+
+    #[test]
+    fn test_transform_config_from_toml() {
+        let config: TransformConfig = toml::from_str(
+            r#"
+            line_ending = "crlf"
+            tracking_params = ["ref"]
+
+            [[steps]]
+            transform = "trim_trailing_whitespace"
+
+            [[steps]]
+            transform = "strip_tracking_params"
+            direction = "send_only"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.line_ending, LineEnding::Crlf);
+        assert_eq!(config.steps[0].direction, SyncDirection::Both);
+        assert_eq!(config.steps[1].transform, BuiltinTransform::StripTrackingParams);
+        assert_eq!(config.steps[1].direction, SyncDirection::SendOnly);
+    }
+}
@@ -0,0 +1,344 @@
+//! Clipboard change notifications
+//!
+//! Where the platform can push change notifications the monitor waits on a
+//! [`ChangeSignal`] instead of reading the clipboard on every tick. On Linux
+//! this uses XFixes selection-owner events on X11 and `wl-paste --watch`
+//! (wlr-data-control) on Wayland.
+
+use crate::clipboard::Selection;
+use log::debug;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Signal raised by a backend whenever the clipboard changes
+#[derive(Debug)]
+pub struct ChangeSignal {
+    notify: Notify,
+    count: AtomicU64,
+    active: AtomicBool,
+}
+
+impl ChangeSignal {
+    /// Create an active signal
+    pub fn new() -> Self {
+        Self {
+            notify: Notify::new(),
+            count: AtomicU64::new(0),
+            active: AtomicBool::new(true),
+        }
+    }
+
+    /// Record a clipboard change and wake the waiting monitor
+    pub fn notify_change(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+
+    /// Wait for the next change
+    ///
+    /// A change raised while nobody was waiting is not lost.
+    pub async fn changed(&self) {
+        self.notify.notified().await;
+    }
+
+    /// Number of changes observed so far
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Whether the watcher feeding this signal is still running
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Mark the watcher as stopped so the monitor falls back to polling
+    pub fn close(&self) {
+        self.active.store(false, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+}
+
+impl Default for ChangeSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A running change watcher; dropping it stops the watcher thread
+pub struct ChangeWatcher {
+    signal: Arc<ChangeSignal>,
+    stop: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl ChangeWatcher {
+    /// Wrap a watcher feeding `signal` that is stopped by calling `stop`
+    pub fn new(signal: Arc<ChangeSignal>, stop: impl FnOnce() + Send + Sync + 'static) -> Self {
+        Self {
+            signal,
+            stop: Some(Box::new(stop)),
+        }
+    }
+
+    /// Signal raised by this watcher
+    pub fn signal(&self) -> &Arc<ChangeSignal> {
+        &self.signal
+    }
+}
+
+impl Drop for ChangeWatcher {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop();
+        }
+    }
+}
+
+impl std::fmt::Debug for ChangeWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangeWatcher").field("signal", &self.signal).finish()
+    }
+}
+
+/// Start a watcher for a system selection, if the platform supports one
+pub fn start_system_watcher(selection: Selection) -> Option<ChangeWatcher> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::start(selection)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        debug!("No {} change notifications on this platform, polling instead", selection);
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use crate::clipboard::{ClipboardError, Result};
+    use crate::utils::platform;
+    use log::warn;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use std::sync::Mutex;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+    use x11rb::protocol::xproto::{
+        AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask, WindowClass,
+    };
+    use x11rb::protocol::Event;
+
+    pub fn start(selection: Selection) -> Option<ChangeWatcher> {
+        let result = if platform::linux::is_wayland() {
+            spawn_wayland_watcher(selection).or_else(|e| {
+                debug!("Wayland {} watcher unavailable: {}", selection, e);
+                spawn_x11_watcher(selection)
+            })
+        } else {
+            spawn_x11_watcher(selection)
+        };
+
+        match result {
+            Ok(signal) => Some(signal),
+            Err(e) => {
+                debug!("{} change notifications unavailable, polling instead: {}", selection, e);
+                None
+            }
+        }
+    }
+
+    /// Watch selection ownership changes with the XFixes extension
+    ///
+    /// Stopping the watcher sends a client message to a hidden window of its
+    /// own, waking the thread blocked in `wait_for_event`.
+    fn spawn_x11_watcher(selection: Selection) -> Result<ChangeWatcher> {
+        let error = |e: &dyn std::fmt::Display| ClipboardError::AccessFailed(format!("XFixes: {}", e));
+
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| error(&e))?;
+        conn.xfixes_query_version(5, 0)
+            .map_err(|e| error(&e))?
+            .reply()
+            .map_err(|e| error(&e))?;
+
+        let root = conn.setup().roots[screen_num].root;
+        let atom_name: &[u8] = match selection {
+            Selection::Clipboard => b"CLIPBOARD",
+            Selection::Primary => b"PRIMARY",
+        };
+        let atom = conn
+            .intern_atom(false, atom_name)
+            .map_err(|e| error(&e))?
+            .reply()
+            .map_err(|e| error(&e))?
+            .atom;
+
+        conn.xfixes_select_selection_input(
+            root,
+            atom,
+            SelectionEventMask::SET_SELECTION_OWNER
+                | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+        )
+        .map_err(|e| error(&e))?;
+
+        // Window that only receives the stop message
+        let wake_window = conn.generate_id().map_err(|e| error(&e))?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            wake_window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .map_err(|e| error(&e))?;
+        conn.flush().map_err(|e| error(&e))?;
+
+        let conn = Arc::new(conn);
+        let signal = Arc::new(ChangeSignal::new());
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        let watcher_conn = conn.clone();
+        let watcher_signal = signal.clone();
+        let watcher_stopping = stopping.clone();
+
+        std::thread::Builder::new()
+            .name("x11-clipboard-watcher".to_string())
+            .spawn(move || {
+                loop {
+                    match watcher_conn.wait_for_event() {
+                        Ok(Event::XfixesSelectionNotify(_)) => watcher_signal.notify_change(),
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!("X11 clipboard watcher stopped: {}", e);
+                            break;
+                        }
+                    }
+
+                    // Stopped, or nobody is listening any more
+                    if watcher_stopping.load(Ordering::SeqCst) || Arc::strong_count(&watcher_signal) == 1 {
+                        break;
+                    }
+                }
+                debug!("X11 clipboard watcher stopped");
+                watcher_signal.close();
+            })
+            .map_err(|e| error(&e))?;
+
+        debug!("Watching {} changes via XFixes", selection);
+        Ok(ChangeWatcher::new(signal, move || {
+            stopping.store(true, Ordering::SeqCst);
+
+            // An event sent with an empty mask goes to the window's creator
+            let wake = ClientMessageEvent::new(32, wake_window, AtomEnum::NONE, [0u32; 5]);
+            let sent = conn
+                .send_event(false, wake_window, EventMask::NO_EVENT, wake)
+                .and_then(|_| conn.flush());
+            if let Err(e) = sent {
+                debug!("Failed to wake X11 clipboard watcher: {}", e);
+            }
+        }))
+    }
+
+    /// Watch selection changes through wl-paste, which uses wlr-data-control
+    ///
+    /// Stopping the watcher kills wl-paste, which ends its output.
+    fn spawn_wayland_watcher(selection: Selection) -> Result<ChangeWatcher> {
+        let mut command = Command::new("wl-paste");
+        if selection == Selection::Primary {
+            command.arg("--primary");
+        }
+
+        let mut child = command
+            .args(["--watch", "echo"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| ClipboardError::AccessFailed(format!("Failed to run wl-paste: {}", e)))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| ClipboardError::AccessFailed("wl-paste stdout unavailable".to_string()))?;
+
+        let child = Arc::new(Mutex::new(child));
+        let signal = Arc::new(ChangeSignal::new());
+
+        let watcher_child = child.clone();
+        let watcher_signal = signal.clone();
+
+        std::thread::Builder::new()
+            .name("wayland-clipboard-watcher".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    if line.is_err() || Arc::strong_count(&watcher_signal) == 1 {
+                        break;
+                    }
+                    watcher_signal.notify_change();
+                }
+
+                // wl-paste exits immediately if the compositor lacks data-control
+                if let Ok(mut child) = watcher_child.lock() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                debug!("Wayland clipboard watcher stopped");
+                watcher_signal.close();
+            })
+            .map_err(|e| ClipboardError::AccessFailed(e.to_string()))?;
+
+        debug!("Watching {} changes via wl-paste", selection);
+        Ok(ChangeWatcher::new(signal, move || {
+            if let Ok(mut child) = child.lock() {
+                let _ = child.kill();
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_change_signal_is_not_lost() {
+        let signal = ChangeSignal::new();
+        signal.notify_change();
+
+        tokio::time::timeout(Duration::from_secs(1), signal.changed())
+            .await
+            .expect("pending change was lost");
+        assert_eq!(signal.count(), 1);
+    }
+
+    #[test]
+    fn test_change_watcher_stops_on_drop() {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watcher_stopped = stopped.clone();
+        let watcher = ChangeWatcher::new(Arc::new(ChangeSignal::new()), move || {
+            watcher_stopped.store(true, Ordering::SeqCst);
+        });
+
+        let signal = watcher.signal().clone();
+        drop(watcher);
+        assert!(stopped.load(Ordering::SeqCst));
+        assert_eq!(signal.count(), 0);
+    }
+
+    #[test]
+    fn test_change_signal_close() {
+        let signal = ChangeSignal::new();
+        assert!(signal.is_active());
+        signal.close();
+        assert!(!signal.is_active());
+    }
+}
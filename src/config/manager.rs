@@ -2,7 +2,7 @@
 
 use crate::config::{AppConfig, ConfigError, Result};
 use crate::utils::platform;
-use log::{debug, info, warn};
+use log::{debug, info};
 use std::path::{Path, PathBuf};
 
 /// Configuration manager for loading and saving application configuration
@@ -83,6 +83,68 @@ impl ConfigManager {
             ));
         }
 
+        if config.clipboard.poll_interval_millis == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "Poll interval must be greater than 0".to_string(),
+            ));
+        }
+
+        if config.clipboard.max_poll_interval_millis < config.clipboard.poll_interval_millis {
+            return Err(ConfigError::ValidationFailed(
+                "Max poll interval cannot be shorter than poll interval".to_string(),
+            ));
+        }
+
+        let chunk_size = config.clipboard.lazy.chunk_size_bytes;
+        if chunk_size == 0 || chunk_size > crate::network::transfer::MAX_CHUNK_SIZE {
+            return Err(ConfigError::ValidationFailed(format!(
+                "Chunk size must be between 1 and {} bytes",
+                crate::network::transfer::MAX_CHUNK_SIZE
+            )));
+        }
+
+        if config.sync.uses_manual_push() && config.sync.manual_queue_size == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "Manual queue size must be greater than 0 in manual sync mode".to_string(),
+            ));
+        }
+
+        if config.events.queue_capacity == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "Event queue capacity must be greater than 0".to_string(),
+            ));
+        }
+
+        if config.events.journal.enabled && config.events.journal.max_files == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "Journal must keep at least one file".to_string(),
+            ));
+        }
+
+        if config.metrics.window_secs == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "Metrics window must be greater than 0 seconds".to_string(),
+            ));
+        }
+
+        if config.metrics.enabled {
+            match config.metrics.listen_address.parse::<std::net::SocketAddr>() {
+                Err(_) => {
+                    return Err(ConfigError::ValidationFailed(format!(
+                        "Invalid metrics listen address: {}",
+                        config.metrics.listen_address
+                    )));
+                }
+                Ok(address) if !address.ip().is_loopback() => {
+                    return Err(ConfigError::ValidationFailed(format!(
+                        "Metrics listen address must be a loopback address: {}",
+                        address
+                    )));
+                }
+                Ok(_) => {}
+            }
+        }
+
         // Validate security configuration
         if config.security.secret_key.is_empty() {
             return Err(ConfigError::ValidationFailed(
@@ -90,6 +152,13 @@ impl ConfigManager {
             ));
         }
 
+        // Validate history configuration
+        if config.history.enabled && config.history.max_items == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "History max items must be greater than 0".to_string(),
+            ));
+        }
+
         // Validate logging configuration
         let valid_levels = ["error", "warn", "info", "debug", "trace"];
         if !valid_levels.contains(&config.logging.level.as_str()) {
@@ -98,6 +167,20 @@ impl ConfigManager {
             ));
         }
 
+        for (module, level) in &config.logging.modules {
+            if !valid_levels.contains(&level.as_str()) && level != "off" {
+                return Err(ConfigError::ValidationFailed(
+                    format!("Invalid log level for {}: {}", module, level),
+                ));
+            }
+        }
+
+        if config.logging.file_path.is_some() && (config.logging.max_file_size == 0 || config.logging.max_files == 0) {
+            return Err(ConfigError::ValidationFailed(
+                "Log file size and number of log files must be greater than 0".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
@@ -179,8 +262,24 @@ impl ConfigWatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::SyncMode;
     use tempfile::tempdir;
 
+    /// Default configuration with one section replaced by `partial`, after a save and load
+    fn with_partial_section(path: &[&str], partial: &str) -> AppConfig {
+        let mut config = toml::Value::try_from(AppConfig::default()).unwrap();
+        let section = path.iter().fold(&mut config, |value, key| value.get_mut(*key).unwrap());
+        *section = toml::from_str(partial).unwrap();
+
+        let config: AppConfig = config.try_into().unwrap();
+        toml::from_str(&toml::to_string(&config).unwrap()).unwrap()
+    }
+
+    /// Compare config sections, which do not implement `PartialEq`
+    fn assert_same<T: serde::Serialize>(actual: &T, expected: &T) {
+        assert_eq!(serde_json::to_value(actual).unwrap(), serde_json::to_value(expected).unwrap());
+    }
+
     #[tokio::test]
     async fn test_config_manager_creation() {
         let temp_dir = tempdir().unwrap();
@@ -214,4 +313,96 @@ mod tests {
         config.network.listen_port = 0;
         assert!(ConfigManager::validate_config(&config).is_err());
     }
+
+    #[tokio::test]
+    async fn test_poll_interval_validation() {
+        let mut config = AppConfig::default();
+        config.clipboard.poll_interval_millis = 500;
+        config.clipboard.max_poll_interval_millis = 100;
+        assert!(ConfigManager::validate_config(&config).is_err());
+
+        config.clipboard.poll_interval_millis = 0;
+        assert!(ConfigManager::validate_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chunk_size_validation() {
+        let mut config = AppConfig::default();
+        config.clipboard.lazy.chunk_size_bytes = 0;
+        assert!(ConfigManager::validate_config(&config).is_err());
+
+        config.clipboard.lazy.chunk_size_bytes = 64 * 1024 * 1024;
+        assert!(ConfigManager::validate_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_listen_address_validation() {
+        let mut config = AppConfig::default();
+        config.metrics.enabled = true;
+        assert!(ConfigManager::validate_config(&config).is_ok());
+
+        config.metrics.listen_address = "[::1]:9464".to_string();
+        assert!(ConfigManager::validate_config(&config).is_ok());
+
+        config.metrics.listen_address = "0.0.0.0:9464".to_string();
+        assert!(ConfigManager::validate_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partial_filter_section() {
+        let config = with_partial_section(&["clipboard", "filters"], "enabled = false");
+        let expected = crate::config::FilterConfig { enabled: false, ..Default::default() };
+        assert_same(&config.clipboard.filters, &expected);
+    }
+
+    #[tokio::test]
+    async fn test_partial_exclusion_section() {
+        let config = with_partial_section(&["clipboard", "exclusions"], "enabled = false");
+        let expected = crate::config::ExclusionConfig { enabled: false, ..Default::default() };
+        assert_same(&config.clipboard.exclusions, &expected);
+    }
+
+    #[tokio::test]
+    async fn test_partial_lazy_transfer_section() {
+        let config = with_partial_section(&["clipboard", "lazy"], "enabled = true");
+        let expected = crate::config::LazyTransferConfig { enabled: true, ..Default::default() };
+        assert_same(&config.clipboard.lazy, &expected);
+    }
+
+    #[tokio::test]
+    async fn test_partial_primary_selection_section() {
+        let config = with_partial_section(&["clipboard", "primary"], "enabled = true");
+        let expected = crate::config::PrimarySelectionConfig { enabled: true, ..Default::default() };
+        assert_same(&config.clipboard.primary, &expected);
+    }
+
+    #[tokio::test]
+    async fn test_partial_history_section() {
+        let config = with_partial_section(&["history"], "enabled = true");
+        let expected = crate::config::HistoryConfig { enabled: true, ..Default::default() };
+        assert_same(&config.history, &expected);
+    }
+
+    #[tokio::test]
+    async fn test_sync_mode_config() {
+        let sync: crate::config::SyncConfig = toml::from_str(
+            r#"
+            mode = "manual"
+            manual_queue_size = 0
+
+            [peers]
+            colleague-laptop = "notify"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(sync.mode_for("12D3KooWPeer", Some("colleague-laptop")), SyncMode::Notify);
+        assert_eq!(sync.mode_for("12D3KooWPeer", None), SyncMode::Manual);
+
+        let config = AppConfig {
+            sync,
+            ..AppConfig::default()
+        };
+        assert!(ConfigManager::validate_config(&config).is_err());
+    }
 }
@@ -7,7 +7,10 @@ pub mod manager;
 
 pub use manager::ConfigManager;
 
+use crate::clipboard::{CompressionCodec, ContentKind, ContentType};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::time::Duration;
 use thiserror::Error;
 
@@ -52,6 +55,22 @@ pub struct AppConfig {
     /// Security configuration
     pub security: SecurityConfig,
 
+    /// Clipboard history configuration
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    /// How this device exchanges items with its peers
+    #[serde(default)]
+    pub sync: SyncConfig,
+
+    /// Event bus queues
+    #[serde(default)]
+    pub events: EventBusConfig,
+
+    /// Metrics collection and the Prometheus/OpenMetrics endpoint
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
     /// Logging configuration
     pub logging: LoggingConfig,
 }
@@ -85,6 +104,11 @@ pub struct NetworkConfig {
 
     /// QUIC port (if different from listen_port)
     pub quic_port: Option<u16>,
+
+    /// Key file giving this device a stable peer ID, created on first start;
+    /// without it the peer ID changes on every start
+    #[serde(default)]
+    pub identity_file: Option<String>,
 }
 
 
@@ -101,6 +125,18 @@ pub struct ClipboardConfig {
     /// Cooldown period in milliseconds to prevent rapid updates
     pub cooldown_millis: u64,
 
+    /// Use native clipboard change notifications where the platform has them
+    #[serde(default = "default_watch_changes")]
+    pub watch_changes: bool,
+
+    /// Polling interval in milliseconds while the clipboard is changing
+    #[serde(default = "default_poll_interval_millis")]
+    pub poll_interval_millis: u64,
+
+    /// Longest polling interval in milliseconds reached when the clipboard is idle
+    #[serde(default = "default_max_poll_interval_millis")]
+    pub max_poll_interval_millis: u64,
+
     /// Maximum content size in bytes
     pub max_content_size: usize,
 
@@ -109,6 +145,343 @@ pub struct ClipboardConfig {
 
     /// Compression threshold in bytes
     pub compression_threshold: usize,
+
+    /// Compression codecs in order of preference; the first one all peers support is used
+    #[serde(default = "default_compression_codecs")]
+    pub compression_codecs: Vec<CompressionCodec>,
+
+    /// Sensitive content filters applied before sync
+    #[serde(default)]
+    pub filters: FilterConfig,
+
+    /// Linux PRIMARY selection (middle-click paste) synchronization
+    #[serde(default)]
+    pub primary: PrimarySelectionConfig,
+
+    /// Announce large items and let peers fetch them on demand
+    #[serde(default)]
+    pub lazy: LazyTransferConfig,
+
+    /// Per-content-type sync policies
+    #[serde(default)]
+    pub policies: PolicyConfig,
+
+    /// Text transformations applied to sent and received items
+    #[serde(default)]
+    pub transforms: TransformConfig,
+
+    /// Applications whose copies are never synced
+    #[serde(default)]
+    pub exclusions: ExclusionConfig,
+
+    /// Handling of items password managers mark as concealed
+    #[serde(default)]
+    pub concealed: ConcealedConfig,
+}
+
+/// Lazy "announce then fetch" transfer configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LazyTransferConfig {
+    /// Broadcast only metadata for large items
+    pub enabled: bool,
+
+    /// Items larger than this many bytes are announced instead of pushed
+    pub threshold_bytes: usize,
+
+    /// Fetch announced items right away instead of waiting for a request
+    pub auto_fetch: bool,
+
+    /// Size limit of the content-addressed cache in bytes
+    pub cache_max_bytes: usize,
+
+    /// Length of the text preview sent with announcements, in characters
+    pub preview_chars: usize,
+
+    /// Size of the chunks items are fetched in; larger items are always announced
+    #[serde(default = "default_chunk_size_bytes")]
+    pub chunk_size_bytes: usize,
+
+    /// Attempts per chunk before a fetch is left to resume later
+    #[serde(default = "default_chunk_retries")]
+    pub chunk_retries: u32,
+
+    /// Delay between chunk attempts in milliseconds
+    #[serde(default = "default_chunk_retry_delay_millis")]
+    pub chunk_retry_delay_millis: u64,
+}
+
+/// Per-content-type sync policy configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// Policies checked in order; the first one matching an item applies
+    #[serde(default)]
+    pub rules: Vec<SyncPolicy>,
+
+    /// Named groups of devices that policies can target
+    #[serde(default)]
+    pub groups: HashMap<String, Vec<String>>,
+
+    /// Device names bound to peer IDs, usable as targets and group members
+    #[serde(default)]
+    pub devices: HashMap<String, String>,
+}
+
+/// Sync policy for items matching a content type, MIME type or kind
+///
+/// Unset matchers match every item. Items no policy matches follow the global
+/// clipboard settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPolicy {
+    /// Policy name used in logs
+    #[serde(default)]
+    pub name: String,
+
+    /// Content type the policy applies to
+    #[serde(default)]
+    pub content_type: Option<ContentType>,
+
+    /// MIME type the policy applies to; `image/*` matches any image type
+    #[serde(default)]
+    pub mime: Option<String>,
+
+    /// Detected content kind the policy applies to
+    #[serde(default)]
+    pub kind: Option<ContentKind>,
+
+    /// Sync matching items at all
+    #[serde(default = "default_policy_enabled")]
+    pub enabled: bool,
+
+    /// Largest matching item synced, in bytes
+    #[serde(default)]
+    pub max_size: Option<usize>,
+
+    /// Whether matching items are sent, received or both
+    #[serde(default)]
+    pub direction: SyncDirection,
+
+    /// Peer IDs, device names bound in `devices` or group names matching items
+    /// are exchanged with; empty means every device
+    #[serde(default)]
+    pub targets: Vec<String>,
+
+    /// Codec used for matching items instead of the negotiated one
+    #[serde(default)]
+    pub compression: Option<CompressionCodec>,
+}
+
+/// Direction items are synced in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    /// Send local items and apply received ones
+    #[default]
+    Both,
+    /// Send local items, ignore received ones
+    SendOnly,
+    /// Apply received items, never send local ones
+    ReceiveOnly,
+}
+
+impl SyncDirection {
+    /// Whether local items are sent
+    pub fn sends(&self) -> bool {
+        !matches!(self, SyncDirection::ReceiveOnly)
+    }
+
+    /// Whether received items are applied
+    pub fn receives(&self) -> bool {
+        !matches!(self, SyncDirection::SendOnly)
+    }
+}
+
+/// Text transformation pipeline configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformConfig {
+    /// Transformations applied in order
+    #[serde(default)]
+    pub steps: Vec<TransformStep>,
+
+    /// Line ending `normalize_line_endings` converts to
+    #[serde(default)]
+    pub line_ending: LineEnding,
+
+    /// Query parameters `strip_tracking_params` removes in addition to the built-in list
+    #[serde(default)]
+    pub tracking_params: Vec<String>,
+}
+
+/// Built-in transformation applied on send, receive or both
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformStep {
+    pub transform: BuiltinTransform,
+
+    /// Whether the transformation applies to sent items, received items or both
+    #[serde(default)]
+    pub direction: SyncDirection,
+}
+
+/// Built-in text transformations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinTransform {
+    /// Remove whitespace at the end of every line and of the text
+    TrimTrailingWhitespace,
+    /// Convert line endings to `line_ending`
+    NormalizeLineEndings,
+    /// Replace typographic quotes with ASCII quotes
+    ReplaceSmartQuotes,
+    /// Remove tracking query parameters such as `utm_source` from URLs
+    StripTrackingParams,
+}
+
+/// Line ending style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// CRLF on Windows, LF elsewhere
+    #[default]
+    Native,
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Line separator for this style on the current platform
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Native if cfg!(windows) => "\r\n",
+            LineEnding::Native | LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Application exclusion list configuration
+///
+/// Copies made while an excluded application has focus are never synced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExclusionConfig {
+    /// Check the focused application on every copy
+    pub enabled: bool,
+
+    /// Process names, matched case-insensitively and without an `.exe` suffix
+    pub processes: Vec<String>,
+
+    /// Window classes, or bundle identifiers on macOS, matched case-insensitively
+    pub window_classes: Vec<String>,
+}
+
+/// Password-manager "concealed" hint configuration
+///
+/// Items carrying the hint are never synced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcealedConfig {
+    /// Skip copies whose hints cannot be read
+    ///
+    /// On Linux the hints are read with `wl-paste` (Wayland) or `xclip` (X11).
+    /// Without them items are synced as not concealed unless this is set.
+    pub skip_unverified: bool,
+}
+
+/// Linux PRIMARY selection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrimarySelectionConfig {
+    /// Monitor and set the PRIMARY selection (Linux only)
+    pub enabled: bool,
+
+    /// Cooldown period in milliseconds; PRIMARY changes on every text selection
+    pub cooldown_millis: u64,
+
+    /// Where received CLIPBOARD items are applied
+    pub clipboard_target: SelectionTarget,
+
+    /// Where received PRIMARY items are applied
+    pub primary_target: SelectionTarget,
+}
+
+/// Local selections a received item is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionTarget {
+    /// The regular clipboard only
+    Clipboard,
+    /// The PRIMARY selection only
+    Primary,
+    /// Both selections
+    Both,
+}
+
+/// Sensitive content filter configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterConfig {
+    /// Enable sensitive content filtering
+    pub enabled: bool,
+
+    /// Built-in detectors and the action taken when they match
+    pub detectors: Vec<DetectorRule>,
+
+    /// Custom regular expression deny rules
+    pub deny_rules: Vec<DenyRule>,
+
+    /// Minimum Shannon entropy (bits per character) flagged by the entropy detector
+    pub entropy_threshold: f64,
+
+    /// Minimum token length considered by the entropy detector
+    pub entropy_min_length: usize,
+
+    /// Lifetime of items synced with the `expire` action, in seconds
+    pub expire_after_secs: u64,
+}
+
+/// Built-in sensitive content detectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveDetector {
+    /// Well-known API key and token formats
+    ApiKey,
+    /// PEM-encoded private keys
+    PrivateKey,
+    /// Credit card numbers passing the Luhn check
+    CreditCard,
+    /// JSON Web Tokens
+    Jwt,
+    /// High-entropy tokens such as generated passwords
+    HighEntropy,
+}
+
+/// Action taken when a filter matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// Replace the matched text before syncing
+    Redact,
+    /// Sync the item, but clear it from clipboards after `expire_after_secs`
+    Expire,
+    /// Do not sync the item at all
+    Block,
+}
+
+/// Built-in detector rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectorRule {
+    pub detector: SensitiveDetector,
+    pub action: FilterAction,
+}
+
+/// Regular expression deny rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenyRule {
+    /// Rule name used in logs
+    pub name: String,
+    /// Regular expression matched against text content
+    pub pattern: String,
+    pub action: FilterAction,
 }
 
 /// Security configuration
@@ -130,6 +503,168 @@ pub struct SecurityConfig {
     pub max_message_age: u64,
 }
 
+/// Clipboard history configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Keep an encrypted local history of clipboard items
+    pub enabled: bool,
+
+    /// Maximum number of items kept in the history
+    pub max_items: usize,
+
+    /// History directory (defaults to the platform data directory)
+    pub directory: Option<String>,
+}
+
+/// Sync mode configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    /// Mode used with every peer that has no override
+    pub mode: SyncMode,
+
+    /// Modes for specific peers, by device name or peer ID
+    #[serde(default)]
+    pub peers: HashMap<String, SyncMode>,
+
+    /// Items kept for a manual push; the oldest is dropped when full
+    pub manual_queue_size: usize,
+}
+
+/// How items are exchanged with a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    /// Send every local copy and apply every received item
+    #[default]
+    Auto,
+    /// Queue local copies until they are pushed explicitly; apply received items
+    Manual,
+    /// Send local copies, ignore received items
+    SendOnly,
+    /// Apply received items, never send local copies
+    ReceiveOnly,
+    /// Send local copies; record received items and report them without applying
+    Notify,
+}
+
+impl SyncMode {
+    /// Whether local copies are sent without an explicit push
+    pub fn sends_automatically(&self) -> bool {
+        matches!(self, SyncMode::Auto | SyncMode::SendOnly | SyncMode::Notify)
+    }
+
+    /// Whether local copies may be sent at all
+    pub fn sends(&self) -> bool {
+        !matches!(self, SyncMode::ReceiveOnly)
+    }
+
+    /// Whether received items are accepted
+    pub fn receives(&self) -> bool {
+        !matches!(self, SyncMode::SendOnly)
+    }
+
+    /// Whether received items are written to the clipboard
+    pub fn applies(&self) -> bool {
+        matches!(self, SyncMode::Auto | SyncMode::Manual | SyncMode::ReceiveOnly)
+    }
+}
+
+impl fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncMode::Auto => write!(f, "auto"),
+            SyncMode::Manual => write!(f, "manual"),
+            SyncMode::SendOnly => write!(f, "send-only"),
+            SyncMode::ReceiveOnly => write!(f, "receive-only"),
+            SyncMode::Notify => write!(f, "notify"),
+        }
+    }
+}
+
+/// Event bus configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventBusConfig {
+    /// Maximum number of events waiting per subscriber
+    pub queue_capacity: usize,
+
+    /// What happens when an event is emitted to a full queue
+    pub overflow: OverflowPolicy,
+
+    /// Journal of emitted events, for reproducing sync problems
+    pub journal: JournalConfig,
+}
+
+/// Event journal configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Write every emitted event to the journal
+    pub enabled: bool,
+
+    /// Journal directory (defaults to the platform data directory)
+    pub directory: Option<String>,
+
+    /// Size in bytes at which the journal file is rotated
+    pub max_file_size: u64,
+
+    /// Number of journal files to keep, including the current one
+    pub max_files: usize,
+
+    /// How clipboard payloads are written
+    pub payloads: PayloadMode,
+}
+
+/// How clipboard payloads appear in the event journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadMode {
+    /// Leave payloads, previews and file names out
+    #[default]
+    Redact,
+    /// Replace payloads, previews, file names and checksums with an HMAC-SHA-256 digest
+    ///
+    /// The key is kept in `journal.key` next to the journal; whoever can read
+    /// it can still confirm guesses of short secrets such as passwords.
+    Hash,
+}
+
+/// Handling of events emitted to a full queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drop the oldest event of the lowest priority, which may be the new event
+    #[default]
+    DropOldestLowest,
+    /// Keep the new event out and return an error to the emitter
+    Reject,
+    /// Make the emitter wait until the subscriber takes an event
+    ///
+    /// An emitter that waits on a queue only it, or a task it is waiting on,
+    /// would drain never wakes up. So the crate's own components, emits from
+    /// registered handlers and the shared `poll_event` queue drop like
+    /// `DropOldestLowest` instead, and only other emitters wait. Code that
+    /// subscribes to the bus should emit with `EventBus::emit_without_waiting`.
+    Block,
+}
+
+/// Metrics configuration
+///
+/// The endpoint is only available when built with the `metrics-exporter` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Serve metrics in OpenMetrics text format at `/metrics`
+    pub enabled: bool,
+
+    /// Loopback address to listen on, e.g. "127.0.0.1:9464"
+    pub listen_address: String,
+
+    /// Seconds of recent durations that percentiles are computed over
+    pub window_secs: u64,
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -147,6 +682,14 @@ pub struct LoggingConfig {
 
     /// Number of log files to keep
     pub max_files: usize,
+
+    /// Also log to stdout when logging to a file
+    #[serde(default = "default_log_stdout")]
+    pub stdout: bool,
+
+    /// Levels of individual modules, e.g. `libp2p = "warn"`
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
 }
 
 impl Default for AppConfig {
@@ -161,6 +704,10 @@ impl Default for AppConfig {
             network: NetworkConfig::default(),
             clipboard: ClipboardConfig::default(),
             security: SecurityConfig::default(),
+            history: HistoryConfig::default(),
+            sync: SyncConfig::default(),
+            events: EventBusConfig::default(),
+            metrics: MetricsConfig::default(),
             logging: LoggingConfig::default(),
         }
     }
@@ -178,6 +725,7 @@ impl Default for NetworkConfig {
             idle_connection_timeout: 300,  // 5 minutes
             enable_quic: false,  // TCP only by default
             quic_port: None,
+            identity_file: None,
         }
     }
 }
@@ -188,9 +736,85 @@ impl Default for ClipboardConfig {
             sync_images: true,
             sync_files: false,
             cooldown_millis: 300,
+            watch_changes: default_watch_changes(),
+            poll_interval_millis: default_poll_interval_millis(),
+            max_poll_interval_millis: default_max_poll_interval_millis(),
             max_content_size: 10 * 1024 * 1024, // 10MB
             enable_compression: true,
             compression_threshold: 1024, // 1KB
+            compression_codecs: default_compression_codecs(),
+            filters: FilterConfig::default(),
+            primary: PrimarySelectionConfig::default(),
+            lazy: LazyTransferConfig::default(),
+            policies: PolicyConfig::default(),
+            transforms: TransformConfig::default(),
+            exclusions: ExclusionConfig::default(),
+            concealed: ConcealedConfig::default(),
+        }
+    }
+}
+
+impl Default for ExclusionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            processes: ["1password", "bitwarden", "keepassxc", "keepass", "lastpass", "dashlane", "enpass"]
+                .map(String::from)
+                .to_vec(),
+            window_classes: ["com.1password.1password", "com.bitwarden.desktop", "org.keepassxc.keepassxc"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl Default for LazyTransferConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_bytes: 1024 * 1024, // 1MB
+            auto_fetch: false,
+            cache_max_bytes: 256 * 1024 * 1024, // 256MB
+            preview_chars: 200,
+            chunk_size_bytes: default_chunk_size_bytes(),
+            chunk_retries: default_chunk_retries(),
+            chunk_retry_delay_millis: default_chunk_retry_delay_millis(),
+        }
+    }
+}
+
+impl LazyTransferConfig {
+    /// Delay between chunk attempts
+    pub fn chunk_retry_delay(&self) -> Duration {
+        Duration::from_millis(self.chunk_retry_delay_millis)
+    }
+}
+
+impl Default for PrimarySelectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cooldown_millis: 500,
+            clipboard_target: SelectionTarget::Clipboard,
+            primary_target: SelectionTarget::Primary,
+        }
+    }
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            detectors: vec![
+                DetectorRule { detector: SensitiveDetector::ApiKey, action: FilterAction::Block },
+                DetectorRule { detector: SensitiveDetector::PrivateKey, action: FilterAction::Block },
+                DetectorRule { detector: SensitiveDetector::CreditCard, action: FilterAction::Redact },
+                DetectorRule { detector: SensitiveDetector::Jwt, action: FilterAction::Block },
+            ],
+            deny_rules: Vec::new(),
+            entropy_threshold: 4.0,
+            entropy_min_length: 20,
+            expire_after_secs: 60,
         }
     }
 }
@@ -207,6 +831,74 @@ impl Default for SecurityConfig {
     }
 }
 
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_items: 100,
+            directory: None,
+        }
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            mode: SyncMode::Auto,
+            peers: HashMap::new(),
+            manual_queue_size: 20,
+        }
+    }
+}
+
+impl SyncConfig {
+    /// Mode used with a peer, matched by peer ID or the device name from its handshake
+    pub fn mode_for(&self, peer_id: &str, device_name: Option<&str>) -> SyncMode {
+        self.peers
+            .get(peer_id)
+            .or_else(|| device_name.and_then(|name| self.peers.get(name)))
+            .copied()
+            .unwrap_or(self.mode)
+    }
+
+    /// Whether some peers only receive local copies when they are pushed
+    pub fn uses_manual_push(&self) -> bool {
+        self.mode == SyncMode::Manual || self.peers.values().any(|mode| *mode == SyncMode::Manual)
+    }
+}
+
+impl Default for EventBusConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1000,
+            overflow: OverflowPolicy::DropOldestLowest,
+            journal: JournalConfig::default(),
+        }
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            max_file_size: 5 * 1024 * 1024, // 5MB
+            max_files: 5,
+            payloads: PayloadMode::Redact,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_address: "127.0.0.1:9464".to_string(),
+            window_secs: 300,
+        }
+    }
+}
+
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
@@ -215,6 +907,8 @@ impl Default for LoggingConfig {
             structured: false,
             max_file_size: 10 * 1024 * 1024, // 10MB
             max_files: 5,
+            stdout: true,
+            modules: HashMap::new(),
         }
     }
 }
@@ -231,11 +925,107 @@ impl NetworkConfig {
     }
 }
 
+fn default_log_stdout() -> bool {
+    true
+}
+
+fn default_watch_changes() -> bool {
+    true
+}
+
+fn default_poll_interval_millis() -> u64 {
+    100
+}
+
+fn default_max_poll_interval_millis() -> u64 {
+    1000
+}
+
+fn default_compression_codecs() -> Vec<CompressionCodec> {
+    vec![CompressionCodec::Zstd, CompressionCodec::Lz4, CompressionCodec::Gzip]
+}
+
+fn default_policy_enabled() -> bool {
+    true
+}
+
+fn default_chunk_size_bytes() -> usize {
+    1024 * 1024 // 1MB
+}
+
+fn default_chunk_retries() -> u32 {
+    5
+}
+
+fn default_chunk_retry_delay_millis() -> u64 {
+    2000
+}
+
 impl ClipboardConfig {
     /// Get cooldown period as Duration
     pub fn cooldown_duration(&self) -> Duration {
         Duration::from_millis(self.cooldown_millis)
     }
+
+    /// Get the active polling interval as Duration
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_millis)
+    }
+
+    /// Get the idle polling interval as Duration
+    pub fn max_poll_interval(&self) -> Duration {
+        Duration::from_millis(self.max_poll_interval_millis)
+    }
+
+    /// Most preferred codec this build supports
+    pub fn preferred_codec(&self) -> CompressionCodec {
+        CompressionCodec::negotiate(&self.compression_codecs, [])
+    }
+}
+
+impl PrimarySelectionConfig {
+    /// Get PRIMARY cooldown period as Duration
+    pub fn cooldown_duration(&self) -> Duration {
+        Duration::from_millis(self.cooldown_millis)
+    }
+}
+
+impl FilterConfig {
+    /// Get expiry period as Duration
+    pub fn expire_after_duration(&self) -> Duration {
+        Duration::from_secs(self.expire_after_secs)
+    }
+}
+
+impl HistoryConfig {
+    /// Get the history directory, falling back to the platform data directory
+    pub fn directory_path(&self) -> Result<std::path::PathBuf> {
+        match &self.directory {
+            Some(directory) => Ok(std::path::PathBuf::from(directory)),
+            None => crate::utils::platform::get_data_dir()
+                .map(|path| path.join("history"))
+                .map_err(|e| ConfigError::ValidationFailed(e.to_string())),
+        }
+    }
+}
+
+impl JournalConfig {
+    /// Get the journal directory, falling back to the platform data directory
+    pub fn directory_path(&self) -> Result<std::path::PathBuf> {
+        match &self.directory {
+            Some(directory) => Ok(std::path::PathBuf::from(directory)),
+            None => crate::utils::platform::get_data_dir()
+                .map(|path| path.join("journal"))
+                .map_err(|e| ConfigError::ValidationFailed(e.to_string())),
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Get the percentile window as Duration
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
 }
 
 impl SecurityConfig {
@@ -34,6 +34,19 @@ impl EncryptionService {
         Ok(Self::new(&key))
     }
 
+    /// Create encryption service for data at rest from configuration and a salt
+    ///
+    /// Uses PBKDF2-HMAC-SHA256 so that the key protecting local storage
+    /// differs from the transport key derived by [`EncryptionService::from_config`].
+    pub fn from_config_with_salt(config: &SecurityConfig, salt: &[u8], iterations: u32) -> Result<Self> {
+        if config.secret_key.is_empty() {
+            return Err(CryptoError::KeyDerivationFailed("Secret key is empty".to_string()));
+        }
+
+        let key = pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(config.secret_key.as_bytes(), salt, iterations);
+        Ok(Self::new(&key))
+    }
+
     /// Encrypt clipboard content
     pub fn encrypt_content(&self, content: &ClipboardContent) -> Result<Vec<u8>> {
         let serialized = serde_json::to_vec(content)
@@ -103,6 +116,13 @@ impl EncryptionService {
         key
     }
 
+    /// Generate a random salt for key derivation
+    pub fn generate_salt() -> [u8; 16] {
+        let mut salt = [0u8; 16];
+        thread_rng().fill_bytes(&mut salt);
+        salt
+    }
+
     /// Get the current encryption key (for key rotation)
     pub fn get_key(&self) -> &[u8; 32] {
         &self.key
@@ -145,6 +165,24 @@ mod tests {
         assert_ne!(key1, different_key);
     }
 
+    #[test]
+    fn test_salted_key_derivation() {
+        let config = SecurityConfig::default();
+        let salt = EncryptionService::generate_salt();
+
+        let service1 = EncryptionService::from_config_with_salt(&config, &salt, 1000).unwrap();
+        let service2 = EncryptionService::from_config_with_salt(&config, &salt, 1000).unwrap();
+        assert_eq!(service1.get_key(), service2.get_key());
+
+        // Keys for data at rest must differ from the transport key
+        let transport = EncryptionService::from_config(&config).unwrap();
+        assert_ne!(service1.get_key(), transport.get_key());
+
+        let other_salt = EncryptionService::generate_salt();
+        let service3 = EncryptionService::from_config_with_salt(&config, &other_salt, 1000).unwrap();
+        assert_ne!(service1.get_key(), service3.get_key());
+    }
+
     #[test]
     fn test_invalid_data_decryption() {
         let key = EncryptionService::generate_random_key();
@@ -1,6 +1,6 @@
 //! Key management implementation
 
-use crate::crypto::{CryptoError, Result};
+use crate::crypto::Result;
 use log::{debug, info, warn};
 use std::time::{Duration, Instant};
 
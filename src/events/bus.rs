@@ -1,23 +1,129 @@
 //! Event bus implementation
 
-use crate::events::{Event, EventError, EventPriority, EventWithMetadata, Result};
-use log::{debug, error, warn};
-use std::collections::VecDeque;
-use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
-use tokio::time::{Duration, Instant};
-
-/// Maximum number of events in the queue
-const MAX_QUEUE_SIZE: usize = 1000;
+use crate::config::{EventBusConfig, OverflowPolicy};
+use crate::events::dispatch::{self, AsyncEventHandler, BlockingHandler, HandlerOptions, HandlerStats, PendingEvents};
+use crate::events::queue::{Offer, PriorityQueue};
+use crate::events::{Event, EventError, EventFilter, EventPriority, EventWithMetadata, Result};
+use futures::Stream;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Waker};
+use tokio::sync::Notify;
 
 /// Event bus for inter-module communication
 pub struct EventBus {
-    /// Event queue with priority ordering
-    queue: Arc<Mutex<VecDeque<EventWithMetadata>>>,
-    /// Event handlers
-    handlers: Arc<RwLock<Vec<Box<dyn EventHandler + Send + Sync>>>>,
+    /// Events for `poll_event`
+    queue: Subscription,
+    /// Set once `poll_event` is called; the shared queue is then filled even
+    /// while the bus has subscribers
+    polled: AtomicBool,
+    /// Queue capacity and overflow policy
+    config: EventBusConfig,
+    /// Events delivered to registered handlers and not finished yet
+    pending: Arc<PendingEvents>,
     /// Bus statistics
-    stats: Arc<RwLock<EventBusStats>>,
+    stats: Arc<StatsCounters>,
+    /// Queues of the streams returned by `subscribe`
+    subscriptions: std::sync::Mutex<Vec<Weak<Subscription>>>,
+}
+
+/// Events waiting for one subscriber
+#[derive(Default)]
+struct Subscription {
+    state: std::sync::Mutex<SubscriptionState>,
+    /// Notified when an event is taken, for emitters waiting for room
+    space: Notify,
+    /// Events the subscriber receives
+    filter: EventFilter,
+    /// Counter of undelivered events, for subscriptions feeding a handler
+    pending: Option<Arc<AtomicUsize>>,
+}
+
+#[derive(Default)]
+struct SubscriptionState {
+    queue: PriorityQueue,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl Subscription {
+    /// Queue an event within `capacity`, waking the subscriber
+    fn offer(&self, event: EventWithMetadata, capacity: usize, overflow: OverflowPolicy) -> Offer {
+        let mut state = self.lock();
+        let offer = state.queue.offer(event, capacity, overflow);
+
+        if let Offer::Queued = offer {
+            if let Some(pending) = &self.pending {
+                pending.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        offer
+    }
+
+    fn pop(&self) -> Option<EventWithMetadata> {
+        let event = self.lock().queue.pop();
+        if event.is_some() {
+            self.space.notify_waiters();
+        }
+        event
+    }
+
+    fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        drop(state);
+        self.space.notify_waiters();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SubscriptionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Stream of the events emitted after subscribing, highest priority first
+///
+/// The stream ends when the bus is dropped.
+pub struct EventStream {
+    subscription: Arc<Subscription>,
+}
+
+impl Stream for EventStream {
+    type Item = EventWithMetadata;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.subscription.lock();
+
+        if let Some(event) = state.queue.pop() {
+            drop(state);
+            self.subscription.space.notify_waiters();
+            Poll::Ready(Some(event))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // Emitters blocked on this queue give up once it has no reader
+        self.subscription.close();
+    }
 }
 
 /// Event handler trait
@@ -35,20 +141,75 @@ pub struct EventBusStats {
     pub events_emitted: u64,
     pub events_processed: u64,
     pub events_dropped: u64,
+    /// Events refused because a queue was full under the reject policy
+    pub events_rejected: u64,
+    /// Events no subscription's filter passed, so nobody received them
+    pub events_unmatched: u64,
     pub queue_size: usize,
     pub max_queue_size: usize,
+    /// Outcomes per registered handler, by handler name
+    pub handlers: HashMap<String, HandlerStats>,
+}
+
+/// Bus statistics updated without taking a lock per counter
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    emitted: AtomicU64,
+    pub(crate) processed: AtomicU64,
+    dropped: AtomicU64,
+    rejected: AtomicU64,
+    unmatched: AtomicU64,
+    max_queue_size: AtomicUsize,
+    handlers: std::sync::Mutex<HashMap<String, HandlerStats>>,
+}
+
+impl StatsCounters {
+    pub(crate) fn lock_handlers(&self) -> std::sync::MutexGuard<'_, HashMap<String, HandlerStats>> {
+        self.handlers.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl EventBus {
-    /// Create a new event bus
+    /// Create a new event bus with the default queue capacity and overflow policy
     pub fn new() -> Self {
+        Self::with_config(EventBusConfig::default())
+    }
+
+    /// Create a new event bus with the given queue capacity and overflow policy
+    pub fn with_config(config: EventBusConfig) -> Self {
         Self {
-            queue: Arc::new(Mutex::new(VecDeque::new())),
-            handlers: Arc::new(RwLock::new(Vec::new())),
-            stats: Arc::new(RwLock::new(EventBusStats::default())),
+            queue: Subscription::default(),
+            polled: AtomicBool::new(false),
+            config,
+            pending: Arc::new(PendingEvents::default()),
+            stats: Arc::new(StatsCounters::default()),
+            subscriptions: std::sync::Mutex::new(Vec::new()),
         }
     }
 
+    /// Subscribe to all events emitted from now on
+    ///
+    /// Every subscriber receives its own copy of each event.
+    pub fn subscribe(&self) -> EventStream {
+        self.subscribe_filtered(EventFilter::new())
+    }
+
+    /// Subscribe to the events emitted from now on that pass `filter`
+    pub fn subscribe_filtered(&self, filter: EventFilter) -> EventStream {
+        self.add_subscription(Subscription {
+            filter,
+            ..Subscription::default()
+        })
+    }
+
+    /// Number of live subscriptions
+    pub fn subscriber_count(&self) -> usize {
+        self.lock_subscriptions()
+            .iter()
+            .filter(|subscription| subscription.strong_count() > 0)
+            .count()
+    }
+
     /// Emit an event to the bus
     pub async fn emit(&self, event: Event) -> Result<()> {
         self.emit_with_priority(event, EventPriority::Normal).await
@@ -58,135 +219,263 @@ impl EventBus {
     pub async fn emit_with_priority(&self, event: Event, priority: EventPriority) -> Result<()> {
         let event_with_metadata = EventWithMetadata::new(event, "system".to_string())
             .with_priority(priority);
+        self.emit_with_metadata(event_with_metadata).await
+    }
 
-        let mut queue = self.queue.lock().await;
-        
-        // Check queue size limit
-        if queue.len() >= MAX_QUEUE_SIZE {
-            // Drop lowest priority event if queue is full
-            if let Some(dropped_event) = self.drop_lowest_priority_event(&mut queue) {
-                warn!("Event queue full, dropped event: {}", dropped_event.event);
-                self.increment_dropped_count().await;
-            } else {
-                return Err(EventError::BusFull);
+    /// Emit an event with its source and priority already set
+    ///
+    /// With the reject policy the event is not queued for subscribers whose
+    /// queue is full and [`EventError::BusFull`] is returned; with the block
+    /// policy this waits until they have room, except when called from a
+    /// registered handler, which would otherwise wait on its own queue.
+    pub async fn emit_with_metadata(&self, event_with_metadata: EventWithMetadata) -> Result<()> {
+        let Some((recipients, shared)) = self.route(&event_with_metadata) else {
+            return Ok(());
+        };
+
+        let wait = !dispatch::in_handler();
+        let mut result = Ok(());
+        for subscription in &recipients {
+            if let Err(e) = self.enqueue(subscription, event_with_metadata.clone(), wait).await {
+                result = Err(e);
+            }
+        }
+        if shared {
+            if let Err(e) = self.enqueue(&self.queue, event_with_metadata, false).await {
+                result = Err(e);
             }
         }
+        result
+    }
 
-        // Insert event in priority order
-        let insert_pos = queue
-            .iter()
-            .position(|e| e.priority < event_with_metadata.priority)
-            .unwrap_or(queue.len());
-        
-        queue.insert(insert_pos, event_with_metadata);
-        
-        // Update statistics
-        self.increment_emitted_count().await;
-        self.update_queue_size(queue.len()).await;
-
-        debug!("Event emitted, queue size: {}", queue.len());
-        Ok(())
+    /// Emit an event without waiting for room in full queues
+    ///
+    /// Under the block policy an event that does not fit is handled as with
+    /// the drop policy. Use this from tasks that subscribe to the bus, or that
+    /// a subscriber waits on: blocking there can wait on a queue only that
+    /// subscriber would drain.
+    pub fn emit_without_waiting(&self, event_with_metadata: EventWithMetadata) -> Result<()> {
+        let Some((recipients, shared)) = self.route(&event_with_metadata) else {
+            return Ok(());
+        };
+
+        let mut result = Ok(());
+        for subscription in &recipients {
+            if let Err(e) = self.offer(subscription, event_with_metadata.clone(), false) {
+                result = Err(e);
+            }
+        }
+        if shared {
+            if let Err(e) = self.offer(&self.queue, event_with_metadata, false) {
+                result = Err(e);
+            }
+        }
+        result
     }
 
-    /// Poll for the next event
+    /// Poll for the next event from the shared queue
+    ///
+    /// Each event is returned to one caller only. Once this has been called
+    /// the shared queue receives every event, alongside the subscribers;
+    /// before that, events emitted while the bus has subscribers are not
+    /// queued here.
     pub async fn poll_event(&self) -> Option<Event> {
-        let mut queue = self.queue.lock().await;
-        
-        if let Some(event_with_metadata) = queue.pop_front() {
-            self.increment_processed_count().await;
-            self.update_queue_size(queue.len()).await;
-            
-            debug!("Event polled: {}", event_with_metadata.event);
-            Some(event_with_metadata.event)
-        } else {
-            None
-        }
+        self.polled.store(true, Ordering::Release);
+        let event_with_metadata = self.queue.pop()?;
+        self.stats.processed.fetch_add(1, Ordering::Relaxed);
+
+        debug!("Event polled: {}", event_with_metadata.event);
+        Some(event_with_metadata.event)
     }
 
     /// Register an event handler
+    ///
+    /// The handler runs automatically for every event emitted from now on,
+    /// with the default [`HandlerOptions`].
     pub async fn register_handler(&self, handler: Box<dyn EventHandler + Send + Sync>) -> Result<()> {
-        let mut handlers = self.handlers.write().await;
+        self.register_async_handler(Arc::new(BlockingHandler(handler)), HandlerOptions::default())
+            .await
+    }
+
+    /// Register an async event handler with a timeout and concurrency limit
+    ///
+    /// Must be called within a Tokio runtime. A handler that fails, panics or
+    /// times out is counted in its [`HandlerStats`] and keeps receiving events.
+    pub async fn register_async_handler(
+        &self,
+        handler: Arc<dyn AsyncEventHandler>,
+        options: HandlerOptions,
+    ) -> Result<()> {
+        if options.max_concurrency == 0 || options.timeout.is_zero() {
+            return Err(EventError::HandlerRegistrationFailed(format!(
+                "handler '{}' needs a non-zero timeout and concurrency",
+                handler.name()
+            )));
+        }
+
         debug!("Registering event handler: {}", handler.name());
-        handlers.push(handler);
+        self.stats
+            .lock_handlers()
+            .entry(handler.name().to_string())
+            .or_default();
+
+        let events = self.add_subscription(Subscription {
+            filter: options.filter.clone(),
+            pending: Some(self.pending.counter()),
+            ..Subscription::default()
+        });
+        dispatch::spawn_dispatcher(handler, options, events, self.stats.clone(), self.pending.clone());
         Ok(())
     }
 
-    /// Process events with registered handlers
+    /// Wait until registered handlers have finished the events emitted so far
+    ///
+    /// Handlers run on their own; events queued before the first subscriber
+    /// was added are handed to the subscribers first, unless `poll_event`
+    /// reads them.
     pub async fn process_events(&self) -> Result<()> {
-        while let Some(event) = self.poll_event().await {
-            let handlers = self.handlers.read().await;
-            
-            for handler in handlers.iter() {
-                if let Err(e) = handler.handle(&event) {
-                    error!("Handler '{}' failed to process event {}: {}", 
-                           handler.name(), event, e);
+        if !self.feeds_shared_queue(self.subscriber_count()) {
+            let wait = !dispatch::in_handler();
+            while let Some(event) = self.queue.pop() {
+                for subscription in self.recipients(&event).1 {
+                    self.enqueue(&subscription, event.clone(), wait).await?;
                 }
             }
         }
-        
+
+        self.pending.wait_idle().await;
         Ok(())
     }
 
     /// Get current queue size
     pub async fn queue_size(&self) -> usize {
-        self.queue.lock().await.len()
+        self.queue.len()
     }
 
     /// Get event bus statistics
     pub async fn get_stats(&self) -> EventBusStats {
-        self.stats.read().await.clone()
+        EventBusStats {
+            events_emitted: self.stats.emitted.load(Ordering::Relaxed),
+            events_processed: self.stats.processed.load(Ordering::Relaxed),
+            events_dropped: self.stats.dropped.load(Ordering::Relaxed),
+            events_rejected: self.stats.rejected.load(Ordering::Relaxed),
+            events_unmatched: self.stats.unmatched.load(Ordering::Relaxed),
+            queue_size: self.queue.len(),
+            max_queue_size: self.stats.max_queue_size.load(Ordering::Relaxed),
+            handlers: self.stats.lock_handlers().clone(),
+        }
     }
 
     /// Clear all events from the queue
     pub async fn clear(&self) -> usize {
-        let mut queue = self.queue.lock().await;
-        let cleared_count = queue.len();
-        queue.clear();
-        self.update_queue_size(0).await;
+        let cleared_count = self.queue.lock().queue.clear();
+        self.queue.space.notify_waiters();
         cleared_count
     }
 
-    /// Drop the lowest priority event from the queue
-    fn drop_lowest_priority_event(&self, queue: &mut VecDeque<EventWithMetadata>) -> Option<EventWithMetadata> {
-        if queue.is_empty() {
-            return None;
-        }
+    /// Live subscriptions whose filter the event passes, with the number of live subscriptions
+    fn recipients(&self, event: &EventWithMetadata) -> (usize, Vec<Arc<Subscription>>) {
+        let mut subscriptions = self.lock_subscriptions();
+        subscriptions.retain(|subscription| subscription.strong_count() > 0);
+
+        let recipients = subscriptions
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|subscription| subscription.filter.matches(event))
+            .collect();
+        (subscriptions.len(), recipients)
+    }
 
-        // Find the event with the lowest priority
-        let mut lowest_priority = EventPriority::Critical;
-        let mut lowest_index = 0;
+    /// Subscriptions an emitted event goes to and whether the shared queue
+    /// takes it, or `None` when nothing takes it
+    fn route(&self, event: &EventWithMetadata) -> Option<(Vec<Arc<Subscription>>, bool)> {
+        self.stats.emitted.fetch_add(1, Ordering::Relaxed);
 
-        for (index, event) in queue.iter().enumerate() {
-            if event.priority <= lowest_priority {
-                lowest_priority = event.priority;
-                lowest_index = index;
-            }
+        let (subscribers, recipients) = self.recipients(event);
+        let shared = self.feeds_shared_queue(subscribers);
+        if recipients.is_empty() && !shared {
+            debug!("No subscription takes event: {}", event.event);
+            self.stats.unmatched.fetch_add(1, Ordering::Relaxed);
+            return None;
         }
 
-        queue.remove(lowest_index)
+        debug!("Event emitted to {} of {} subscriptions", recipients.len(), subscribers);
+        Some((recipients, shared))
+    }
+
+    /// Whether emitted events go to the shared queue
+    ///
+    /// It is only filled while nobody subscribes or once `poll_event` reads
+    /// it, so unread events do not pile up. Nothing is known to drain it, so
+    /// emitters never wait on it.
+    fn feeds_shared_queue(&self, subscribers: usize) -> bool {
+        subscribers == 0 || self.polled.load(Ordering::Acquire)
     }
 
-    async fn increment_emitted_count(&self) {
-        let mut stats = self.stats.write().await;
-        stats.events_emitted += 1;
+    /// Queue an event for one subscriber, applying the overflow policy
+    ///
+    /// Under the block policy this waits for room if `wait` is set and drops
+    /// like the drop policy otherwise.
+    async fn enqueue(&self, subscription: &Subscription, mut event: EventWithMetadata, wait: bool) -> Result<()> {
+        loop {
+            // Registered before offering, so an event taken meanwhile is not missed
+            let space = subscription.space.notified();
+
+            match self.offer(subscription, event, wait)? {
+                Some(full) => {
+                    event = full;
+                    space.await;
+                }
+                None => return Ok(()),
+            }
+        }
     }
 
-    async fn increment_processed_count(&self) {
-        let mut stats = self.stats.write().await;
-        stats.events_processed += 1;
+    /// Offer an event to one subscriber, returning it if it has to wait for room
+    fn offer(
+        &self,
+        subscription: &Subscription,
+        event: EventWithMetadata,
+        wait: bool,
+    ) -> Result<Option<EventWithMetadata>> {
+        let overflow = match self.config.overflow {
+            OverflowPolicy::Block if !wait => OverflowPolicy::DropOldestLowest,
+            overflow => overflow,
+        };
+
+        match subscription.offer(event, self.config.queue_capacity, overflow) {
+            Offer::Queued => {
+                self.stats.max_queue_size.fetch_max(subscription.len(), Ordering::Relaxed);
+                Ok(None)
+            }
+            Offer::Dropped(dropped) => {
+                warn!("Event queue full, dropped event: {}", dropped.event);
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+            Offer::Full(full) if overflow == OverflowPolicy::Block => {
+                // Nobody will take an event from a closed queue
+                if subscription.lock().closed {
+                    self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(None);
+                }
+                Ok(Some(full))
+            }
+            Offer::Full(_) => {
+                self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(EventError::BusFull)
+            }
+        }
     }
 
-    async fn increment_dropped_count(&self) {
-        let mut stats = self.stats.write().await;
-        stats.events_dropped += 1;
+    fn add_subscription(&self, subscription: Subscription) -> EventStream {
+        let subscription = Arc::new(subscription);
+        self.lock_subscriptions().push(Arc::downgrade(&subscription));
+        EventStream { subscription }
     }
 
-    async fn update_queue_size(&self, size: usize) {
-        let mut stats = self.stats.write().await;
-        stats.queue_size = size;
-        if size > stats.max_queue_size {
-            stats.max_queue_size = size;
-        }
+    fn lock_subscriptions(&self) -> std::sync::MutexGuard<'_, Vec<Weak<Subscription>>> {
+        self.subscriptions.lock().unwrap_or_else(|e| e.into_inner())
     }
 }
 
@@ -196,6 +485,14 @@ impl Default for EventBus {
     }
 }
 
+impl Drop for EventBus {
+    fn drop(&mut self) {
+        for subscription in self.lock_subscriptions().iter().filter_map(Weak::upgrade) {
+            subscription.close();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,6 +530,342 @@ mod tests {
         assert_eq!(bus.queue_size().await, 0);
     }
 
+    #[tokio::test]
+    async fn test_handler_registration() {
+        let bus = EventBus::new();
+        let handler = TestHandler {
+            name: "test".to_string(),
+        };
+
+        bus.register_handler(Box::new(handler)).await.unwrap();
+        bus.emit(Event::Shutdown).await.unwrap();
+        bus.process_events().await.unwrap();
+
+        assert_eq!(bus.queue_size().await, 0);
+        assert_eq!(bus.get_stats().await.events_processed, 1);
+    }
+
+    struct SlowHandler {
+        name: &'static str,
+        delay: std::time::Duration,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    impl SlowHandler {
+        fn new(name: &'static str, delay_ms: u64) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                delay: std::time::Duration::from_millis(delay_ms),
+                running: AtomicUsize::new(0),
+                max_running: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncEventHandler for SlowHandler {
+        async fn handle(&self, event: &Event) -> Result<()> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            match event {
+                Event::Error { error } if error == "panic" => panic!("handler panicked"),
+                Event::Error { error } => Err(EventError::ProcessingFailed(error.clone())),
+                _ => Ok(()),
+            }
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_handlers_run_without_polling() {
+        let bus = EventBus::new();
+        let handler = SlowHandler::new("slow", 10);
+        bus.register_async_handler(handler.clone(), HandlerOptions::default()).await.unwrap();
+
+        bus.emit(Event::Shutdown).await.unwrap();
+        bus.emit(Event::Error { error: "bad".to_string() }).await.unwrap();
+
+        // Handlers run on their own; wait for them without polling the bus
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while bus.get_stats().await.events_processed < 2 {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("handler did not run");
+
+        let stats = bus.get_stats().await.handlers["slow"].clone();
+        assert_eq!((stats.succeeded, stats.failed), (1, 1));
+        assert!(stats.max_latency >= std::time::Duration::from_millis(10));
+        assert!(stats.average_latency() <= stats.max_latency);
+    }
+
+    #[tokio::test]
+    async fn test_handler_timeouts_and_panics_are_isolated() {
+        let bus = EventBus::new();
+        let slow = SlowHandler::new("slow", 200);
+        let fast = SlowHandler::new("fast", 0);
+        let timeout = HandlerOptions::default().with_timeout(std::time::Duration::from_millis(20));
+        bus.register_async_handler(slow, timeout).await.unwrap();
+        bus.register_async_handler(fast, HandlerOptions::default()).await.unwrap();
+
+        bus.emit(Event::Error { error: "panic".to_string() }).await.unwrap();
+        bus.emit(Event::Shutdown).await.unwrap();
+        bus.process_events().await.unwrap();
+
+        let stats = bus.get_stats().await;
+        assert_eq!(stats.handlers["slow"].timed_out, 2);
+        assert_eq!(stats.handlers["fast"].panicked, 1);
+        assert_eq!(stats.handlers["fast"].succeeded, 1);
+        assert_eq!(stats.events_processed, 4);
+    }
+
+    #[tokio::test]
+    async fn test_handler_concurrency_limit() {
+        let bus = EventBus::new();
+        let serial = SlowHandler::new("serial", 20);
+        let parallel = SlowHandler::new("parallel", 20);
+        bus.register_async_handler(serial.clone(), HandlerOptions::default()).await.unwrap();
+        bus.register_async_handler(parallel.clone(), HandlerOptions::default().with_max_concurrency(3))
+            .await
+            .unwrap();
+
+        for _ in 0..6 {
+            bus.emit(Event::Shutdown).await.unwrap();
+        }
+        bus.process_events().await.unwrap();
+
+        assert_eq!(serial.max_running.load(Ordering::SeqCst), 1);
+        assert!((2..=3).contains(&parallel.max_running.load(Ordering::SeqCst)));
+        assert_eq!(bus.get_stats().await.handlers["parallel"].succeeded, 6);
+
+        let invalid = HandlerOptions::default().with_max_concurrency(0);
+        assert!(bus.register_async_handler(SlowHandler::new("invalid", 0), invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscriptions_and_handlers() {
+        use crate::events::EventKind;
+        use futures::StreamExt;
+
+        let bus = EventBus::new();
+        let mut errors = bus.subscribe_filtered(
+            EventFilter::new().kind(EventKind::Error).min_priority(EventPriority::High),
+        );
+        let synced = SlowHandler::new("synced", 0);
+        let only_synced = EventFilter::new().kind(EventKind::ClipboardSynced).source("network");
+        bus.register_async_handler(synced, HandlerOptions::default().with_filter(only_synced))
+            .await
+            .unwrap();
+
+        let sync = Event::ClipboardSynced { from_peer: "peer".to_string(), content_size: 3 };
+        bus.emit(Event::Error { error: "minor".to_string() }).await.unwrap();
+        bus.emit_with_priority(Event::Error { error: "major".to_string() }, EventPriority::High).await.unwrap();
+        bus.emit(sync.clone()).await.unwrap();
+        bus.emit_with_metadata(EventWithMetadata::new(sync, "network".to_string())).await.unwrap();
+        bus.process_events().await.unwrap();
+
+        assert!(matches!(errors.next().await.unwrap().event, Event::Error { error } if error == "major"));
+        assert_eq!(bus.get_stats().await.handlers["synced"].runs(), 1);
+
+        // Filtered-out events do not fall back to the shared queue
+        assert_eq!(bus.queue_size().await, 0);
+        assert_eq!(bus.get_stats().await.events_emitted, 4);
+        assert_eq!(bus.get_stats().await.events_unmatched, 2);
+    }
+
+    #[tokio::test]
+    async fn test_polling_alongside_subscribers() {
+        use futures::StreamExt;
+
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        assert!(bus.poll_event().await.is_none());
+
+        // Once polled, the shared queue keeps receiving events next to the subscribers
+        bus.emit(Event::Shutdown).await.unwrap();
+        assert!(matches!(bus.poll_event().await, Some(Event::Shutdown)));
+        assert!(matches!(events.next().await.unwrap().event, Event::Shutdown));
+
+        drop(events);
+        bus.emit(Event::Shutdown).await.unwrap();
+        assert!(matches!(bus.poll_event().await, Some(Event::Shutdown)));
+        assert_eq!(bus.get_stats().await.events_unmatched, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_each_receive_every_event() {
+        use futures::StreamExt;
+
+        let bus = EventBus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        bus.emit(Event::ConfigChanged { section: "sync".to_string() }).await.unwrap();
+        bus.emit(Event::Shutdown).await.unwrap();
+
+        for stream in [&mut first, &mut second] {
+            assert!(matches!(stream.next().await.unwrap().event, Event::ConfigChanged { .. }));
+            assert!(matches!(stream.next().await.unwrap().event, Event::Shutdown));
+        }
+
+        // Events delivered to subscribers are not left in the shared queue
+        assert_eq!(bus.queue_size().await, 0);
+
+        drop(second);
+        assert_eq!(bus.subscriber_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_wakes_on_emit_and_keeps_priority_order() {
+        use futures::StreamExt;
+
+        let bus = Arc::new(EventBus::new());
+        let mut events = bus.subscribe();
+
+        // A waiting subscriber is woken by the emit
+        let emitter = bus.clone();
+        let waiting = tokio::spawn(async move { events.next().await.map(|event| (event, events)) });
+        tokio::task::yield_now().await;
+        emitter.emit(Event::Shutdown).await.unwrap();
+        let (event, mut events) = tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .expect("subscriber not woken")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(event.event, Event::Shutdown));
+
+        bus.emit_with_priority(Event::Shutdown, EventPriority::Low).await.unwrap();
+        bus.emit_with_priority(Event::Error { error: "test".to_string() }, EventPriority::Critical).await.unwrap();
+        assert_eq!(events.next().await.unwrap().priority, EventPriority::Critical);
+        assert_eq!(events.next().await.unwrap().priority, EventPriority::Low);
+
+        // The stream ends once the bus is gone
+        drop(emitter);
+        drop(bus);
+        assert!(events.next().await.is_none());
+    }
+
+    fn bounded(queue_capacity: usize, overflow: OverflowPolicy) -> EventBus {
+        EventBus::with_config(EventBusConfig { queue_capacity, overflow, ..EventBusConfig::default() })
+    }
+
+    #[tokio::test]
+    async fn test_overflow_drops_or_rejects() {
+        let dropping = bounded(2, OverflowPolicy::DropOldestLowest);
+        dropping.emit_with_priority(Event::Shutdown, EventPriority::Low).await.unwrap();
+        dropping.emit(Event::Shutdown).await.unwrap();
+        dropping.emit_with_priority(Event::Error { error: "x".to_string() }, EventPriority::High).await.unwrap();
+
+        let stats = dropping.get_stats().await;
+        assert_eq!((stats.events_dropped, stats.queue_size, stats.max_queue_size), (1, 2, 2));
+        assert!(matches!(dropping.poll_event().await, Some(Event::Error { .. })));
+
+        let rejecting = bounded(1, OverflowPolicy::Reject);
+        rejecting.emit(Event::Shutdown).await.unwrap();
+        let full = rejecting.emit_with_priority(Event::Shutdown, EventPriority::Critical).await;
+        assert!(matches!(full, Err(EventError::BusFull)));
+        assert_eq!(rejecting.get_stats().await.events_rejected, 1);
+        assert_eq!(rejecting.queue_size().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_waits_for_room() {
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let bus = Arc::new(bounded(1, OverflowPolicy::Block));
+        let mut events = bus.subscribe();
+        bus.emit(Event::Shutdown).await.unwrap();
+
+        let emitter = bus.clone();
+        let mut blocked = tokio::spawn(async move { emitter.emit(Event::Error { error: "x".to_string() }).await });
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut blocked).await.is_err());
+
+        // Taking an event makes room for the waiting emitter
+        assert!(matches!(events.next().await.unwrap().event, Event::Shutdown));
+        tokio::time::timeout(Duration::from_secs(1), blocked).await.unwrap().unwrap().unwrap();
+        assert!(matches!(events.next().await.unwrap().event, Event::Error { .. }));
+
+        // A dropped subscriber does not keep emitters waiting
+        bus.emit(Event::Shutdown).await.unwrap();
+        let emitter = bus.clone();
+        let blocked = tokio::spawn(async move { emitter.emit(Event::Shutdown).await });
+        tokio::task::yield_now().await;
+        drop(events);
+        tokio::time::timeout(Duration::from_secs(1), blocked).await.unwrap().unwrap().unwrap();
+        assert_eq!(bus.get_stats().await.events_dropped, 1);
+    }
+
+    /// Handler emitting errors back into the bus for every shutdown it sees
+    struct EchoHandler {
+        bus: Arc<EventBus>,
+        echoes: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncEventHandler for EchoHandler {
+        async fn handle(&self, event: &Event) -> Result<()> {
+            if let Event::Shutdown = event {
+                for i in 0..self.echoes {
+                    self.bus.emit(Event::Error { error: i.to_string() }).await?;
+                }
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_does_not_wait_on_own_queue() {
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        // A subscriber emitting into its own full queue drops the oldest event instead
+        let bus = Arc::new(bounded(1, OverflowPolicy::Block));
+        let mut events = bus.subscribe();
+        bus.emit(Event::Shutdown).await.unwrap();
+        let event = EventWithMetadata::new(Event::Error { error: "x".to_string() }, "test".to_string());
+        bus.emit_without_waiting(event).unwrap();
+        assert_eq!(bus.get_stats().await.events_dropped, 1);
+        assert!(matches!(events.next().await.unwrap().event, Event::Error { .. }));
+        drop(events);
+
+        // A handler's emits do not wait on the queue feeding that handler
+        let handler = Arc::new(EchoHandler { bus: bus.clone(), echoes: 3 });
+        let options = HandlerOptions::default().with_max_concurrency(1).with_timeout(Duration::from_secs(5));
+        bus.register_async_handler(handler, options).await.unwrap();
+        bus.emit(Event::Shutdown).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), bus.process_events())
+            .await
+            .expect("handler waited on its own queue")
+            .unwrap();
+        // The shutdown and its echoes were each handled or dropped, next to the earlier drop
+        let stats = bus.get_stats().await;
+        assert_eq!(stats.handlers["echo"].timed_out, 0);
+        assert_eq!(stats.handlers["echo"].succeeded + stats.events_dropped, 1 + 4);
+
+        // Nothing is known to drain the shared queue, so emitters never wait on it
+        let unread = bounded(1, OverflowPolicy::Block);
+        unread.emit(Event::Shutdown).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), unread.emit(Event::Shutdown))
+            .await
+            .expect("emitter waited on the shared queue")
+            .unwrap();
+        assert_eq!(unread.get_stats().await.events_dropped, 1);
+    }
+
     #[tokio::test]
     async fn test_priority_ordering() {
         let bus = EventBus::new();
@@ -0,0 +1,234 @@
+//! Automatic dispatch of events to registered handlers
+//!
+//! Every registered handler reads its own subscription. Each event runs in a
+//! separate task, so a handler that panics or hangs is cut off without
+//! affecting the bus or the other handlers.
+
+use crate::events::bus::{EventHandler, StatsCounters};
+use crate::events::{Event, EventFilter, EventStream, EventWithMetadata, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::{error, warn};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, Semaphore};
+
+/// Default time a handler may take for one event
+pub const DEFAULT_HANDLER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Event handler that may await I/O
+#[async_trait]
+pub trait AsyncEventHandler: Send + Sync {
+    /// Handle an event
+    async fn handle(&self, event: &Event) -> Result<()>;
+
+    /// Handle an event along with its priority, time and source
+    async fn handle_with_metadata(&self, event: &EventWithMetadata) -> Result<()> {
+        self.handle(&event.event).await
+    }
+
+    /// Get handler name for debugging
+    fn name(&self) -> &str;
+}
+
+/// Limits and filter applied to a registered handler
+#[derive(Debug, Clone)]
+pub struct HandlerOptions {
+    /// Longest the handler may take for one event before it is cancelled
+    pub timeout: Duration,
+    /// Events the handler processes at the same time; 1 keeps emit order
+    pub max_concurrency: usize,
+    /// Events the handler runs for
+    pub filter: EventFilter,
+}
+
+impl HandlerOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl Default for HandlerOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_HANDLER_TIMEOUT,
+            max_concurrency: 1,
+            filter: EventFilter::new(),
+        }
+    }
+}
+
+/// Outcomes and latency of one handler
+#[derive(Debug, Default, Clone)]
+pub struct HandlerStats {
+    pub succeeded: u64,
+    pub failed: u64,
+    pub timed_out: u64,
+    pub panicked: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl HandlerStats {
+    /// Events the handler was run for
+    pub fn runs(&self) -> u64 {
+        self.succeeded + self.failed + self.timed_out + self.panicked
+    }
+
+    /// Mean time the handler took per event
+    pub fn average_latency(&self) -> Duration {
+        match self.runs() {
+            0 => Duration::ZERO,
+            runs => self.total_latency / runs as u32,
+        }
+    }
+}
+
+/// Outcome of running a handler for one event
+enum Outcome {
+    Succeeded,
+    Failed,
+    TimedOut,
+    Panicked,
+}
+
+/// Runs a synchronous [`EventHandler`] as an [`AsyncEventHandler`]
+///
+/// The handler runs on the async runtime, so it should not block for long;
+/// a timeout cannot interrupt it while it runs.
+pub(crate) struct BlockingHandler(pub Box<dyn EventHandler + Send + Sync>);
+
+#[async_trait]
+impl AsyncEventHandler for BlockingHandler {
+    async fn handle(&self, event: &Event) -> Result<()> {
+        self.0.handle(event)
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+/// Events delivered to handlers but not finished yet, to wait for them
+#[derive(Default)]
+pub(crate) struct PendingEvents {
+    count: Arc<AtomicUsize>,
+    idle: Notify,
+}
+
+impl PendingEvents {
+    /// Counter incremented for every event queued for a handler
+    pub fn counter(&self) -> Arc<AtomicUsize> {
+        self.count.clone()
+    }
+
+    fn finish(&self) {
+        if self.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+
+    /// Wait until no delivered event is waiting for or running in a handler
+    pub async fn wait_idle(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.count.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+/// Feed a handler's subscription to it until the bus is dropped
+pub(crate) fn spawn_dispatcher(
+    handler: Arc<dyn AsyncEventHandler>,
+    options: HandlerOptions,
+    mut events: EventStream,
+    stats: Arc<StatsCounters>,
+    pending: Arc<PendingEvents>,
+) {
+    let permits = Arc::new(Semaphore::new(options.max_concurrency.max(1)));
+    let timeout = options.timeout;
+
+    tokio::spawn(async move {
+        while let Some(event) = events.next().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let handler = handler.clone();
+            let stats = stats.clone();
+            let pending = pending.clone();
+
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let outcome = run_handler(handler.clone(), event, timeout).await;
+                drop(permit);
+
+                record(&stats, handler.name(), outcome, started.elapsed());
+                pending.finish();
+            });
+        }
+    });
+}
+
+tokio::task_local! {
+    /// Set while a handler runs, so its emits do not wait on its own queue
+    static HANDLING: ();
+}
+
+/// Whether the current task runs a registered handler
+pub(crate) fn in_handler() -> bool {
+    HANDLING.try_with(|_| ()).is_ok()
+}
+
+/// Run a handler in its own task, so a panic is caught and a timeout cancels it
+async fn run_handler(handler: Arc<dyn AsyncEventHandler>, event: EventWithMetadata, timeout: Duration) -> Outcome {
+    let name = handler.name().to_string();
+    let description = event.event.to_string();
+    let mut run = tokio::spawn(HANDLING.scope((), async move { handler.handle_with_metadata(&event).await }));
+
+    match tokio::time::timeout(timeout, &mut run).await {
+        Ok(Ok(Ok(()))) => Outcome::Succeeded,
+        Ok(Ok(Err(e))) => {
+            error!("Handler '{}' failed to process event {}: {}", name, description, e);
+            Outcome::Failed
+        }
+        Ok(Err(e)) => {
+            error!("Handler '{}' panicked processing event {}: {}", name, description, e);
+            Outcome::Panicked
+        }
+        Err(_) => {
+            run.abort();
+            warn!("Handler '{}' timed out after {:?} processing event {}", name, timeout, description);
+            Outcome::TimedOut
+        }
+    }
+}
+
+fn record(stats: &StatsCounters, name: &str, outcome: Outcome, latency: Duration) {
+    stats.processed.fetch_add(1, Ordering::Relaxed);
+
+    let mut handlers = stats.lock_handlers();
+    let handler = handlers.entry(name.to_string()).or_default();
+    match outcome {
+        Outcome::Succeeded => handler.succeeded += 1,
+        Outcome::Failed => handler.failed += 1,
+        Outcome::TimedOut => handler.timed_out += 1,
+        Outcome::Panicked => handler.panicked += 1,
+    }
+    handler.total_latency += latency;
+    handler.max_latency = handler.max_latency.max(latency);
+}
@@ -0,0 +1,177 @@
+//! Event filters for subscriptions and handlers
+//!
+//! An [`EventFilter`] selects events by kind, source and minimum priority, plus
+//! optional predicates. The kind, source and priority parts can be read from
+//! configuration.
+
+use crate::events::{Event, EventKind, EventPriority, EventWithMetadata};
+use serde::Deserialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// Predicate deciding whether an event passes a filter
+pub type EventPredicate = Arc<dyn Fn(&EventWithMetadata) -> bool + Send + Sync>;
+
+/// Selection of events; an empty filter passes every event
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    /// Kinds that pass; empty passes every kind
+    pub kinds: Vec<EventKind>,
+    /// Sources that pass; empty passes every source
+    pub sources: Vec<String>,
+    /// Lowest priority that passes
+    pub min_priority: EventPriority,
+    #[serde(skip)]
+    predicates: Vec<EventPredicate>,
+}
+
+impl EventFilter {
+    /// Create a filter that passes every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also pass events of `kind`
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Also pass events of these kinds
+    pub fn kinds(mut self, kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        self.kinds.extend(kinds);
+        self
+    }
+
+    /// Also pass events from `source`
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    /// Only pass events of at least `priority`
+    pub fn min_priority(mut self, priority: EventPriority) -> Self {
+        self.min_priority = priority;
+        self
+    }
+
+    /// Only pass events for which `predicate` holds
+    pub fn predicate(mut self, predicate: impl Fn(&EventWithMetadata) -> bool + Send + Sync + 'static) -> Self {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only pass events whose [`Event`] satisfies `predicate`
+    pub fn event_predicate(self, predicate: impl Fn(&Event) -> bool + Send + Sync + 'static) -> Self {
+        self.predicate(move |event| predicate(&event.event))
+    }
+
+    /// Check whether the filter passes every event
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+            && self.sources.is_empty()
+            && self.min_priority == EventPriority::Low
+            && self.predicates.is_empty()
+    }
+
+    /// Check whether an event passes the filter
+    pub fn matches(&self, event: &EventWithMetadata) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.event.kind()))
+            && (self.sources.is_empty() || self.sources.contains(&event.source))
+            && event.priority >= self.min_priority
+            && self.predicates.iter().all(|predicate| predicate(event))
+    }
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        Self {
+            kinds: Vec::new(),
+            sources: Vec::new(),
+            min_priority: EventPriority::Low,
+            predicates: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventFilter")
+            .field("kinds", &self.kinds)
+            .field("sources", &self.sources)
+            .field("min_priority", &self.min_priority)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Event, source: &str, priority: EventPriority) -> EventWithMetadata {
+        EventWithMetadata::new(event, source.to_string()).with_priority(priority)
+    }
+
+    fn synced(size: usize) -> Event {
+        Event::ClipboardSynced { from_peer: "peer".to_string(), content_size: size }
+    }
+
+    #[test]
+    fn test_event_kind_names_are_stable() {
+        assert_eq!(synced(1).kind(), EventKind::ClipboardSynced);
+        assert_eq!(Event::Shutdown.kind().to_string(), "shutdown");
+
+        for kind in EventKind::ALL {
+            assert_eq!(kind.as_str().parse::<EventKind>().unwrap(), kind);
+            assert_eq!(serde_json::to_string(&kind).unwrap(), format!("\"{}\"", kind));
+        }
+        assert!("clipboardsynced".parse::<EventKind>().is_err());
+    }
+
+    #[test]
+    fn test_filter_by_kind_source_and_priority() {
+        assert!(EventFilter::new().is_empty());
+        assert!(EventFilter::new().matches(&event(Event::Shutdown, "system", EventPriority::Low)));
+
+        let filter = EventFilter::new()
+            .kinds([EventKind::ClipboardSynced, EventKind::Error])
+            .source("network")
+            .min_priority(EventPriority::Normal);
+
+        assert!(filter.matches(&event(synced(1), "network", EventPriority::High)));
+        assert!(!filter.matches(&event(Event::Shutdown, "network", EventPriority::High)));
+        assert!(!filter.matches(&event(synced(1), "system", EventPriority::High)));
+        assert!(!filter.matches(&event(synced(1), "network", EventPriority::Low)));
+    }
+
+    #[test]
+    fn test_filter_predicates_all_hold() {
+        let filter = EventFilter::new()
+            .kind(EventKind::ClipboardSynced)
+            .event_predicate(|event| matches!(event, Event::ClipboardSynced { content_size, .. } if *content_size > 100))
+            .predicate(|event| event.source != "test");
+
+        assert!(!filter.is_empty());
+        assert!(filter.matches(&event(synced(1000), "network", EventPriority::Normal)));
+        assert!(!filter.matches(&event(synced(10), "network", EventPriority::Normal)));
+        assert!(!filter.matches(&event(synced(1000), "test", EventPriority::Normal)));
+    }
+
+    #[test]
+    fn test_filter_from_config() {
+        let filter: EventFilter = toml::from_str(
+            r#"
+            kinds = ["clipboard_synced"]
+            min_priority = "high"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(filter.kinds, vec![EventKind::ClipboardSynced]);
+        assert!(filter.sources.is_empty());
+        assert!(filter.matches(&event(synced(1), "system", EventPriority::Critical)));
+        assert!(!filter.matches(&event(synced(1), "system", EventPriority::Normal)));
+    }
+}
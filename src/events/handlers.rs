@@ -1,7 +1,11 @@
 //! Event handlers implementation
 
-use crate::events::{Event, Result};
+use crate::events::{AsyncEventHandler, Event, EventWithMetadata, Result, TraceContext};
+use crate::utils::metrics::{MetricKey, PerformanceMetrics};
+use async_trait::async_trait;
 use log::{debug, error, info, warn};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
 /// Re-export the EventHandler trait from bus module
 pub use super::bus::EventHandler;
@@ -58,6 +62,15 @@ impl EventHandler for LoggingEventHandler {
             Event::ClipboardSynced { from_peer, content_size } => {
                 info!("Clipboard synced from {} ({} bytes)", from_peer, content_size);
             }
+            Event::ClipboardReceived { content, from_peer } => {
+                info!("Clipboard item received from {} ({} bytes), not applied", from_peer, content.metadata.size);
+            }
+            Event::ContentAnnounced { announcement, from_peer } => {
+                info!("Content announced by {} ({} bytes)", from_peer, announcement.metadata.size);
+            }
+            Event::TransferProgress { from_peer, bytes_done, total_bytes, .. } => {
+                debug!("Transfer from {}: {}/{} bytes", from_peer, bytes_done, total_bytes);
+            }
         }
 
         Ok(())
@@ -74,49 +87,107 @@ impl Default for LoggingEventHandler {
     }
 }
 
-/// Event handler for metrics collection
+/// Counter of events of each kind, e.g. `events_clipboard_changed`
+///
+/// Events carrying an item are labeled with its `content_type`.
+pub const EVENTS_METRIC_PREFIX: &str = "events_";
+/// Counter of bytes applied from peers, labeled by `peer`
+pub const BYTES_SYNCED_METRIC: &str = "bytes_synced";
+/// Gauge of currently connected peers, as counted in `NetworkStats::peers_connected`
+pub const PEERS_CONNECTED_METRIC: &str = "peers_connected";
+/// Counter of application errors
+pub const ERRORS_METRIC: &str = "errors";
+/// Duration from a copy on one device to it being applied on another, labeled by `peer`
+///
+/// The time between stages is recorded under e.g. `sync_latency_sent_to_received`.
+pub const SYNC_LATENCY_METRIC: &str = "sync_latency";
+
+/// Event handler recording events into [`PerformanceMetrics`]
 pub struct MetricsEventHandler {
     name: String,
-    // Add metrics collection fields here
+    metrics: Arc<PerformanceMetrics>,
+    /// Connected peers, so a peer reported disconnected twice is counted once
+    peers: Mutex<HashSet<String>>,
 }
 
 impl MetricsEventHandler {
     pub fn new() -> Self {
+        Self::with_metrics(Arc::new(PerformanceMetrics::new()))
+    }
+
+    /// Record into metrics shared with the rest of the application
+    pub fn with_metrics(metrics: Arc<PerformanceMetrics>) -> Self {
         Self {
             name: "MetricsEventHandler".to_string(),
+            metrics,
+            peers: Mutex::new(HashSet::new()),
         }
     }
-}
 
-impl EventHandler for MetricsEventHandler {
-    fn handle(&self, event: &Event) -> Result<()> {
-        // Collect metrics based on event type
+    pub fn metrics(&self) -> Arc<PerformanceMetrics> {
+        self.metrics.clone()
+    }
+
+    async fn record(&self, event: &Event) {
+        let key = MetricKey::new(format!("{}{}", EVENTS_METRIC_PREFIX, event.kind()));
+        let key = match event {
+            Event::ClipboardChanged { content, .. } | Event::ClipboardReceived { content, .. } => {
+                key.label("content_type", content.content_type.to_string())
+            }
+            _ => key,
+        };
+        self.metrics.increment_counter(key).await;
+
         match event {
-            Event::ClipboardChanged { .. } => {
-                // Increment clipboard change counter
-                debug!("Metrics: Clipboard change recorded");
-            }
-            Event::NetworkMessage { .. } => {
-                // Increment network message counter
-                debug!("Metrics: Network message recorded");
-            }
-            Event::DeviceConnected { .. } => {
-                // Update connected devices count
-                debug!("Metrics: Device connection recorded");
-            }
-            Event::DeviceDisconnected { .. } => {
-                // Update connected devices count
-                debug!("Metrics: Device disconnection recorded");
+            Event::ClipboardSynced { from_peer, content_size } => {
+                let key = MetricKey::new(BYTES_SYNCED_METRIC).label("peer", from_peer);
+                self.metrics.add_to_counter(key, *content_size as u64).await;
+            }
+            Event::PeerConnected { peer_id } | Event::PeerDisconnected { peer_id } => {
+                let connected = {
+                    let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+                    if matches!(event, Event::PeerConnected { .. }) {
+                        peers.insert(peer_id.clone());
+                    } else {
+                        peers.remove(peer_id);
+                    }
+                    peers.len()
+                };
+                self.metrics.set_gauge(PEERS_CONNECTED_METRIC, connected as f64).await;
             }
             Event::Error { .. } => {
-                // Increment error counter
-                debug!("Metrics: Error recorded");
-            }
-            _ => {
-                // Handle other events for metrics
+                self.metrics.increment_counter(ERRORS_METRIC).await;
             }
+            _ => {}
+        }
+    }
+
+    /// Record how long a synced item took end to end and per hop
+    async fn record_latency(&self, trace: &TraceContext, peer: &str) {
+        if let Some(latency) = trace.end_to_end() {
+            debug!("Item {} synced in {:?} end to end", trace.trace_id, latency);
+            let key = MetricKey::new(SYNC_LATENCY_METRIC).label("peer", peer);
+            self.metrics.record_duration(key, latency).await;
+        }
+        for (from, to, latency) in trace.hop_latencies() {
+            let key = MetricKey::new(format!("{}_{}_to_{}", SYNC_LATENCY_METRIC, from, to)).label("peer", peer);
+            self.metrics.record_duration(key, latency).await;
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncEventHandler for MetricsEventHandler {
+    async fn handle(&self, event: &Event) -> Result<()> {
+        self.record(event).await;
+        Ok(())
+    }
+
+    async fn handle_with_metadata(&self, event: &EventWithMetadata) -> Result<()> {
+        self.record(&event.event).await;
+        if let (Event::ClipboardSynced { from_peer, .. }, Some(trace)) = (&event.event, &event.trace) {
+            self.record_latency(trace, from_peer).await;
         }
-        
         Ok(())
     }
 
@@ -130,3 +201,40 @@ impl Default for MetricsEventHandler {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::HopStage;
+
+    #[tokio::test]
+    async fn test_metrics_handler_records_events() {
+        let handler = MetricsEventHandler::new();
+        let peer = |id: &str| id.to_string();
+
+        handler.handle(&Event::PeerConnected { peer_id: peer("a") }).await.unwrap();
+        handler.handle(&Event::PeerConnected { peer_id: peer("b") }).await.unwrap();
+        handler.handle(&Event::PeerDisconnected { peer_id: peer("a") }).await.unwrap();
+        handler.handle(&Event::PeerDisconnected { peer_id: peer("a") }).await.unwrap();
+        handler.handle(&Event::Error { error: "failed".to_string() }).await.unwrap();
+
+        let trace = TraceContext::start(HopStage::Copied, "laptop")
+            .with_hop(HopStage::Sent, "laptop")
+            .with_hop(HopStage::Applied, "desktop");
+        let synced = Event::ClipboardSynced { from_peer: peer("b"), content_size: 120 };
+        handler
+            .handle_with_metadata(&EventWithMetadata::new(synced, "app".to_string()).with_trace(Some(trace)))
+            .await
+            .unwrap();
+
+        let metrics = handler.metrics();
+        assert_eq!(metrics.get_counter("events_peer_connected").await, 2);
+        assert_eq!(metrics.get_counter("events_peer_disconnected").await, 2);
+        assert_eq!(metrics.get_gauge(PEERS_CONNECTED_METRIC).await, Some(1.0));
+        assert_eq!(metrics.get_counter(ERRORS_METRIC).await, 1);
+        let from_b = |name: &str| MetricKey::new(name).label("peer", "b");
+        assert_eq!(metrics.get_counter(from_b(BYTES_SYNCED_METRIC)).await, 120);
+        assert!(metrics.get_average_duration(from_b(SYNC_LATENCY_METRIC)).await.is_some());
+        assert!(metrics.get_average_duration(from_b("sync_latency_sent_to_applied")).await.is_some());
+    }
+}
@@ -0,0 +1,436 @@
+//! Event journal
+//!
+//! The [`EventJournal`] handler appends every event it receives to a JSON lines
+//! file, one [`EventWithMetadata`] per line, rotating the file by size.
+//! Clipboard payloads, previews and file names are redacted or hashed before
+//! they are written, and so are content checksums, which are digests of the
+//! payload. Hashing uses HMAC-SHA-256 with a random key kept next to the
+//! journal, so short secrets cannot be recovered from the journal alone by
+//! hashing guesses; anyone who also reads the key can still do that. [`replay`] feeds a journal back into an [`EventBus`] to
+//! reproduce a problem.
+
+use crate::config::{JournalConfig, PayloadMode};
+use crate::events::{AsyncEventHandler, Event, EventBus, EventWithMetadata, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Name of the journal file currently written to
+const CURRENT_FILE_NAME: &str = "events.jsonl";
+
+/// Name of the file holding the key of hash-mode digests
+const KEY_FILE_NAME: &str = "journal.key";
+
+/// Journal writing redacted events to rotating files
+pub struct EventJournal {
+    directory: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    payloads: PayloadMode,
+    /// Key of hash-mode digests; empty in redact mode
+    key: Vec<u8>,
+    current: Mutex<CurrentFile>,
+}
+
+struct CurrentFile {
+    file: tokio::fs::File,
+    size: u64,
+}
+
+impl EventJournal {
+    /// Open the journal in the configured directory, appending to an existing journal
+    pub async fn open(config: &JournalConfig) -> Result<Self> {
+        let directory = config
+            .directory_path()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e.to_string()))?;
+        Self::open_in(&directory, config).await
+    }
+
+    /// Open the journal in `directory`, ignoring the configured directory
+    pub async fn open_in(directory: &Path, config: &JournalConfig) -> Result<Self> {
+        create_private_dir(directory).await?;
+        let key = match config.payloads {
+            PayloadMode::Redact => Vec::new(),
+            PayloadMode::Hash => load_or_create_key(directory).await?,
+        };
+        let current = open_current(directory).await?;
+        debug!("Event journal opened in {}", directory.display());
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            max_file_size: config.max_file_size,
+            max_files: config.max_files.max(1),
+            payloads: config.payloads,
+            key,
+            current: Mutex::new(current),
+        })
+    }
+
+    /// Directory the journal files are written to
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Append an event, redacting its payloads
+    pub async fn append(&self, event: &EventWithMetadata) -> Result<()> {
+        let record = EventWithMetadata {
+            event: redact_event(&event.event, self.payloads, &self.key),
+            ..event.clone()
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let mut current = self.current.lock().await;
+        if current.size > 0 && current.size + line.len() as u64 > self.max_file_size {
+            *current = self.rotate().await?;
+        }
+
+        current.file.write_all(&line).await?;
+        current.file.flush().await?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift the rotated files by one, dropping the oldest, and start a new file
+    async fn rotate(&self) -> Result<CurrentFile> {
+        let path = |index: usize| self.directory.join(file_name(index));
+
+        remove_if_exists(&path(self.max_files - 1)).await?;
+        for index in (1..self.max_files - 1).rev() {
+            rename_if_exists(&path(index), &path(index + 1)).await?;
+        }
+        if self.max_files > 1 {
+            rename_if_exists(&path(0), &path(1)).await?;
+        } else {
+            remove_if_exists(&path(0)).await?;
+        }
+
+        debug!("Event journal rotated");
+        open_current(&self.directory).await
+    }
+}
+
+#[async_trait]
+impl AsyncEventHandler for EventJournal {
+    async fn handle(&self, event: &Event) -> Result<()> {
+        self.append(&EventWithMetadata::new(event.clone(), "system".to_string())).await
+    }
+
+    async fn handle_with_metadata(&self, event: &EventWithMetadata) -> Result<()> {
+        self.append(event).await
+    }
+
+    fn name(&self) -> &str {
+        "event_journal"
+    }
+}
+
+/// Read the events in a journal directory, oldest first
+///
+/// Lines that cannot be parsed, such as a line cut short by a crash, are skipped.
+pub async fn read_journal(directory: &Path) -> Result<Vec<EventWithMetadata>> {
+    let mut indexes = Vec::new();
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if let Some(index) = entry.file_name().to_str().and_then(file_index) {
+            indexes.push(index);
+        }
+    }
+    // Higher indexes hold older events
+    indexes.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut events = Vec::new();
+    for index in indexes {
+        let path = directory.join(file_name(index));
+        let content = tokio::fs::read_to_string(&path).await?;
+
+        for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(event) => events.push(event),
+                Err(e) => warn!("Skipping journal record {}:{}: {}", path.display(), number + 1, e),
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Emit the events of a journal into a bus in their original order, returning how many were emitted
+///
+/// Events keep their recorded priority, source and timestamp. A journal
+/// registered on the same bus records the replayed events again.
+pub async fn replay(directory: &Path, bus: &EventBus) -> Result<usize> {
+    let events = read_journal(directory).await?;
+    let count = events.len();
+
+    for event in events {
+        bus.emit_with_metadata(event).await?;
+    }
+
+    debug!("Replayed {} journal events", count);
+    Ok(count)
+}
+
+/// Copy of an event with clipboard payloads, previews, file names and checksums redacted or hashed
+///
+/// Hash mode replaces them with an HMAC-SHA-256 digest under `key`, so equal
+/// items can be matched up without the digest revealing short secrets.
+/// Checksums are hashed as well, being plain digests of the payload.
+pub fn redact_event(event: &Event, mode: PayloadMode, key: &[u8]) -> Event {
+    let conceal = |data: &[u8]| conceal(data, mode, key);
+    let mut event = event.clone();
+
+    match &mut event {
+        Event::ClipboardChanged { content, .. } | Event::ClipboardReceived { content, .. } => {
+            content.data = conceal(&content.data).map(String::into_bytes).unwrap_or_default();
+            content.metadata.filename = content.metadata.filename.as_deref().and_then(|name| conceal(name.as_bytes()));
+            content.checksum = conceal(content.checksum.as_bytes()).unwrap_or_default();
+        }
+        Event::ContentAnnounced { announcement, .. } => {
+            announcement.preview = announcement.preview.as_deref().and_then(|preview| conceal(preview.as_bytes()));
+            announcement.metadata.filename =
+                announcement.metadata.filename.as_deref().and_then(|name| conceal(name.as_bytes()));
+            announcement.checksum = conceal(announcement.checksum.as_bytes()).unwrap_or_default();
+        }
+        Event::TransferProgress { checksum, .. } => *checksum = conceal(checksum.as_bytes()).unwrap_or_default(),
+        Event::NetworkMessage { message, .. } => {
+            message.payload = conceal(&message.payload).map(String::into_bytes).unwrap_or_default();
+        }
+        _ => {}
+    }
+
+    event
+}
+
+/// Keyed digest of sensitive data in hash mode, nothing in redact mode
+fn conceal(data: &[u8], mode: PayloadMode, key: &[u8]) -> Option<String> {
+    match mode {
+        PayloadMode::Redact => None,
+        PayloadMode::Hash => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+            mac.update(data);
+            Some(format!("hmac-sha256:{:x}", mac.finalize().into_bytes()))
+        }
+    }
+}
+
+/// Read the key of hash-mode digests, creating it on first use
+///
+/// The key stays the same across restarts, so digests in older journal files
+/// still match.
+async fn load_or_create_key(directory: &Path) -> Result<Vec<u8>> {
+    let path = directory.join(KEY_FILE_NAME);
+    match tokio::fs::read(&path).await {
+        Ok(key) => Ok(key),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key: [u8; 32] = rand::random();
+            let mut options = tokio::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            options.open(&path).await?.write_all(&key).await?;
+
+            debug!("Created event journal key {}", path.display());
+            Ok(key.to_vec())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Name of the journal file with `index`; 0 is the current file
+fn file_name(index: usize) -> String {
+    match index {
+        0 => CURRENT_FILE_NAME.to_string(),
+        index => format!("events.{}.jsonl", index),
+    }
+}
+
+fn file_index(name: &str) -> Option<usize> {
+    if name == CURRENT_FILE_NAME {
+        return Some(0);
+    }
+    name.strip_prefix("events.")?.strip_suffix(".jsonl")?.parse().ok()
+}
+
+async fn open_current(directory: &Path) -> Result<CurrentFile> {
+    let path = directory.join(CURRENT_FILE_NAME);
+    let file = tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await?;
+    let size = file.metadata().await?.len();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+
+    Ok(CurrentFile { file, size })
+}
+
+/// Create a directory readable only by the current user
+async fn create_private_dir(path: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(path).await?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700)).await?;
+    }
+
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+async fn rename_if_exists(from: &Path, to: &Path) -> Result<()> {
+    match tokio::fs::rename(from, to).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::ClipboardContent;
+    use crate::events::EventPriority;
+    use futures::StreamExt;
+
+    fn config(max_file_size: u64, max_files: usize, payloads: PayloadMode) -> JournalConfig {
+        JournalConfig {
+            enabled: true,
+            directory: None,
+            max_file_size,
+            max_files,
+            payloads,
+        }
+    }
+
+    fn copied(text: &str) -> Event {
+        let mut content = ClipboardContent::new_text(text.to_string(), "laptop".to_string());
+        content.metadata.filename = Some("secret.txt".to_string());
+        Event::ClipboardChanged { content, device_system: "laptop".to_string() }
+    }
+
+    #[test]
+    fn test_payloads_are_redacted_or_hashed() {
+        let Event::ClipboardChanged { content, .. } = redact_event(&copied("hunter2"), PayloadMode::Redact, &[]) else {
+            unreachable!()
+        };
+        assert!(content.data.is_empty());
+        assert!(content.metadata.filename.is_none());
+        assert!(content.checksum.is_empty());
+        assert_eq!(content.metadata.size, 7);
+
+        let Event::ClipboardChanged { content, .. } = copied("hunter2") else { unreachable!() };
+        let announced = Event::ContentAnnounced {
+            announcement: content.announcement(16),
+            from_peer: "peer".to_string(),
+        };
+        let Event::ContentAnnounced { announcement, .. } = redact_event(&announced, PayloadMode::Redact, &[]) else {
+            unreachable!()
+        };
+        assert!(announcement.checksum.is_empty());
+        assert!(announcement.preview.is_none());
+
+        let hashed = |key: &[u8]| {
+            let Event::ClipboardChanged { content, .. } = redact_event(&copied("hunter2"), PayloadMode::Hash, key) else {
+                unreachable!()
+            };
+            content
+        };
+        let (first, second, other) = (hashed(b"key"), hashed(b"key"), hashed(b"other key"));
+        assert_eq!(first.data, second.data);
+        assert_ne!(first.data, other.data);
+        assert_ne!(first.checksum, other.checksum);
+        assert!(first.checksum.starts_with("hmac-sha256:"));
+        assert_ne!(first.checksum, ClipboardContent::new_text("hunter2".to_string(), "laptop".to_string()).checksum);
+
+        let data = String::from_utf8(first.data).unwrap();
+        assert!(data.starts_with("hmac-sha256:"));
+        assert!(!data.contains(&format!("{:x}", <Sha256 as sha2::Digest>::digest(b"hunter2"))));
+        assert!(!serde_json::to_string(&first.metadata).unwrap().contains("secret.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_hash_key_is_private_and_kept_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::open_in(dir.path(), &config(1024, 2, PayloadMode::Hash)).await.unwrap();
+        let key = std::fs::read(dir.path().join(KEY_FILE_NAME)).unwrap();
+        assert_eq!((journal.key.len(), &journal.key), (32, &key));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(KEY_FILE_NAME)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let reopened = EventJournal::open_in(dir.path(), &config(1024, 2, PayloadMode::Hash)).await.unwrap();
+        assert_eq!(reopened.key, key);
+
+        let other = tempfile::tempdir().unwrap();
+        let other = EventJournal::open_in(other.path(), &config(1024, 2, PayloadMode::Hash)).await.unwrap();
+        assert_ne!(other.key, key);
+    }
+
+    #[tokio::test]
+    async fn test_journal_rotates_and_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::open_in(dir.path(), &config(600, 3, PayloadMode::Redact)).await.unwrap();
+
+        for index in 0..20 {
+            let event = EventWithMetadata::new(copied(&format!("item {}", index)), "monitor".to_string());
+            journal.append(&event).await.unwrap();
+        }
+
+        let mut files: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["events.1.jsonl", "events.2.jsonl", "events.jsonl"]);
+
+        let contents = std::fs::read_to_string(dir.path().join("events.jsonl")).unwrap();
+        assert!(!contents.contains("item"));
+        assert!(contents.len() <= 600);
+    }
+
+    #[tokio::test]
+    async fn test_journal_handler_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::open_in(dir.path(), &config(1024 * 1024, 2, PayloadMode::Hash)).await.unwrap();
+
+        let bus = EventBus::new();
+        bus.register_async_handler(std::sync::Arc::new(journal), Default::default()).await.unwrap();
+        bus.emit(copied("one")).await.unwrap();
+        bus.emit_with_metadata(
+            EventWithMetadata::new(Event::Error { error: "sync failed".to_string() }, "network".to_string())
+                .with_priority(EventPriority::High),
+        )
+        .await
+        .unwrap();
+        bus.process_events().await.unwrap();
+
+        // A partly written last record is skipped
+        let mut file = std::fs::OpenOptions::new().append(true).open(dir.path().join("events.jsonl")).unwrap();
+        std::io::Write::write_all(&mut file, b"{\"event\":").unwrap();
+
+        let target = EventBus::new();
+        let mut events = target.subscribe();
+        assert_eq!(replay(dir.path(), &target).await.unwrap(), 2);
+
+        // Recorded priorities are kept, so the error is taken first
+        let error = events.next().await.unwrap();
+        assert_eq!((error.source.as_str(), error.priority), ("network", EventPriority::High));
+        assert!(matches!(error.event, Event::Error { .. }));
+        assert!(matches!(events.next().await.unwrap().event, Event::ClipboardChanged { .. }));
+    }
+}
@@ -4,15 +4,25 @@
 //! allowing different parts of the application to communicate asynchronously.
 
 pub mod bus;
+pub mod dispatch;
+pub mod filter;
 pub mod handlers;
+pub mod journal;
+mod queue;
+pub mod trace;
 
-pub use bus::EventBus;
+pub use bus::{EventBus, EventStream};
+pub use dispatch::{AsyncEventHandler, HandlerOptions, HandlerStats};
+pub use filter::EventFilter;
+pub use journal::EventJournal;
+pub use trace::{HopStage, TraceContext};
 pub use handlers::EventHandler;
 
-use crate::clipboard::ClipboardContent;
+use crate::clipboard::{ClipboardContent, ContentAnnouncement};
 use crate::network::Message;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 use thiserror::Error;
 
 /// Event system errors
@@ -27,8 +37,14 @@ pub enum EventError {
     #[error("Event processing failed: {0}")]
     ProcessingFailed(String),
 
+    #[error("Unknown event kind: {0}")]
+    UnknownKind(String),
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 /// Result type for event operations
@@ -81,6 +97,26 @@ pub enum Event {
         content_size: usize,
     },
 
+    /// Item received from a peer but not applied, in notify mode
+    ClipboardReceived {
+        content: ClipboardContent,
+        from_peer: String,
+    },
+
+    /// A peer announced a large item that can be fetched on demand
+    ContentAnnounced {
+        announcement: ContentAnnouncement,
+        from_peer: String,
+    },
+
+    /// Progress of a chunked transfer from a peer
+    TransferProgress {
+        checksum: String,
+        from_peer: String,
+        bytes_done: u64,
+        total_bytes: u64,
+    },
+
     /// Application error occurred
     Error {
         error: String,
@@ -101,6 +137,112 @@ pub enum Event {
     Shutdown,
 }
 
+impl Event {
+    /// Kind of the event, without its data
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::ClipboardChanged { .. } => EventKind::ClipboardChanged,
+            Event::NetworkMessage { .. } => EventKind::NetworkMessage,
+            Event::DeviceConnected { .. } => EventKind::DeviceConnected,
+            Event::DeviceDisconnected { .. } => EventKind::DeviceDisconnected,
+            Event::PeerDiscovered { .. } => EventKind::PeerDiscovered,
+            Event::PeerConnected { .. } => EventKind::PeerConnected,
+            Event::PeerDisconnected { .. } => EventKind::PeerDisconnected,
+            Event::ClipboardSynced { .. } => EventKind::ClipboardSynced,
+            Event::ClipboardReceived { .. } => EventKind::ClipboardReceived,
+            Event::ContentAnnounced { .. } => EventKind::ContentAnnounced,
+            Event::TransferProgress { .. } => EventKind::TransferProgress,
+            Event::Error { .. } => EventKind::Error,
+            Event::Heartbeat { .. } => EventKind::Heartbeat,
+            Event::ConfigChanged { .. } => EventKind::ConfigChanged,
+            Event::Shutdown => EventKind::Shutdown,
+        }
+    }
+}
+
+/// Kind of an [`Event`]
+///
+/// The names are stable, so kinds can be used in configuration, e.g.
+/// `kinds = ["clipboard_synced"]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ClipboardChanged,
+    NetworkMessage,
+    DeviceConnected,
+    DeviceDisconnected,
+    PeerDiscovered,
+    PeerConnected,
+    PeerDisconnected,
+    ClipboardSynced,
+    ClipboardReceived,
+    ContentAnnounced,
+    TransferProgress,
+    Error,
+    Heartbeat,
+    ConfigChanged,
+    Shutdown,
+}
+
+impl EventKind {
+    /// Every event kind
+    pub const ALL: [EventKind; 15] = [
+        EventKind::ClipboardChanged,
+        EventKind::NetworkMessage,
+        EventKind::DeviceConnected,
+        EventKind::DeviceDisconnected,
+        EventKind::PeerDiscovered,
+        EventKind::PeerConnected,
+        EventKind::PeerDisconnected,
+        EventKind::ClipboardSynced,
+        EventKind::ClipboardReceived,
+        EventKind::ContentAnnounced,
+        EventKind::TransferProgress,
+        EventKind::Error,
+        EventKind::Heartbeat,
+        EventKind::ConfigChanged,
+        EventKind::Shutdown,
+    ];
+
+    /// Stable name of the kind, as used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::ClipboardChanged => "clipboard_changed",
+            EventKind::NetworkMessage => "network_message",
+            EventKind::DeviceConnected => "device_connected",
+            EventKind::DeviceDisconnected => "device_disconnected",
+            EventKind::PeerDiscovered => "peer_discovered",
+            EventKind::PeerConnected => "peer_connected",
+            EventKind::PeerDisconnected => "peer_disconnected",
+            EventKind::ClipboardSynced => "clipboard_synced",
+            EventKind::ClipboardReceived => "clipboard_received",
+            EventKind::ContentAnnounced => "content_announced",
+            EventKind::TransferProgress => "transfer_progress",
+            EventKind::Error => "error",
+            EventKind::Heartbeat => "heartbeat",
+            EventKind::ConfigChanged => "config_changed",
+            EventKind::Shutdown => "shutdown",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = EventError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| EventError::UnknownKind(s.to_string()))
+    }
+}
+
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -140,32 +282,40 @@ impl fmt::Display for Event {
             Event::ClipboardSynced { from_peer, content_size } => {
                 write!(f, "ClipboardSynced(from_peer: {}, content_size: {})", from_peer, content_size)
             }
+            Event::ClipboardReceived { content, from_peer } => {
+                write!(f, "ClipboardReceived(from_peer: {}, content_size: {})", from_peer, content.metadata.size)
+            }
+            Event::ContentAnnounced { announcement, from_peer } => {
+                write!(f, "ContentAnnounced(from_peer: {}, size: {})", from_peer, announcement.metadata.size)
+            }
+            Event::TransferProgress { from_peer, bytes_done, total_bytes, .. } => {
+                write!(f, "TransferProgress(from_peer: {}, {}/{} bytes)", from_peer, bytes_done, total_bytes)
+            }
         }
     }
 }
 
 /// Event priority levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EventPriority {
     Low = 0,
+    #[default]
     Normal = 1,
     High = 2,
     Critical = 3,
 }
 
-impl Default for EventPriority {
-    fn default() -> Self {
-        EventPriority::Normal
-    }
-}
-
 /// Event with metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventWithMetadata {
     pub event: Event,
     pub priority: EventPriority,
     pub timestamp: u64,
     pub source: String,
+    /// Trace of the clipboard item the event is about
+    #[serde(default)]
+    pub trace: Option<TraceContext>,
 }
 
 impl EventWithMetadata {
@@ -175,6 +325,7 @@ impl EventWithMetadata {
             priority: EventPriority::default(),
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
             source,
+            trace: None,
         }
     }
 
@@ -182,4 +333,9 @@ impl EventWithMetadata {
         self.priority = priority;
         self
     }
+
+    pub fn with_trace(mut self, trace: Option<TraceContext>) -> Self {
+        self.trace = trace;
+        self
+    }
 }
@@ -0,0 +1,145 @@
+//! Bounded priority queue of events
+//!
+//! Events are kept in one FIFO queue per priority, so queueing, taking the
+//! next event and dropping the oldest lowest-priority event are O(1).
+
+use crate::config::OverflowPolicy;
+use crate::events::{EventPriority, EventWithMetadata};
+use std::collections::VecDeque;
+
+const LEVELS: usize = EventPriority::Critical as usize + 1;
+
+/// Result of offering an event to a full or non-full queue
+#[derive(Debug)]
+pub(crate) enum Offer {
+    /// The event was queued
+    Queued,
+    /// The queue was full and this event was dropped to make room; it may be
+    /// the offered event itself
+    Dropped(EventWithMetadata),
+    /// The queue was full and the policy keeps the offered event out
+    Full(EventWithMetadata),
+}
+
+/// Events by priority, highest priority first and FIFO within a priority
+#[derive(Debug, Default)]
+pub(crate) struct PriorityQueue {
+    levels: [VecDeque<EventWithMetadata>; LEVELS],
+    len: usize,
+}
+
+impl PriorityQueue {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Queue an event regardless of capacity
+    pub fn push(&mut self, event: EventWithMetadata) {
+        self.levels[event.priority as usize].push_back(event);
+        self.len += 1;
+    }
+
+    /// Take the oldest event of the highest priority
+    pub fn pop(&mut self) -> Option<EventWithMetadata> {
+        let event = self.levels.iter_mut().rev().find_map(VecDeque::pop_front)?;
+        self.len -= 1;
+        Some(event)
+    }
+
+    /// Queue an event if there is room, or apply the overflow policy
+    pub fn offer(&mut self, event: EventWithMetadata, capacity: usize, policy: OverflowPolicy) -> Offer {
+        if self.len < capacity {
+            self.push(event);
+            return Offer::Queued;
+        }
+
+        match policy {
+            OverflowPolicy::DropOldestLowest => match self.lowest_priority() {
+                Some(lowest) if lowest <= event.priority as usize => {
+                    let dropped = self.levels[lowest].pop_front().expect("level is not empty");
+                    self.levels[event.priority as usize].push_back(event);
+                    Offer::Dropped(dropped)
+                }
+                // The offered event ranks below everything queued
+                _ => Offer::Dropped(event),
+            },
+            OverflowPolicy::Reject | OverflowPolicy::Block => Offer::Full(event),
+        }
+    }
+
+    /// Remove every event, returning how many were queued
+    pub fn clear(&mut self) -> usize {
+        self.levels.iter_mut().for_each(VecDeque::clear);
+        std::mem::take(&mut self.len)
+    }
+
+    fn lowest_priority(&self) -> Option<usize> {
+        self.levels.iter().position(|level| !level.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Event;
+
+    fn event(name: &str, priority: EventPriority) -> EventWithMetadata {
+        EventWithMetadata::new(Event::ConfigChanged { section: name.to_string() }, "test".to_string())
+            .with_priority(priority)
+    }
+
+    fn name(event: &EventWithMetadata) -> &str {
+        match &event.event {
+            Event::ConfigChanged { section } => section,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_priority_order_and_fifo_within_priority() {
+        let mut queue = PriorityQueue::default();
+        queue.push(event("low", EventPriority::Low));
+        queue.push(event("normal-1", EventPriority::Normal));
+        queue.push(event("critical", EventPriority::Critical));
+        queue.push(event("normal-2", EventPriority::Normal));
+        assert_eq!(queue.len(), 4);
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop()).map(|e| name(&e).to_string()).collect();
+        assert_eq!(order, ["critical", "normal-1", "normal-2", "low"]);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn test_drop_oldest_lowest_on_overflow() {
+        let policy = OverflowPolicy::DropOldestLowest;
+        let mut queue = PriorityQueue::default();
+        assert!(matches!(queue.offer(event("normal", EventPriority::Normal), 3, policy), Offer::Queued));
+        queue.push(event("low-1", EventPriority::Low));
+        queue.push(event("low-2", EventPriority::Low));
+
+        match queue.offer(event("high", EventPriority::High), 3, policy) {
+            Offer::Dropped(dropped) => assert_eq!(name(&dropped), "low-1"),
+            other => panic!("unexpected {:?}", other),
+        }
+        queue.clear();
+
+        // An event ranking below everything queued is the one dropped
+        queue.push(event("normal", EventPriority::Normal));
+        match queue.offer(event("low", EventPriority::Low), 1, policy) {
+            Offer::Dropped(dropped) => assert_eq!(name(&dropped), "low"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_reject_and_block_keep_queue_intact() {
+        for policy in [OverflowPolicy::Reject, OverflowPolicy::Block] {
+            let mut queue = PriorityQueue::default();
+            queue.push(event("low", EventPriority::Low));
+            assert!(matches!(queue.offer(event("critical", EventPriority::Critical), 1, policy), Offer::Full(_)));
+            assert_eq!(queue.clear(), 1);
+            assert_eq!(queue.len(), 0);
+        }
+    }
+}
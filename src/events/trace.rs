@@ -0,0 +1,154 @@
+//! Tracing of clipboard items across devices
+//!
+//! A [`TraceContext`] follows an item from the copy on one device to the
+//! clipboard of another. It travels in [`EventWithMetadata`] and in the
+//! message header, and every stage appends a timestamped [`Hop`], so the time
+//! an item took end to end and per hop can be computed on the receiving side.
+//!
+//! Hops recorded on different devices are compared by wall-clock time, so
+//! cross-device durations are only as accurate as the devices' clocks.
+//!
+//! [`EventWithMetadata`]: crate::events::EventWithMetadata
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Stage of an item's way from one clipboard to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HopStage {
+    /// Picked up from the local clipboard
+    Copied,
+    /// Sent to peers
+    Sent,
+    /// Received from a peer
+    Received,
+    /// Written to the local clipboard
+    Applied,
+}
+
+impl HopStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HopStage::Copied => "copied",
+            HopStage::Sent => "sent",
+            HopStage::Received => "received",
+            HopStage::Applied => "applied",
+        }
+    }
+}
+
+impl fmt::Display for HopStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A stage an item passed, on which device and when
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hop {
+    pub stage: HopStage,
+    /// Name of the device the stage happened on
+    pub device: String,
+    /// Time of the stage in microseconds since the Unix epoch
+    pub at: u64,
+}
+
+/// Trace ID and the hops of one item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    pub trace_id: String,
+    pub hops: Vec<Hop>,
+}
+
+impl TraceContext {
+    /// Start a trace with a new ID at `stage`
+    pub fn start(stage: HopStage, device: &str) -> Self {
+        Self {
+            trace_id: uuid::Uuid::new_v4().to_string(),
+            hops: Vec::new(),
+        }
+        .with_hop(stage, device)
+    }
+
+    /// Record that the item reached `stage` now
+    pub fn with_hop(mut self, stage: HopStage, device: &str) -> Self {
+        self.hops.push(Hop {
+            stage,
+            device: device.to_string(),
+            at: chrono::Utc::now().timestamp_micros().max(0) as u64,
+        });
+        self
+    }
+
+    /// Latest hop at `stage`
+    pub fn hop(&self, stage: HopStage) -> Option<&Hop> {
+        self.hops.iter().rfind(|hop| hop.stage == stage)
+    }
+
+    /// Time from the first hop to the item being applied
+    pub fn end_to_end(&self) -> Option<Duration> {
+        let first = self.hops.first()?;
+        let applied = self.hop(HopStage::Applied)?;
+        Some(elapsed(first, applied))
+    }
+
+    /// Time between consecutive hops, e.g. `(Sent, Received, 12ms)`
+    pub fn hop_latencies(&self) -> Vec<(HopStage, HopStage, Duration)> {
+        self.hops
+            .windows(2)
+            .map(|pair| (pair[0].stage, pair[1].stage, elapsed(&pair[0], &pair[1])))
+            .collect()
+    }
+}
+
+/// Time between two hops; a clock behind the earlier device's counts as no time
+fn elapsed(from: &Hop, to: &Hop) -> Duration {
+    Duration::from_micros(to.at.saturating_sub(from.at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(stage: HopStage, device: &str, at: u64) -> Hop {
+        Hop { stage, device: device.to_string(), at }
+    }
+
+    #[test]
+    fn test_trace_records_hops_in_order() {
+        let trace = TraceContext::start(HopStage::Copied, "laptop").with_hop(HopStage::Sent, "laptop");
+        assert_eq!(trace.hops.len(), 2);
+        assert!(trace.hops[0].at <= trace.hops[1].at);
+        assert!(trace.end_to_end().is_none());
+
+        let other = TraceContext::start(HopStage::Copied, "laptop");
+        assert_ne!(trace.trace_id, other.trace_id);
+    }
+
+    #[test]
+    fn test_latencies_between_hops() {
+        let trace = TraceContext {
+            trace_id: "trace".to_string(),
+            hops: vec![
+                hop(HopStage::Copied, "laptop", 1_000),
+                hop(HopStage::Sent, "laptop", 3_000),
+                hop(HopStage::Received, "desktop", 2_500),
+                hop(HopStage::Applied, "desktop", 10_000),
+            ],
+        };
+
+        assert_eq!(trace.end_to_end(), Some(Duration::from_micros(9_000)));
+        assert_eq!(
+            trace.hop_latencies(),
+            vec![
+                (HopStage::Copied, HopStage::Sent, Duration::from_micros(2_000)),
+                // The receiver's clock is behind the sender's
+                (HopStage::Sent, HopStage::Received, Duration::ZERO),
+                (HopStage::Received, HopStage::Applied, Duration::from_micros(7_500)),
+            ]
+        );
+        assert_eq!(trace.hop(HopStage::Received).unwrap().device, "desktop");
+    }
+}
@@ -32,23 +32,75 @@ pub mod events;
 pub mod network;
 pub mod utils;
 
-use config::AppConfig;
-use events::EventBus;
-use log::{error, info, warn};
+use clipboard::{ClipboardContent, ContentAnnouncement};
+use config::{AppConfig, SecurityConfig, SyncConfig, SyncMode};
+use events::{EventBus, EventStream, EventWithMetadata, HopStage, TraceContext};
+use futures::future::{BoxFuture, Shared};
+use futures::{FutureExt, StreamExt};
+use log::{debug, error, info, warn};
+use network::{
+    ChunkRequest, ClipboardOrdering, HlcTimestamp, HybridClock, IncomingTransfer, Message, MessageType,
+    NetworkManager, PeerRequester, Resolution, SeenMessages, TransferManifest,
+};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 
 /// Main application error type
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// Number of received update message IDs remembered to drop duplicates
+const SEEN_MESSAGES_CAPACITY: usize = 1024;
+
+/// Announced item not fetched yet
+struct PendingAnnouncement {
+    /// Peer that announced the item
+    peer: String,
+    announcement: ContentAnnouncement,
+    clock: HlcTimestamp,
+    trace: Option<TraceContext>,
+}
+
+/// Fetch of an announced item running in the background; resolves once the
+/// item is in the content store
+type FetchHandle = Shared<BoxFuture<'static, std::result::Result<(), String>>>;
+
 /// Main CrossCopy application
 pub struct CrossCopyApp {
     config: AppConfig,
     event_bus: Arc<EventBus>,
+    /// Events read by the event loop, subscribed on creation so none are missed
+    events: tokio::sync::Mutex<EventStream>,
     clipboard_monitor: Option<clipboard::ClipboardMonitor>,
     network_manager: Option<network::NetworkManager>,
-    encryption_service: Option<crypto::EncryptionService>,
+    encryption_service: Option<Arc<crypto::EncryptionService>>,
+    history: Option<clipboard::ClipboardHistory>,
+    content_store: Arc<clipboard::ContentStore>,
+    policies: clipboard::SyncPolicies,
+    /// Sync modes, switchable at runtime
+    sync: Arc<RwLock<SyncConfig>>,
+    /// Local copies waiting for a manual push, oldest first
+    push_queue: Arc<RwLock<VecDeque<ClipboardContent>>>,
+    /// Announced items not fetched yet, by checksum
+    announcements: Arc<RwLock<HashMap<String, PendingAnnouncement>>>,
+    /// Text transformations, shared with the monitor
+    transforms: Arc<clipboard::TransformPipeline>,
+    /// Clock and last-writer-wins register of clipboard updates, shared with the monitor
+    ordering: Arc<ClipboardOrdering>,
+    /// Recently received update message IDs, to drop duplicates
+    seen_messages: Arc<RwLock<SeenMessages>>,
+    /// Interrupted chunked transfers, by checksum
+    transfers: Arc<RwLock<HashMap<String, IncomingTransfer>>>,
+    /// Fetches running in the background, by checksum
+    fetches: Arc<tokio::sync::Mutex<HashMap<String, FetchHandle>>>,
+    /// Checksums of items fetched in the background, applied by the event loop
+    fetched_sender: mpsc::UnboundedSender<String>,
+    fetched: tokio::sync::Mutex<mpsc::UnboundedReceiver<String>>,
     running: Arc<RwLock<bool>>,
+    /// Metrics recorded from events by the metrics handler
+    metrics: Arc<utils::metrics::PerformanceMetrics>,
+    #[cfg(feature = "metrics-exporter")]
+    metrics_exporter: Option<utils::openmetrics::MetricsExporter>,
 }
 
 impl CrossCopyApp {
@@ -56,15 +108,48 @@ impl CrossCopyApp {
     pub async fn new(config: AppConfig) -> Result<Self> {
         info!("Initializing CrossCopy application");
 
-        let event_bus = Arc::new(EventBus::new());
-        
+        let event_bus = Arc::new(EventBus::with_config(config.events.clone()));
+        let events = tokio::sync::Mutex::new(event_bus.subscribe());
+        let content_store = Arc::new(clipboard::ContentStore::new(config.clipboard.lazy.cache_max_bytes));
+        let policies = clipboard::SyncPolicies::from_config(&config.clipboard.policies);
+        let transforms = Arc::new(clipboard::TransformPipeline::from_config(&config.clipboard.transforms));
+        let sync = Arc::new(RwLock::new(config.sync.clone()));
+        // Keyed on the peer ID once the network manager is initialized
+        let ordering = Arc::new(ClipboardOrdering::new(HybridClock::new(config.device_name.clone())));
+        let (fetched_sender, fetched) = mpsc::unbounded_channel();
+
+        let metrics = Arc::new(utils::metrics::PerformanceMetrics::with_window(config.metrics.window()));
+        event_bus
+            .register_async_handler(
+                Arc::new(events::handlers::MetricsEventHandler::with_metrics(metrics.clone())),
+                events::HandlerOptions::default(),
+            )
+            .await?;
+
         Ok(Self {
             config,
             event_bus,
+            events,
             clipboard_monitor: None,
             network_manager: None,
             encryption_service: None,
+            history: None,
+            content_store,
+            policies,
+            sync,
+            push_queue: Arc::new(RwLock::new(VecDeque::new())),
+            announcements: Arc::new(RwLock::new(HashMap::new())),
+            transforms,
+            ordering,
+            seen_messages: Arc::new(RwLock::new(SeenMessages::new(SEEN_MESSAGES_CAPACITY))),
+            transfers: Arc::new(RwLock::new(HashMap::new())),
+            fetches: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            fetched_sender,
+            fetched: tokio::sync::Mutex::new(fetched),
             running: Arc::new(RwLock::new(false)),
+            metrics,
+            #[cfg(feature = "metrics-exporter")]
+            metrics_exporter: None,
         })
     }
 
@@ -78,6 +163,12 @@ impl CrossCopyApp {
         // Initialize encryption service
         self.init_encryption_service().await?;
 
+        // Initialize clipboard history
+        self.init_history().await?;
+
+        // Initialize event journal
+        self.init_journal().await?;
+
         // Initialize network manager
         self.init_network_manager().await?;
 
@@ -87,6 +178,9 @@ impl CrossCopyApp {
         // Start all services
         self.start_services().await?;
 
+        // Serve metrics to Prometheus
+        self.init_metrics_exporter().await?;
+
         // Main event loop
         self.event_loop().await?;
 
@@ -99,6 +193,14 @@ impl CrossCopyApp {
         
         *self.running.write().await = false;
 
+        // Wake the event loop so it sees the app stopped
+        let shutdown = EventWithMetadata::new(events::Event::Shutdown, "system".to_string())
+            .with_priority(events::EventPriority::Critical);
+        self.event_bus.emit_without_waiting(shutdown)?;
+
+        // Let handlers such as the journal finish the events emitted so far
+        self.event_bus.process_events().await?;
+
         // Stop clipboard monitor
         if let Some(monitor) = &mut self.clipboard_monitor {
             monitor.stop().await?;
@@ -109,6 +211,10 @@ impl CrossCopyApp {
             manager.stop().await?;
         }
 
+        // Stop metrics endpoint
+        #[cfg(feature = "metrics-exporter")]
+        self.metrics_exporter.take();
+
         info!("CrossCopy application shutdown complete");
         Ok(())
     }
@@ -117,19 +223,136 @@ impl CrossCopyApp {
         info!("Initializing encryption service");
         
         let encryption_service = crypto::EncryptionService::from_config(&self.config.security)?;
-        self.encryption_service = Some(encryption_service);
+        self.encryption_service = Some(Arc::new(encryption_service));
         
         Ok(())
     }
 
+    async fn init_history(&mut self) -> Result<()> {
+        if !self.config.history.enabled {
+            return Ok(());
+        }
+
+        info!("Initializing clipboard history");
+
+        let directory = self.config.history.directory_path()?;
+        let history = clipboard::ClipboardHistory::open(
+            &directory,
+            self.config.history.max_items,
+            &self.config.security,
+        ).await?;
+
+        self.history = Some(history);
+
+        Ok(())
+    }
+
+    async fn init_journal(&mut self) -> Result<()> {
+        let config = &self.config.events.journal;
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let journal = events::EventJournal::open(config).await?;
+        info!("Writing event journal to {}", journal.directory().display());
+
+        self.event_bus
+            .register_async_handler(Arc::new(journal), events::HandlerOptions::default())
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "metrics-exporter")]
+    async fn init_metrics_exporter(&mut self) -> Result<()> {
+        if !self.config.metrics.enabled {
+            return Ok(());
+        }
+
+        let sources = utils::openmetrics::MetricsSources {
+            metrics: self.metrics.clone(),
+            event_bus: self.event_bus.clone(),
+            network: self.network_manager.as_ref().map(|manager| manager.shared_stats()),
+        };
+        let exporter = utils::openmetrics::MetricsExporter::start(&self.config.metrics.listen_address, sources).await?;
+        self.metrics_exporter = Some(exporter);
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "metrics-exporter"))]
+    async fn init_metrics_exporter(&mut self) -> Result<()> {
+        if self.config.metrics.enabled {
+            warn!("Metrics endpoint is enabled but crosscopy was built without the metrics-exporter feature");
+        }
+        Ok(())
+    }
+
+    /// Event bus the application emits its events on
+    ///
+    /// Use it to subscribe to events or to replay a journal with
+    /// [`events::journal::replay`].
+    pub fn event_bus(&self) -> Arc<EventBus> {
+        self.event_bus.clone()
+    }
+
+    /// Apply new security settings, re-keying the clipboard history
+    pub async fn update_security_config(&mut self, security: SecurityConfig) -> Result<()> {
+        info!("Updating security configuration");
+
+        if let Some(history) = &mut self.history {
+            history.rekey(&security).await?;
+        }
+
+        if self.encryption_service.is_some() {
+            self.encryption_service = Some(Arc::new(crypto::EncryptionService::from_config(&security)?));
+        }
+
+        self.config.security = security;
+        Ok(())
+    }
+
+    /// Securely delete the clipboard history and its keys
+    pub async fn wipe_history(&mut self) -> Result<()> {
+        match self.history.take() {
+            Some(history) => history.secure_wipe().await?,
+            None => {
+                let directory = self.config.history.directory_path()?;
+                clipboard::ClipboardHistory::wipe_directory(&directory).await?;
+            }
+        }
+
+        info!("Clipboard history wiped");
+        Ok(())
+    }
+
+    /// Add a custom text transformation after the configured ones
+    ///
+    /// Transformations must be added before the app is started.
+    pub fn add_transform(
+        &mut self,
+        direction: config::SyncDirection,
+        transform: Box<dyn clipboard::ContentTransform>,
+    ) -> Result<()> {
+        let transforms = Arc::get_mut(&mut self.transforms)
+            .ok_or("Transformations cannot be added while the app is running")?;
+        transforms.add_transform(direction, transform);
+        Ok(())
+    }
+
     async fn init_network_manager(&mut self) -> Result<()> {
         info!("Initializing network manager");
         
-        let network_manager = network::NetworkManager::new(
+        let mut network_manager = network::NetworkManager::new(
             self.config.network.clone(),
             self.event_bus.clone(),
         ).await?;
-        
+        network_manager.set_device_name(self.config.device_name.clone());
+
+        // Ties between concurrent copies are broken by device ID, so it has to
+        // be the same on every start (see `network.identity_file`)
+        let device_id = network_manager.local_peer_id().to_string();
+        self.ordering = Arc::new(ClipboardOrdering::new(HybridClock::new(device_id)));
         self.network_manager = Some(network_manager);
         
         Ok(())
@@ -158,34 +381,50 @@ impl CrossCopyApp {
 
         // Start clipboard monitor
         if let Some(monitor) = &mut self.clipboard_monitor {
+            monitor.set_transforms(self.transforms.clone());
+            monitor.set_ordering(self.ordering.clone());
+            monitor.set_device_name(self.config.device_name.clone());
             monitor.start().await?;
         }
 
         Ok(())
     }
 
+    /// Handle bus events until the app stops
+    ///
+    /// This loop drains the app's own subscription, and the network and fetch
+    /// tasks it waits on emit into it, so the app emits without waiting for
+    /// room even under the block overflow policy.
     async fn event_loop(&self) -> Result<()> {
         info!("Entering main event loop");
 
+        let mut events = self.events.lock().await;
+        let mut fetched = self.fetched.lock().await;
         while *self.running.read().await {
-            // Process events from the event bus
-            if let Some(event) = self.event_bus.poll_event().await {
-                if let Err(e) = self.handle_event(event).await {
-                    error!("Error handling event: {}", e);
-                }
-            }
+            // Fetches run in their own tasks so the loop keeps serving peers,
+            // including chunk requests from a peer fetching from us
+            let result = tokio::select! {
+                event = events.next() => match event {
+                    Some(event) => self.handle_event(event).await,
+                    None => break,
+                },
+                Some(checksum) = fetched.recv() => self.apply_fetched(&checksum).await,
+            };
 
-            // Small delay to prevent busy waiting
-            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            if let Err(e) = result {
+                error!("Error handling event: {}", e);
+                let event = events::Event::Error { error: e.to_string() };
+                let _ = self.event_bus.emit_without_waiting(EventWithMetadata::new(event, "system".to_string()));
+            }
         }
 
         Ok(())
     }
 
-    async fn handle_event(&self, event: events::Event) -> Result<()> {
-        match event {
+    async fn handle_event(&self, event: EventWithMetadata) -> Result<()> {
+        match event.event {
             events::Event::ClipboardChanged { content, device_system } => {
-                self.handle_clipboard_change(content, device_system).await?;
+                self.handle_clipboard_change(content, device_system, event.trace).await?;
             }
             events::Event::NetworkMessage { message, sender } => {
                 self.handle_network_message(message, sender).await?;
@@ -214,6 +453,7 @@ impl CrossCopyApp {
             }
             events::Event::PeerConnected { peer_id } => {
                 info!("Peer connected: {}", peer_id);
+                self.resume_transfers(&peer_id).await;
             }
             events::Event::PeerDisconnected { peer_id } => {
                 info!("Peer disconnected: {}", peer_id);
@@ -221,6 +461,15 @@ impl CrossCopyApp {
             events::Event::ClipboardSynced { from_peer, content_size } => {
                 info!("Clipboard synced from {} ({} bytes)", from_peer, content_size);
             }
+            events::Event::ClipboardReceived { content, from_peer } => {
+                info!("Received a {} byte item from {}, not applied", content.metadata.size, from_peer);
+            }
+            events::Event::ContentAnnounced { announcement, from_peer } => {
+                info!("{} announced a {} byte item", from_peer, announcement.metadata.size);
+            }
+            events::Event::TransferProgress { from_peer, bytes_done, total_bytes, .. } => {
+                debug!("Received {}/{} bytes from {}", bytes_done, total_bytes, from_peer);
+            }
         }
 
         Ok(())
@@ -230,9 +479,82 @@ impl CrossCopyApp {
         &self,
         content: clipboard::ClipboardContent,
         device_system: String,
+        trace: Option<TraceContext>,
     ) -> Result<()> {
         info!("Handling clipboard change from device: {}", device_system);
 
+        // Copies reported without a trace are traced from here
+        let trace = trace.unwrap_or_else(|| TraceContext::start(HopStage::Copied, &self.config.device_name));
+
+        let stamp = {
+            let mut register = self.ordering.lock().await;
+            match content.metadata.clock.clone() {
+                // The monitor stamped the copy when it picked it up; a later
+                // item received since then has replaced it on the clipboard
+                Some(stamp) => {
+                    if register.current().map(|(current, _)| current) != Some(&stamp) {
+                        info!("Not sending item {}, a later update is current", content.checksum);
+                        return Ok(());
+                    }
+                    stamp
+                }
+                None => {
+                    if register.current().is_some_and(|(_, checksum)| checksum == content.checksum) {
+                        debug!("Not re-sending item {}, it is already current", content.checksum);
+                        return Ok(());
+                    }
+                    let stamp = self.ordering.clock().now();
+                    register.offer(&stamp, &content.checksum);
+                    stamp
+                }
+            }
+        };
+
+        // Middle-click selections change constantly and are not kept in history
+        let record_history = content.metadata.selection == clipboard::Selection::Clipboard;
+        if let Some(history) = self.history.as_ref().filter(|_| record_history) {
+            if let Err(e) = history.record(&content).await {
+                warn!("Failed to record clipboard history: {}", e);
+            }
+        }
+
+        // Peers in manual mode only get items that are pushed explicitly
+        let sync = self.sync.read().await;
+        if sync.uses_manual_push() {
+            let mut queue = self.push_queue.write().await;
+            queue.retain(|queued| queued.checksum != content.checksum);
+            queue.push_back(content.clone());
+            while queue.len() > sync.manual_queue_size {
+                queue.pop_front();
+            }
+        }
+        drop(sync);
+
+        self.distribute(content, stamp, false, trace).await
+    }
+
+    /// Send a local item to the peers whose sync mode and policy allow it
+    ///
+    /// `pushed` items go to peers in manual mode, others to peers that send automatically.
+    async fn distribute(
+        &self,
+        content: ClipboardContent,
+        stamp: HlcTimestamp,
+        pushed: bool,
+        trace: TraceContext,
+    ) -> Result<()> {
+        // Peers may not support the codec the item was compressed with
+        #[cfg(feature = "compression")]
+        let content = self.recompress_for_peers(content).await?;
+
+        let lazy = &self.config.clipboard.lazy;
+        // Items too large for a single chunk are always fetched in chunks
+        if (lazy.enabled && content.metadata.size > lazy.threshold_bytes)
+            || content.data.len() > lazy.chunk_size_bytes
+        {
+            return self.announce_content(content, stamp, pushed, trace).await;
+        }
+
         // Encrypt content if encryption is enabled
         let encrypted_content = if let Some(encryption_service) = &self.encryption_service {
             encryption_service.encrypt_content(&content)?
@@ -242,33 +564,792 @@ impl CrossCopyApp {
 
         // Send to network manager for distribution
         if let Some(network_manager) = &self.network_manager {
-            network_manager.broadcast_clipboard_content(encrypted_content).await?;
+            let message = Message::new(
+                MessageType::ClipboardSync,
+                encrypted_content,
+                network_manager.device_system().to_string(),
+            )
+            .with_clock(stamp)
+            .with_trace(trace.with_hop(HopStage::Sent, &self.config.device_name));
+            self.send_to_allowed_peers(network_manager, message, &content.content_type, &content.metadata, pushed)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send an item's message to the peers its sync policy targets and whose sync mode sends it
+    async fn send_to_allowed_peers(
+        &self,
+        network_manager: &NetworkManager,
+        message: Message,
+        content_type: &clipboard::ContentType,
+        metadata: &clipboard::ContentMetadata,
+        pushed: bool,
+    ) -> Result<()> {
+        let sync = self.sync.read().await.clone();
+        let sends = |mode: SyncMode| if pushed { mode == SyncMode::Manual } else { mode.sends_automatically() };
+
+        if !pushed && sync.peers.is_empty() && !self.policies.is_targeted(content_type, metadata) {
+            if !sends(sync.mode) {
+                debug!("Not sending item {} in {} mode", message.header.message_id, sync.mode);
+                return Ok(());
+            }
+            return Ok(network_manager.broadcast_message(message).await?);
+        }
+
+        for (peer_id, device_name) in network_manager.get_connected_devices().await {
+            let device_name = device_name.as_deref();
+            if !sends(sync.mode_for(&peer_id, device_name)) {
+                debug!("Sync mode excludes {} from item {}", peer_id, message.header.message_id);
+            } else if !self.policies.allows_peer(content_type, metadata, &peer_id) {
+                debug!("Sync policy excludes {} from item {}", peer_id, message.header.message_id);
+            } else {
+                network_manager.send_message_to_peer(&peer_id, message.clone()).await?;
+            }
         }
 
         Ok(())
     }
 
+    /// Current default sync mode
+    pub async fn sync_mode(&self) -> SyncMode {
+        self.sync.read().await.mode
+    }
+
+    /// Switch the sync mode used with peers that have no override
+    pub async fn set_sync_mode(&self, mode: SyncMode) -> Result<()> {
+        info!("Switching to {} sync mode", mode);
+        self.sync.write().await.mode = mode;
+        self.sync_mode_changed().await
+    }
+
+    /// Set or clear (`None`) the sync mode for a peer, by device name or peer ID
+    pub async fn set_peer_sync_mode(&self, peer: &str, mode: Option<SyncMode>) -> Result<()> {
+        let mut sync = self.sync.write().await;
+        match mode {
+            Some(mode) => {
+                info!("Switching to {} sync mode with {}", mode, peer);
+                sync.peers.insert(peer.to_string(), mode);
+            }
+            None => {
+                sync.peers.remove(peer);
+            }
+        }
+        drop(sync);
+        self.sync_mode_changed().await
+    }
+
+    async fn sync_mode_changed(&self) -> Result<()> {
+        // Nothing is pushed once no peer is in manual mode
+        if !self.sync.read().await.uses_manual_push() {
+            self.push_queue.write().await.clear();
+        }
+
+        let event = events::Event::ConfigChanged {
+            section: "sync".to_string(),
+        };
+        self.event_bus.emit_without_waiting(EventWithMetadata::new(event, "system".to_string()))?;
+        Ok(())
+    }
+
+    /// Local copies waiting for a manual push, oldest first
+    pub async fn queued_items(&self) -> Vec<ClipboardContent> {
+        self.push_queue.read().await.iter().cloned().collect()
+    }
+
+    /// Push the most recent queued copy to peers in manual mode
+    ///
+    /// Returns `false` when nothing is queued. Meant for a push command or hotkey.
+    pub async fn push_latest(&self) -> Result<bool> {
+        let Some(content) = self.push_queue.write().await.pop_back() else {
+            return Ok(false);
+        };
+
+        info!("Pushing queued item {}", content.checksum);
+        self.distribute(content, self.ordering.clock().now(), true, self.push_trace()).await?;
+        Ok(true)
+    }
+
+    /// Push a specific queued copy to peers in manual mode
+    pub async fn push_item(&self, checksum: &str) -> Result<()> {
+        let content = {
+            let mut queue = self.push_queue.write().await;
+            let position = queue
+                .iter()
+                .position(|queued| queued.checksum == checksum)
+                .ok_or_else(|| format!("No queued item with checksum {}", checksum))?;
+            queue.remove(position)
+        };
+
+        if let Some(content) = content {
+            info!("Pushing queued item {}", checksum);
+            self.distribute(content, self.ordering.clock().now(), true, self.push_trace()).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply an item that was received in notify mode
+    pub async fn apply_received(&self, checksum: &str) -> Result<()> {
+        let content = self
+            .content_store
+            .get(checksum)
+            .await
+            .ok_or_else(|| format!("No received item with checksum {}", checksum))?;
+
+        let mut register = self.ordering.lock().await;
+        register.offer(&self.ordering.clock().now(), checksum);
+        if let Some(clipboard_monitor) = &self.clipboard_monitor {
+            clipboard_monitor.apply_content(&content).await?;
+        }
+        drop(register);
+        Ok(())
+    }
+
+    /// Sync mode an item received from a peer is handled in, if it is accepted at all
+    async fn accept_from(
+        &self,
+        content_type: &clipboard::ContentType,
+        metadata: &clipboard::ContentMetadata,
+        sender: &str,
+    ) -> Option<SyncMode> {
+        let device_name = self.peer_device_name(sender).await;
+        let mode = self.sync.read().await.mode_for(sender, device_name.as_deref());
+        if !mode.receives() {
+            info!("Ignoring item from {} in {} mode", sender, mode);
+            return None;
+        }
+
+        if let Err(e) = self.policies.check_receive(content_type, metadata, sender) {
+            info!("Ignoring item from {}: {}", sender, e);
+            return None;
+        }
+
+        Some(mode)
+    }
+
+    /// Device name a connected peer sent in its handshake
+    async fn peer_device_name(&self, peer_id: &str) -> Option<String> {
+        let network_manager = self.network_manager.as_ref()?;
+        network_manager
+            .get_connected_devices()
+            .await
+            .into_iter()
+            .find(|(peer, _)| peer == peer_id)
+            .and_then(|(_, device_name)| device_name)
+    }
+
+    /// Recompress an item with the codec negotiated with the connected peers
+    #[cfg(feature = "compression")]
+    async fn recompress_for_peers(&self, mut content: ClipboardContent) -> Result<ClipboardContent> {
+        let Some(network_manager) = &self.network_manager else {
+            return Ok(content);
+        };
+
+        let compression = content.metadata.compression;
+        if compression == clipboard::CompressionCodec::None {
+            return Ok(content);
+        }
+
+        // A codec picked by a sync policy is still only used if every peer supports it
+        let preferred = match self.policies.compression(&content.content_type, &content.metadata) {
+            Some(codec) => vec![codec],
+            None => self.config.clipboard.compression_codecs.clone(),
+        };
+        let codec = network_manager.negotiate_codec(&preferred).await;
+        if codec != compression {
+            debug!("Recompressing item from {} to {} for connected peers", compression, codec);
+            content.decompress(self.config.clipboard.max_content_size)?;
+            content.compress(codec)?;
+        }
+
+        Ok(content)
+    }
+
+    /// Keep a large item locally and broadcast only its metadata
+    async fn announce_content(
+        &self,
+        content: ClipboardContent,
+        stamp: HlcTimestamp,
+        pushed: bool,
+        trace: TraceContext,
+    ) -> Result<()> {
+        let announcement = content.announcement(self.config.clipboard.lazy.preview_chars);
+        self.content_store.insert(content).await?;
+
+        info!("Announcing {} byte item {}", announcement.metadata.size, announcement.checksum);
+
+        if let Some(network_manager) = &self.network_manager {
+            let payload = self.seal(&serde_json::to_vec(&announcement)?)?;
+            let message = Message::new(
+                MessageType::ClipboardAnnounce,
+                payload,
+                network_manager.device_system().to_string(),
+            )
+            .with_clock(stamp)
+            .with_trace(trace.with_hop(HopStage::Sent, &self.config.device_name));
+            self.send_to_allowed_peers(
+                network_manager,
+                message,
+                &announcement.content_type,
+                &announcement.metadata,
+                pushed,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Announced items that have not been fetched yet
+    pub async fn pending_announcements(&self) -> Vec<ContentAnnouncement> {
+        self.announcements
+            .read()
+            .await
+            .values()
+            .map(|pending| pending.announcement.clone())
+            .collect()
+    }
+
+    /// Fetch an announced item from the peer that announced it and apply it
+    ///
+    /// The item is fetched in chunks in a background task, which a concurrent
+    /// fetch of the same item joins. If the fetch fails the chunks received so
+    /// far are kept, and the next fetch (or the peer reconnecting) resumes it.
+    pub async fn fetch_content(&self, checksum: &str) -> Result<()> {
+        self.fetch_task(checksum).await?.await?;
+        self.apply_fetched(checksum).await
+    }
+
+    /// Start fetching an announced item in the background unless it is already being fetched
+    ///
+    /// Once the item is fetched the event loop applies it.
+    async fn start_fetch(&self, checksum: &str) -> Result<()> {
+        self.fetch_task(checksum).await.map(|_| ())
+    }
+
+    /// Start fetching an announced item in the background, or join the running fetch
+    async fn fetch_task(&self, checksum: &str) -> Result<FetchHandle> {
+        let mut fetches = self.fetches.lock().await;
+        if let Some(fetch) = fetches.get(checksum) {
+            return Ok(fetch.clone());
+        }
+
+        let peer = self
+            .announcements
+            .read()
+            .await
+            .get(checksum)
+            .map(|pending| pending.peer.clone())
+            .ok_or_else(|| format!("No announced item with checksum {}", checksum))?;
+        let requester = self
+            .network_manager
+            .as_ref()
+            .ok_or("Network manager not initialized")?
+            .requester()?;
+
+        let fetcher = ContentFetcher {
+            requester,
+            encryption_service: self.encryption_service.clone(),
+            lazy: self.config.clipboard.lazy.clone(),
+            event_bus: self.event_bus.clone(),
+            content_store: self.content_store.clone(),
+            transfers: self.transfers.clone(),
+        };
+        let registry = self.fetches.clone();
+        let fetched_sender = self.fetched_sender.clone();
+        let task_checksum = checksum.to_string();
+
+        // The fetch lock is held until the handle is registered, so the task
+        // cannot unregister itself before that
+        let task = tokio::spawn(async move {
+            let result = fetcher.fetch(&task_checksum, &peer).await.map_err(|e| e.to_string());
+            registry.lock().await.remove(&task_checksum);
+            if result.is_ok() {
+                let _ = fetched_sender.send(task_checksum);
+            }
+            result
+        });
+
+        let fetch = async move {
+            task.await
+                .unwrap_or_else(|e| Err(format!("Fetch task failed: {}", e)))
+        }
+        .boxed()
+        .shared();
+        fetches.insert(checksum.to_string(), fetch.clone());
+
+        Ok(fetch)
+    }
+
+    /// Apply an item fetched in the background, unless it was applied already
+    async fn apply_fetched(&self, checksum: &str) -> Result<()> {
+        let Some(pending) = self.announcements.write().await.remove(checksum) else {
+            return Ok(());
+        };
+        let content = self
+            .content_store
+            .get(checksum)
+            .await
+            .ok_or_else(|| format!("Fetched item {} was evicted before it was applied", checksum))?;
+
+        self.apply_remote_content(&content, &pending.peer, &pending.clock, pending.trace).await
+    }
+
+    /// Resume interrupted transfers from a peer that reconnected
+    async fn resume_transfers(&self, peer_id: &str) {
+        let checksums: Vec<String> = {
+            let transfers = self.transfers.read().await;
+            let announcements = self.announcements.read().await;
+            transfers
+                .keys()
+                .filter(|checksum| announcements.get(*checksum).is_some_and(|pending| pending.peer == peer_id))
+                .cloned()
+                .collect()
+        };
+
+        for checksum in checksums {
+            if let Err(e) = self.start_fetch(&checksum).await {
+                warn!("Failed to resume transfer of {}: {}", checksum, e);
+            }
+        }
+    }
+
     async fn handle_network_message(
         &self,
         message: network::Message,
         sender: String,
     ) -> Result<()> {
-        info!("Handling network message from: {}", sender);
+        info!("Handling {} message from: {}", message.header.message_type, sender);
+
+        let is_update = matches!(
+            message.header.message_type,
+            MessageType::ClipboardSync | MessageType::ClipboardAnnounce
+        );
+        if is_update && !self.accept_update(&message, &sender).await {
+            return Ok(());
+        }
+
+        match message.header.message_type {
+            MessageType::ClipboardSync => self.handle_clipboard_sync(message, sender).await,
+            MessageType::ClipboardAnnounce => self.handle_announcement(message, sender).await,
+            MessageType::ContentRequest | MessageType::ChunkRequest => {
+                self.serve_content_request(message, sender).await
+            }
+            other => {
+                debug!("Ignoring {} message from {}", other, sender);
+                Ok(())
+            }
+        }
+    }
+
+    /// Drop duplicate and echoed clipboard updates and merge the sender's clock
+    async fn accept_update(&self, message: &Message, sender: &str) -> bool {
+        if !self.seen_messages.write().await.insert(&message.header.message_id) {
+            debug!("Dropping duplicate message {} from {}", message.header.message_id, sender);
+            return false;
+        }
 
-        // Decrypt message if encryption is enabled
-        let decrypted_content = if let Some(encryption_service) = &self.encryption_service {
-            encryption_service.decrypt_message(&message)?
+        if message.header.origin.as_deref() == Some(self.ordering.clock().device_id()) {
+            debug!("Dropping echo of our own update {} from {}", message.header.message_id, sender);
+            return false;
+        }
+
+        if let Some(stamp) = &message.header.clock {
+            if let Err(e) = self.ordering.clock().observe(stamp) {
+                warn!("Dropping update from {}: {}", sender, e);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Timestamp of a received update; updates from peers without a clock count as current
+    fn update_stamp(&self, message: &Message, sender: &str) -> HlcTimestamp {
+        message.header.clock.clone().unwrap_or_else(|| HlcTimestamp {
+            device_id: sender.to_string(),
+            ..self.ordering.clock().now()
+        })
+    }
+
+    async fn handle_clipboard_sync(&self, message: Message, sender: String) -> Result<()> {
+        let decrypted_content = self.unseal(&message.payload)?;
+        let stamp = self.update_stamp(&message, &sender);
+
+        match serde_json::from_slice::<ClipboardContent>(&decrypted_content) {
+            Ok(content) => self.apply_remote_content(&content, &sender, &stamp, message.header.trace).await,
+            Err(_) => match String::from_utf8(decrypted_content) {
+                // Raw payloads are treated as text, subject to the same receive checks
+                Ok(text) => {
+                    let content = ClipboardContent::new_text(text, sender.clone());
+                    self.apply_remote_content(&content, &sender, &stamp, message.header.trace).await
+                }
+                Err(_) => {
+                    warn!("Dropping clipboard sync from {} that is neither an item nor text", sender);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    async fn handle_announcement(&self, message: Message, sender: String) -> Result<()> {
+        let announcement: ContentAnnouncement = serde_json::from_slice(&self.unseal(&message.payload)?)?;
+        let stamp = self.update_stamp(&message, &sender);
+
+        if announcement.is_expired() {
+            info!("Dropping expired announcement from: {}", sender);
+            return Ok(());
+        }
+
+        let stale = self
+            .ordering
+            .lock()
+            .await
+            .current()
+            .is_some_and(|(current, _)| stamp <= *current);
+        if stale {
+            info!("Ignoring announcement from {}, a later update is current", sender);
+            return Ok(());
+        }
+
+        if self
+            .accept_from(&announcement.content_type, &announcement.metadata, &sender)
+            .await
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        if let Some(content) = self.content_store.get(&announcement.checksum).await {
+            debug!("Announced item {} is already cached", announcement.checksum);
+            return self.apply_remote_content(&content, &sender, &stamp, message.header.trace).await;
+        }
+
+        let checksum = announcement.checksum.clone();
+        self.announcements.write().await.insert(
+            checksum.clone(),
+            PendingAnnouncement {
+                peer: sender.clone(),
+                announcement: announcement.clone(),
+                clock: stamp,
+                trace: message.header.trace.clone(),
+            },
+        );
+
+        let event = events::Event::ContentAnnounced {
+            announcement,
+            from_peer: sender,
+        };
+        self.event_bus.emit_without_waiting(EventWithMetadata::new(event, "system".to_string()))?;
+
+        // Without lazy transfers enabled, items are only announced when too large to push
+        let lazy = &self.config.clipboard.lazy;
+        if lazy.auto_fetch || !lazy.enabled {
+            self.start_fetch(&checksum).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn serve_content_request(&self, message: Message, sender: String) -> Result<()> {
+        let network_manager = self
+            .network_manager
+            .as_ref()
+            .ok_or("Network manager not initialized")?;
+        let request = self.unseal(&message.payload)?;
+        let chunk_size = self.config.clipboard.lazy.chunk_size_bytes;
+
+        let (response_type, body) = if message.header.message_type == MessageType::ChunkRequest {
+            let request: ChunkRequest = serde_json::from_slice(&request)?;
+            let chunk = self
+                .content_store
+                .with_content(&request.checksum, |content| {
+                    // Checked for every chunk, as a peer may ask for chunks without the manifest
+                    self.policies
+                        .allows_peer(&content.content_type, &content.metadata, &sender)
+                        .then(|| content.data.chunks(chunk_size).nth(request.index).map(<[u8]>::to_vec))
+                        .flatten()
+                })
+                .await
+                .flatten();
+            (MessageType::ChunkResponse, chunk)
         } else {
-            message.payload
+            let checksum = String::from_utf8(request)?;
+            info!("Serving item {} to {}", checksum, sender);
+            let manifest = self
+                .content_store
+                .with_content(&checksum, |content| {
+                    // Items a sync policy keeps from this peer are reported as missing
+                    self.policies
+                        .allows_peer(&content.content_type, &content.metadata, &sender)
+                        .then(|| TransferManifest::new(content, chunk_size))
+                })
+                .await
+                .flatten()
+                .map(|manifest| serde_json::to_vec(&manifest))
+                .transpose()?;
+            (MessageType::ContentResponse, manifest)
+        };
+
+        let device_system = network_manager.device_system().to_string();
+        let response = match body {
+            Some(body) => Message::new(response_type, self.seal(&body)?, device_system),
+            None => {
+                warn!("{} requested content that is not cached", sender);
+                Message::new(MessageType::Error, b"content not found".to_vec(), device_system)
+            }
+        };
+
+        network_manager.respond(&message.header.message_id, response).await?;
+        Ok(())
+    }
+
+    async fn apply_remote_content(
+        &self,
+        content: &ClipboardContent,
+        sender: &str,
+        stamp: &HlcTimestamp,
+        trace: Option<TraceContext>,
+    ) -> Result<()> {
+        // Items synced with an expiry must not be applied once they have expired
+        if content.is_expired() {
+            info!("Dropping expired clipboard content from: {}", sender);
+            return Ok(());
+        }
+
+        let Some(mode) = self
+            .accept_from(&content.content_type, &content.metadata, sender)
+            .await
+        else {
+            return Ok(());
         };
+        let content = &self.prepare_received(content)?;
+
+        // Concurrent copies resolve to the same winner on every device. The
+        // register stays locked until the item is on the clipboard, so the
+        // monitor cannot stamp a local copy in between.
+        let ordered = mode.applies() && content.metadata.selection == clipboard::Selection::Clipboard;
+        let mut register = if ordered { Some(self.ordering.lock().await) } else { None };
+        if let Some(register) = register.as_mut() {
+            match register.offer(stamp, &content.checksum) {
+                Resolution::Apply => {}
+                Resolution::Stale => {
+                    info!("Dropping item from {}, a later update than {} is current", sender, stamp);
+                    return Ok(());
+                }
+                Resolution::Duplicate => {
+                    debug!("Item {} from {} is already current", content.checksum, sender);
+                    return Ok(());
+                }
+            }
+        }
+
+        let record_history = content.metadata.selection == clipboard::Selection::Clipboard;
+        if let Some(history) = self.history.as_ref().filter(|_| record_history) {
+            if let Err(e) = history.record(content).await {
+                warn!("Failed to record clipboard history: {}", e);
+            }
+        }
+
+        // In notify mode the item is kept for `apply_received` instead of clobbering the clipboard
+        if !mode.applies() {
+            if let Err(e) = self.content_store.insert(content.clone()).await {
+                warn!("Failed to keep received item {}: {}", content.checksum, e);
+            }
+            let event = events::Event::ClipboardReceived {
+                content: content.clone(),
+                from_peer: sender.to_string(),
+            };
+            self.event_bus
+                .emit_without_waiting(EventWithMetadata::new(event, "app".to_string()).with_trace(trace))?;
+            return Ok(());
+        }
 
         // Update local clipboard
         if let Some(clipboard_monitor) = &self.clipboard_monitor {
-            clipboard_monitor.update_clipboard(decrypted_content).await?;
+            clipboard_monitor.apply_content(content).await?;
         }
+        drop(register);
 
+        // The metrics handler records the sync latency from the trace
+        let trace = trace.map(|trace| trace.with_hop(HopStage::Applied, &self.config.device_name));
+        let event = events::Event::ClipboardSynced {
+            from_peer: sender.to_string(),
+            content_size: content.metadata.size,
+        };
+        self.event_bus
+            .emit_without_waiting(EventWithMetadata::new(event, "app".to_string()).with_trace(trace))?;
         Ok(())
     }
+
+    /// Trace of a queued item pushed now; when it was copied is not known
+    fn push_trace(&self) -> TraceContext {
+        TraceContext::start(HopStage::Sent, &self.config.device_name)
+    }
+
+    /// Metrics collected from the application's events
+    ///
+    /// Names are listed in [`events::handlers`], e.g.
+    /// [`SYNC_LATENCY_METRIC`](events::handlers::SYNC_LATENCY_METRIC).
+    pub fn metrics(&self) -> Arc<utils::metrics::PerformanceMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Current counters, gauges and durations, including sync latency
+    pub async fn metrics_snapshot(&self) -> utils::metrics::MetricsSummary {
+        self.metrics.get_summary().await
+    }
+
+    /// Decompress and transform a received item
+    ///
+    /// The item is then ordered by the checksum of the data put on the clipboard,
+    /// which the monitor recognizes when it picks the item up again.
+    fn prepare_received(&self, content: &ClipboardContent) -> Result<ClipboardContent> {
+        #[allow(unused_mut)]
+        let mut content = content.clone();
+        #[cfg(feature = "compression")]
+        content.decompress(self.config.clipboard.max_content_size)?;
+        Ok(self.transforms.apply_on_receive(content))
+    }
+
+    /// Encrypt an outgoing payload if encryption is set up
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>> {
+        seal(self.encryption_service.as_deref(), data)
+    }
+
+    /// Decrypt an incoming payload if encryption is set up
+    fn unseal(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        unseal(self.encryption_service.as_deref(), payload)
+    }
+}
+
+/// What a background fetch needs from the app
+struct ContentFetcher {
+    requester: PeerRequester,
+    encryption_service: Option<Arc<crypto::EncryptionService>>,
+    lazy: config::LazyTransferConfig,
+    event_bus: Arc<EventBus>,
+    content_store: Arc<clipboard::ContentStore>,
+    transfers: Arc<RwLock<HashMap<String, IncomingTransfer>>>,
+}
+
+impl ContentFetcher {
+    /// Fetch an item in chunks into the content store
+    ///
+    /// On failure the chunks received so far are kept for resuming.
+    async fn fetch(&self, checksum: &str, peer: &str) -> Result<()> {
+        let resumed = self.transfers.write().await.remove(checksum);
+        let mut transfer = match resumed {
+            Some(transfer) => {
+                info!("Resuming item {} from {} at {} bytes", checksum, peer, transfer.bytes_done());
+                transfer
+            }
+            None => {
+                info!("Fetching item {} from {}", checksum, peer);
+                let response = self
+                    .request_with_retry(peer, MessageType::ContentRequest, checksum.as_bytes())
+                    .await?;
+                let manifest: TransferManifest = serde_json::from_slice(&self.unseal(&response.payload)?)?;
+                if manifest.checksum != checksum {
+                    return Err(clipboard::ClipboardError::ChecksumMismatch(checksum.to_string()).into());
+                }
+                IncomingTransfer::new(manifest, self.lazy.cache_max_bytes)?
+            }
+        };
+
+        if let Err(e) = self.receive_chunks(peer, &mut transfer).await {
+            warn!(
+                "Transfer of {} paused at {}/{} bytes: {}",
+                checksum,
+                transfer.bytes_done(),
+                transfer.total_bytes(),
+                e
+            );
+            self.transfers.write().await.insert(checksum.to_string(), transfer);
+            return Err(e);
+        }
+
+        let content = transfer.into_content()?;
+        self.content_store.insert(content).await?;
+        Ok(())
+    }
+
+    async fn receive_chunks(&self, peer: &str, transfer: &mut IncomingTransfer) -> Result<()> {
+        let checksum = transfer.manifest().checksum.clone();
+
+        while let Some(index) = transfer.next_missing() {
+            let request = serde_json::to_vec(&ChunkRequest {
+                checksum: checksum.clone(),
+                index,
+            })?;
+            let response = self
+                .request_with_retry(peer, MessageType::ChunkRequest, &request)
+                .await?;
+            transfer.accept(index, &self.unseal(&response.payload)?)?;
+
+            let event = events::Event::TransferProgress {
+                checksum: checksum.clone(),
+                from_peer: peer.to_string(),
+                bytes_done: transfer.bytes_done() as u64,
+                total_bytes: transfer.total_bytes() as u64,
+            };
+            self.event_bus.emit_without_waiting(EventWithMetadata::new(event, "system".to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a request, retrying while the peer is unreachable
+    async fn request_with_retry(&self, peer: &str, message_type: MessageType, payload: &[u8]) -> Result<Message> {
+        let mut attempt = 1;
+
+        loop {
+            let request = Message::new(
+                message_type,
+                seal(self.encryption_service.as_deref(), payload)?,
+                self.requester.device_system().to_string(),
+            );
+
+            match self.requester.request(peer, request).await {
+                Ok(response) if response.header.message_type == MessageType::Error => {
+                    return Err(format!(
+                        "{} rejected {}: {}",
+                        peer,
+                        message_type,
+                        String::from_utf8_lossy(&response.payload)
+                    ).into());
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if attempt < self.lazy.chunk_retries => {
+                    debug!("{} to {} failed (attempt {}): {}", message_type, peer, attempt, e);
+                    attempt += 1;
+                    tokio::time::sleep(self.lazy.chunk_retry_delay()).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn unseal(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        unseal(self.encryption_service.as_deref(), payload)
+    }
+}
+
+/// Encrypt an outgoing payload if encryption is set up
+fn seal(encryption_service: Option<&crypto::EncryptionService>, data: &[u8]) -> Result<Vec<u8>> {
+    match encryption_service {
+        Some(encryption_service) => Ok(encryption_service.encrypt(data)?),
+        None => Ok(data.to_vec()),
+    }
+}
+
+/// Decrypt an incoming payload if encryption is set up
+fn unseal(encryption_service: Option<&crypto::EncryptionService>, payload: &[u8]) -> Result<Vec<u8>> {
+    match encryption_service {
+        Some(encryption_service) => Ok(encryption_service.decrypt(payload)?),
+        None => Ok(payload.to_vec()),
+    }
 }
 
 #[cfg(test)]
@@ -281,4 +1362,566 @@ mod tests {
         let app = CrossCopyApp::new(config).await;
         assert!(app.is_ok());
     }
+
+    /// Start an app's network layer on a free port with a fixed key and no mDNS
+    async fn lazy_app(device_name: &str, configure: impl Fn(&mut AppConfig)) -> Arc<CrossCopyApp> {
+        let mut config = AppConfig {
+            device_name: device_name.to_string(),
+            ..AppConfig::default()
+        };
+        config.network.listen_port = 0;
+        config.network.enable_mdns = false;
+        config.clipboard.lazy.enabled = true;
+        config.clipboard.lazy.threshold_bytes = 16;
+        config.clipboard.lazy.chunk_size_bytes = 64;
+        configure(&mut config);
+
+        let mut app = CrossCopyApp::new(config).await.unwrap();
+        app.encryption_service = Some(Arc::new(crypto::EncryptionService::new(&[7u8; 32])));
+        app.clipboard_monitor = Some(
+            clipboard::ClipboardMonitor::with_backend(
+                app.config.clipboard.clone(),
+                app.event_bus.clone(),
+                Arc::new(clipboard::MemoryClipboard::new()),
+            )
+            .unwrap(),
+        );
+        app.init_network_manager().await.unwrap();
+        app.start_services().await.unwrap();
+        *app.running.write().await = true;
+        Arc::new(app)
+    }
+
+    /// Dial `to` from `from` over loopback and wait until both sides are connected
+    async fn connect(from: &CrossCopyApp, to: &CrossCopyApp) {
+        let manager_from = from.network_manager.as_ref().unwrap();
+        let manager_to = to.network_manager.as_ref().unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let address = loop {
+            let loopback = manager_to
+                .listen_addresses()
+                .await
+                .into_iter()
+                .find(|address| address.to_string().starts_with("/ip4/127.0.0.1/"));
+            if let Some(address) = loopback {
+                break address;
+            }
+            assert!(std::time::Instant::now() < deadline, "listener did not come up");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        };
+
+        manager_from.dial(address).await.unwrap();
+        while manager_from.get_connection_count().await == 0 || manager_to.get_connection_count().await == 0 {
+            assert!(std::time::Instant::now() < deadline, "peers did not connect");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Two connected apps running their event loops, the second dialing the first
+    async fn connected_apps(configure: fn(&mut AppConfig)) -> (Arc<CrossCopyApp>, Arc<CrossCopyApp>) {
+        let app_a = lazy_app("device-a", configure).await;
+        let app_b = lazy_app("device-b", configure).await;
+        run_connected(app_a, app_b).await
+    }
+
+    /// Connect two apps, the second dialing the first, and run their event loops
+    async fn run_connected(
+        app_a: Arc<CrossCopyApp>,
+        app_b: Arc<CrossCopyApp>,
+    ) -> (Arc<CrossCopyApp>, Arc<CrossCopyApp>) {
+        connect(&app_b, &app_a).await;
+
+        let loop_a = app_a.clone();
+        let loop_b = app_b.clone();
+        tokio::spawn(async move { loop_a.event_loop().await });
+        tokio::spawn(async move { loop_b.event_loop().await });
+
+        (app_a, app_b)
+    }
+
+    /// Two apps that find each other through mDNS, once each sees the other connected
+    ///
+    /// Their event loops are not started, so events from connecting are still queued.
+    async fn discovered_apps(configure: fn(&mut AppConfig)) -> (Arc<CrossCopyApp>, Arc<CrossCopyApp>) {
+        let with_mdns = |config: &mut AppConfig| {
+            configure(config);
+            config.network.enable_mdns = true;
+        };
+        let app_a = lazy_app("device-a", with_mdns).await;
+        let app_b = lazy_app("device-b", with_mdns).await;
+
+        let manager_a = app_a.network_manager.as_ref().unwrap();
+        let manager_b = app_b.network_manager.as_ref().unwrap();
+        let peer_a = manager_a.local_peer_id().to_string();
+        let peer_b = manager_b.local_peer_id().to_string();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !manager_a.get_connected_peers().await.contains(&peer_b)
+            || !manager_b.get_connected_peers().await.contains(&peer_a)
+        {
+            assert!(std::time::Instant::now() < deadline, "peers did not discover each other");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        (app_a, app_b)
+    }
+
+    /// Text on an app's in-memory clipboard
+    fn clipboard_text(app: &CrossCopyApp) -> Option<String> {
+        let backend = app.clipboard_monitor.as_ref().unwrap().backend();
+        let data = backend.get(clipboard::backend::MIME_TEXT).unwrap()?;
+        String::from_utf8(data).ok()
+    }
+
+    async fn wait_for(what: &str, mut condition: impl FnMut() -> bool) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !condition() {
+            assert!(std::time::Instant::now() < deadline, "{} did not happen", what);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Announce an item from `app_a` and wait for `app_b` to receive the announcement
+    async fn announce(app_a: &CrossCopyApp, app_b: &CrossCopyApp, content: ClipboardContent) -> ContentAnnouncement {
+        app_a.handle_clipboard_change(content, "test".to_string(), None).await.unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            if let Some(announcement) = app_b.pending_announcements().await.pop() {
+                return announcement;
+            }
+            assert!(std::time::Instant::now() < deadline, "announcement did not arrive");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_items_are_announced_and_fetched() {
+        let (app_a, app_b) = connected_apps(|_| {}).await;
+
+        // Only the announcement travels until the body is requested
+        let content = ClipboardContent::new_text("a fairly large clipboard item".repeat(10), "test".to_string());
+        let checksum = content.checksum.clone();
+        let announcement = announce(&app_a, &app_b, content).await;
+        assert_eq!(announcement.checksum, checksum);
+        assert!(announcement.preview.unwrap().starts_with("a fairly large"));
+        assert!(!app_b.content_store.contains(&checksum).await);
+
+        let mut progress = app_b
+            .event_bus
+            .subscribe_filtered(events::EventFilter::new().kind(events::EventKind::TransferProgress));
+        app_b.fetch_content(&checksum).await.unwrap();
+        assert!(app_b.content_store.contains(&checksum).await);
+        assert!(app_b.pending_announcements().await.is_empty());
+        assert_eq!(clipboard_text(&app_b), Some("a fairly large clipboard item".repeat(10)));
+
+        // One progress event per chunk, ending at the full size
+        let mut reported = Vec::new();
+        while let Some(Some(event)) = progress.next().now_or_never() {
+            if let events::Event::TransferProgress { bytes_done, total_bytes, .. } = event.event {
+                reported.push((bytes_done, total_bytes));
+            }
+        }
+        assert_eq!(reported.len(), 290usize.div_ceil(64));
+        assert!(reported.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(reported.last(), Some(&(290, 290)));
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_transfer_resumes_on_reconnect() {
+        let (app_a, app_b) = connected_apps(|config| config.clipboard.lazy.chunk_retries = 1).await;
+
+        let text = "resumable transfer ".repeat(20);
+        let content = ClipboardContent::new_text(text.clone(), "test".to_string());
+        let checksum = content.checksum.clone();
+        announce(&app_a, &app_b, content.clone()).await;
+
+        // The first chunks arrived before the connection dropped
+        let manifest = TransferManifest::new(&content, 64);
+        let mut transfer = IncomingTransfer::new(manifest.clone(), usize::MAX).unwrap();
+        for index in 0..3 {
+            transfer.accept(index, &content.data[manifest.chunk_range(index).unwrap()]).unwrap();
+        }
+        app_b.transfers.write().await.insert(checksum.clone(), transfer);
+
+        let manager_b = app_b.network_manager.as_ref().unwrap();
+        let peer_a = app_a.network_manager.as_ref().unwrap().local_peer_id().to_string();
+        manager_b.disconnect(&peer_a).await.unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while manager_b.get_connection_count().await > 0 {
+            assert!(std::time::Instant::now() < deadline, "peer did not disconnect");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        // Fetching while disconnected keeps the chunks received so far
+        assert!(app_b.fetch_content(&checksum).await.is_err());
+        assert_eq!(app_b.transfers.read().await.get(&checksum).map(|t| t.bytes_done()), Some(192));
+
+        // Reconnecting resumes the transfer in the background and applies the item
+        connect(&app_b, &app_a).await;
+        wait_for("resumed item applied", || clipboard_text(&app_b).as_deref() == Some(text.as_str())).await;
+        assert!(app_b.transfers.read().await.is_empty());
+        assert!(app_b.pending_announcements().await.is_empty());
+        assert_eq!(app_b.content_store.get(&checksum).await.unwrap().data, content.data);
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_discovered_peers_exchange_handshakes() {
+        let (app_a, app_b) = discovered_apps(|_| {}).await;
+        let manager_a = app_a.network_manager.as_ref().unwrap();
+        let manager_b = app_b.network_manager.as_ref().unwrap();
+        let peer_a = manager_a.local_peer_id().to_string();
+        let peer_b = manager_b.local_peer_id().to_string();
+
+        // Connecting to a discovered peer sends the handshake, naming the device
+        // and offering more than the gzip assumed for peers without one
+        let preferred = clipboard::CompressionCodec::supported();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let named = |peer: &str, name: &str| (peer.to_string(), Some(name.to_string()));
+        loop {
+            let named_a = manager_b.get_connected_devices().await.contains(&named(&peer_a, "device-a"));
+            let named_b = manager_a.get_connected_devices().await.contains(&named(&peer_b, "device-b"));
+            if named_a && named_b && manager_a.negotiate_codec(&preferred).await == preferred[0] {
+                break;
+            }
+            assert!(std::time::Instant::now() < deadline, "handshakes were not exchanged");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        // The connection was reported once and not dropped by discovery
+        let mut events = Vec::new();
+        while let Ok(Some(event)) =
+            tokio::time::timeout(std::time::Duration::from_millis(100), app_a.events.lock().await.next()).await
+        {
+            events.push(event.event);
+        }
+        let connected = events
+            .iter()
+            .filter(|event| matches!(event, events::Event::PeerConnected { peer_id } if *peer_id == peer_b));
+        assert_eq!(connected.count(), 1);
+        let disconnected = events
+            .iter()
+            .filter(|event| matches!(event, events::Event::PeerDisconnected { peer_id } if *peer_id == peer_b));
+        assert_eq!(disconnected.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_peers_gauge_matches_network_stats() {
+        let (app_a, app_b) = discovered_apps(|_| {}).await;
+        let manager_a = app_a.network_manager.as_ref().unwrap();
+        let peer_b = app_b.network_manager.as_ref().unwrap().local_peer_id().to_string();
+
+        // The gauge follows connection events, which discovered peers emit too
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let gauge = app_a.metrics().get_gauge(events::handlers::PEERS_CONNECTED_METRIC).await;
+            let connected = manager_a.get_network_stats().await.peers_connected;
+            if connected > 0 && gauge == Some(connected as f64) {
+                break;
+            }
+            assert!(std::time::Instant::now() < deadline, "gauge {:?} != {} peers", gauge, connected);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        manager_a.disconnect(&peer_b).await.unwrap();
+        loop {
+            let gauge = app_a.metrics().get_gauge(events::handlers::PEERS_CONNECTED_METRIC).await;
+            let stats = manager_a.get_network_stats().await;
+            if stats.peers_disconnected > 0 && gauge == Some(stats.peers_connected as f64) {
+                break;
+            }
+            assert!(std::time::Instant::now() < deadline, "gauge {:?} != {} peers", gauge, stats.peers_connected);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_transfer_resumes_when_peer_is_discovered() {
+        let (app_a, app_b) = discovered_apps(|config| config.clipboard.lazy.chunk_retries = 1).await;
+        let peer_a = app_a.network_manager.as_ref().unwrap().local_peer_id().to_string();
+
+        // b kept the first chunks of an item a announced before they lost each other
+        let text = "resumable transfer ".repeat(20);
+        let content = ClipboardContent::new_text(text.clone(), "test".to_string());
+        let checksum = content.checksum.clone();
+        app_a.content_store.insert(content.clone()).await.unwrap();
+        let pending = PendingAnnouncement {
+            peer: peer_a,
+            announcement: content.announcement(16),
+            clock: app_a.ordering.clock().now(),
+            trace: None,
+        };
+        app_b.announcements.write().await.insert(checksum.clone(), pending);
+
+        let manifest = TransferManifest::new(&content, 64);
+        let mut transfer = IncomingTransfer::new(manifest.clone(), usize::MAX).unwrap();
+        for index in 0..3 {
+            transfer.accept(index, &content.data[manifest.chunk_range(index).unwrap()]).unwrap();
+        }
+        app_b.transfers.write().await.insert(checksum.clone(), transfer);
+
+        // Connecting to the discovered peer resumes the transfer and applies the item
+        for app in [&app_a, &app_b] {
+            let app = app.clone();
+            tokio::spawn(async move { app.event_loop().await });
+        }
+        wait_for("resumed item applied", || clipboard_text(&app_b).as_deref() == Some(text.as_str())).await;
+        assert!(app_b.transfers.read().await.is_empty());
+        assert_eq!(app_b.content_store.get(&checksum).await.unwrap().data, content.data);
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_policy_targets_limit_recipients() {
+        // b calls itself "phone", but targets only match names bound to peer IDs
+        let app_b = lazy_app("phone", |_| {}).await;
+        let peer_b = app_b.network_manager.as_ref().unwrap().local_peer_id().to_string();
+        let app_a = lazy_app("device-a", |config| {
+            config.clipboard.policies = config::PolicyConfig {
+                rules: vec![
+                    config::SyncPolicy {
+                        name: "images".to_string(),
+                        content_type: None,
+                        mime: Some("image/*".to_string()),
+                        kind: None,
+                        enabled: true,
+                        max_size: None,
+                        direction: config::SyncDirection::Both,
+                        targets: vec!["phone".to_string()],
+                        compression: None,
+                    },
+                    config::SyncPolicy {
+                        name: "text".to_string(),
+                        content_type: Some(clipboard::ContentType::Text),
+                        mime: None,
+                        kind: None,
+                        enabled: true,
+                        max_size: None,
+                        direction: config::SyncDirection::Both,
+                        targets: vec!["laptops".to_string()],
+                        compression: None,
+                    },
+                ],
+                groups: HashMap::from([("laptops".to_string(), vec!["laptop-b".to_string()])]),
+                devices: HashMap::from([("laptop-b".to_string(), peer_b.clone())]),
+            };
+        })
+        .await;
+        let (app_a, app_b) = run_connected(app_a, app_b).await;
+
+        // Wait for b's handshake so its self-reported name is known
+        let manager_a = app_a.network_manager.as_ref().unwrap();
+        while manager_a.get_connected_devices().await.iter().all(|(_, name)| name.is_none()) {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let image = ClipboardContent::new_image(vec![0x42; 256], "image/png".to_string(), "test".to_string());
+        app_a.handle_clipboard_change(image.clone(), "test".to_string(), None).await.unwrap();
+
+        let text = ClipboardContent::new_text("text for laptops only ".repeat(5), "test".to_string());
+        let announcement = announce(&app_a, &app_b, text.clone()).await;
+        assert_eq!(announcement.checksum, text.checksum);
+        assert!(app_b.pending_announcements().await.iter().all(|a| a.checksum != image.checksum));
+
+        // Knowing the checksum is not enough to fetch the image chunk by chunk
+        let manager_b = app_b.network_manager.as_ref().unwrap();
+        let peer_a = manager_a.local_peer_id().to_string();
+        let request_chunk = |checksum: &str| {
+            let request = serde_json::to_vec(&ChunkRequest { checksum: checksum.to_string(), index: 0 }).unwrap();
+            Message::new(MessageType::ChunkRequest, app_b.seal(&request).unwrap(), "test".to_string())
+        };
+        let refused = manager_b.request(&peer_a, request_chunk(&image.checksum)).await.unwrap();
+        assert_eq!(refused.header.message_type, MessageType::Error);
+        let served = manager_b.request(&peer_a, request_chunk(&text.checksum)).await.unwrap();
+        assert_eq!(served.header.message_type, MessageType::ChunkResponse);
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_peers_fetch_from_each_other_at_once() {
+        let (app_a, app_b) = connected_apps(|config| config.clipboard.lazy.auto_fetch = true).await;
+
+        // b starts fetching a's copy, and copies something itself while that runs
+        let first = "copied on a ".repeat(100);
+        let second = "copied on b ".repeat(100);
+        let from_a = ClipboardContent::new_text(first, "test".to_string());
+        announce(&app_a, &app_b, from_a.clone()).await;
+        app_b
+            .handle_clipboard_change(ClipboardContent::new_text(second.clone(), "test".to_string()), "test".to_string(), None)
+            .await
+            .unwrap();
+
+        // Each event loop keeps serving chunks while its own fetch is running
+        wait_for("later copy applied on a", || clipboard_text(&app_a).as_deref() == Some(second.as_str())).await;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !app_b.content_store.contains(&from_a.checksum).await {
+            assert!(std::time::Instant::now() < deadline, "fetch from a did not complete");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_sync_modes_switch_at_runtime() {
+        let (app_a, app_b) = connected_apps(|config| config.clipboard.lazy.enabled = false).await;
+        let manager_a = app_a.network_manager.as_ref().unwrap();
+        let text = |text: &str| ClipboardContent::new_text(text.to_string(), "test".to_string());
+
+        // Manual mode queues copies until they are pushed
+        app_a.set_sync_mode(SyncMode::Manual).await.unwrap();
+        let sent_before = manager_a.get_network_stats().await.messages_sent;
+        app_a.handle_clipboard_change(text("queued"), "test".to_string(), None).await.unwrap();
+        assert_eq!(manager_a.get_network_stats().await.messages_sent, sent_before);
+        assert_eq!(app_a.queued_items().await.len(), 1);
+
+        assert!(app_a.push_latest().await.unwrap());
+        wait_for("pushed item applied", || clipboard_text(&app_b).as_deref() == Some("queued")).await;
+        assert!(!app_a.push_latest().await.unwrap());
+
+        // Notify mode keeps received items off the clipboard until applied
+        app_a.set_sync_mode(SyncMode::Auto).await.unwrap();
+        app_b.set_sync_mode(SyncMode::Notify).await.unwrap();
+        let notified = text("notified");
+        app_a.handle_clipboard_change(notified.clone(), "test".to_string(), None).await.unwrap();
+
+        let store = app_b.content_store.clone();
+        let checksum = notified.checksum.clone();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !store.contains(&checksum).await {
+            assert!(std::time::Instant::now() < deadline, "notified item did not arrive");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(clipboard_text(&app_b).as_deref(), Some("queued"));
+
+        app_b.apply_received(&checksum).await.unwrap();
+        assert_eq!(clipboard_text(&app_b).as_deref(), Some("notified"));
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_raw_sync_payloads_follow_receive_mode() {
+        let app = lazy_app("device-b", |config| config.clipboard.lazy.enabled = false).await;
+        let raw = |text: &str| {
+            let payload = app.seal(text.as_bytes()).unwrap();
+            Message::new(MessageType::ClipboardSync, payload, "test".to_string())
+        };
+
+        // Payloads that are not items are still text from a peer, not a local copy
+        app.set_sync_mode(SyncMode::SendOnly).await.unwrap();
+        app.handle_clipboard_sync(raw("ignored"), "peer".to_string()).await.unwrap();
+        assert_eq!(clipboard_text(&app), None);
+
+        app.set_sync_mode(SyncMode::Auto).await.unwrap();
+        app.handle_clipboard_sync(raw("applied"), "peer".to_string()).await.unwrap();
+        assert_eq!(clipboard_text(&app).as_deref(), Some("applied"));
+    }
+
+    #[tokio::test]
+    async fn test_transforms_apply_on_send_and_receive() {
+        let (app_a, app_b) = connected_apps(|config| {
+            config.clipboard.lazy.enabled = false;
+            config.clipboard.transforms.steps = vec![
+                config::TransformStep {
+                    transform: config::BuiltinTransform::StripTrackingParams,
+                    direction: config::SyncDirection::SendOnly,
+                },
+                config::TransformStep {
+                    transform: config::BuiltinTransform::TrimTrailingWhitespace,
+                    direction: config::SyncDirection::ReceiveOnly,
+                },
+            ];
+        })
+        .await;
+
+        let copied = "https://example.com/post?id=3&utm_source=feed   \n";
+        let backend = app_a.clipboard_monitor.as_ref().unwrap().backend();
+        backend.set(clipboard::backend::MIME_TEXT, copied.as_bytes()).unwrap();
+
+        wait_for("transformed item applied", || {
+            clipboard_text(&app_b).as_deref() == Some("https://example.com/post?id=3")
+        })
+        .await;
+
+        // The transformed item is not picked up as a new copy and sent back
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        assert_eq!(clipboard_text(&app_a).as_deref(), Some(copied));
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_sync_latency_is_traced_across_devices() {
+        use events::handlers::{BYTES_SYNCED_METRIC, PEERS_CONNECTED_METRIC, SYNC_LATENCY_METRIC};
+        use utils::metrics::MetricKey;
+
+        let (app_a, app_b) = connected_apps(|config| config.clipboard.lazy.enabled = false).await;
+        let peer_a = app_a.network_manager.as_ref().unwrap().local_peer_id().to_string();
+        let from_a = |name: &str| MetricKey::new(name).label("peer", peer_a.as_str());
+
+        let backend = app_a.clipboard_monitor.as_ref().unwrap().backend();
+        backend.set(clipboard::backend::MIME_TEXT, b"traced item").unwrap();
+        wait_for("traced item applied", || clipboard_text(&app_b).as_deref() == Some("traced item")).await;
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while app_b.metrics().get_average_duration(from_a(SYNC_LATENCY_METRIC)).await.is_none() {
+            assert!(std::time::Instant::now() < deadline, "sync latency was not recorded");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        // Every hop from the copy on one device to the clipboard of the other is timed
+        let summary = app_b.metrics_snapshot().await;
+        for hop in ["copied_to_sent", "sent_to_received", "received_to_applied"] {
+            let key = from_a(&format!("{}_{}", SYNC_LATENCY_METRIC, hop));
+            assert_eq!(summary.duration_stats.get(&key).map(|stats| stats.count), Some(1), "{}", key);
+        }
+        assert!(app_a.metrics_snapshot().await.duration_stats.is_empty());
+        assert_eq!(summary.counters.get(&from_a(BYTES_SYNCED_METRIC)), Some(&11));
+        assert_eq!(summary.gauges.get(&MetricKey::new(PEERS_CONNECTED_METRIC)), Some(&1.0));
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_copies_converge() {
+        let (app_a, app_b) = connected_apps(|config| config.clipboard.lazy.enabled = false).await;
+
+        // Both devices copy at the same time; their monitors pick the copies up
+        for (app, text) in [(&app_a, "copied on a"), (&app_b, "copied on b")] {
+            let backend = app.clipboard_monitor.as_ref().unwrap().backend();
+            backend.set(clipboard::backend::MIME_TEXT, text.as_bytes()).unwrap();
+        }
+
+        // Exactly one of the copies wins on both devices instead of the clipboards swapping
+        wait_for("clipboards converged", || clipboard_text(&app_a) == clipboard_text(&app_b)).await;
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        assert_eq!(clipboard_text(&app_a), clipboard_text(&app_b));
+
+        let current_a = app_a.ordering.lock().await.current().map(|(stamp, _)| stamp.clone());
+        let current_b = app_b.ordering.lock().await.current().map(|(stamp, _)| stamp.clone());
+        assert!(current_a.is_some());
+        assert_eq!(current_a, current_b);
+
+        // Copies are stamped with the copying device's peer ID
+        let peers = [&app_a, &app_b].map(|app| app.network_manager.as_ref().unwrap().local_peer_id().to_string());
+        assert!(peers.contains(&current_a.unwrap().device_id));
+
+        *app_a.running.write().await = false;
+        *app_b.running.write().await = false;
+    }
 }
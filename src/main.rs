@@ -1,5 +1,5 @@
 use crosscopy::{
-    config::{AppConfig, ConfigManager},
+    config::ConfigManager,
     utils::logger,
     CrossCopyApp, Result,
 };
@@ -10,18 +10,27 @@ use std::env;
 async fn main() -> Result<()> {
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
-    let config_path = args.get(1).map(|s| s.as_str());
+    let (command, config_path) = match args.get(1).map(|s| s.as_str()) {
+        Some("wipe-history") => (Some("wipe-history"), args.get(2).map(|s| s.as_str())),
+        config_path => (None, config_path),
+    };
 
     // Load configuration
     let config_manager = ConfigManager::new(config_path)?;
     let config = config_manager.load_config().await?;
 
     // Initialize logger
-    logger::init_logger(&config.logging.level)?;
+    logger::init_from_config(&config.logging)?;
     info!("CrossCopy v{} starting...", env!("CARGO_PKG_VERSION"));
 
     // Create and start the application
     let mut app = CrossCopyApp::new(config).await?;
+
+    if command == Some("wipe-history") {
+        app.wipe_history().await?;
+        info!("Clipboard history and keys deleted");
+        return Ok(());
+    }
     
     // Handle graceful shutdown
     let shutdown_signal = tokio::signal::ctrl_c();
@@ -1,11 +1,15 @@
 //! libp2p network behaviour implementation for CrossCopy
 
+use crate::network::codec::{MessageCodec, SYNC_PROTOCOL};
+use crate::network::Message;
 use libp2p::{
     mdns,
-    swarm::NetworkBehaviour,
+    request_response::{self, OutboundRequestId, ProtocolSupport, ResponseChannel},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
     PeerId, Multiaddr,
 };
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Simple clipboard content message
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,25 +30,98 @@ pub enum CrossCopyEvent {
     PeerExpired {
         peer_id: PeerId,
     },
+    /// A peer sent a request that must be answered through `channel`
+    MessageReceived {
+        peer_id: PeerId,
+        message: Message,
+        channel: ResponseChannel<Message>,
+    },
+    /// A peer answered one of our requests
+    ResponseReceived {
+        peer_id: PeerId,
+        request_id: OutboundRequestId,
+        message: Message,
+    },
+    /// One of our requests failed
+    RequestFailed {
+        peer_id: PeerId,
+        request_id: OutboundRequestId,
+        error: String,
+    },
+    /// A request from a peer could not be answered
+    InboundFailed {
+        peer_id: PeerId,
+        error: String,
+    },
+    /// A response has been flushed to a peer
+    ResponseSent {
+        peer_id: PeerId,
+    },
 }
 
-/// Main network behaviour for CrossCopy (simplified with mDNS only)
+/// Main network behaviour for CrossCopy
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "CrossCopyEvent")]
 pub struct CrossCopyBehaviour {
-    pub mdns: mdns::tokio::Behaviour,
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
+    pub request_response: request_response::Behaviour<MessageCodec>,
 }
 
 impl CrossCopyBehaviour {
     /// Create a new CrossCopy behaviour
-    pub fn new(local_peer_id: PeerId) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(
+        local_peer_id: PeerId,
+        enable_mdns: bool,
+        request_timeout: Duration,
+    ) -> std::result::Result<Self, Box<dyn std::error::Error>> {
         // Create mDNS behaviour
-        let mdns = mdns::tokio::Behaviour::new(
-            mdns::Config::default(),
-            local_peer_id,
-        )?;
+        let mdns = if enable_mdns {
+            Some(mdns::tokio::Behaviour::new(
+                mdns::Config::default(),
+                local_peer_id,
+            )?)
+        } else {
+            None
+        };
+
+        let request_response = request_response::Behaviour::new(
+            [(SYNC_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default().with_request_timeout(request_timeout),
+        );
+
+        Ok(Self {
+            mdns: mdns.into(),
+            request_response,
+        })
+    }
+}
 
-        Ok(Self { mdns })
+impl From<request_response::Event<Message, Message>> for CrossCopyEvent {
+    fn from(event: request_response::Event<Message, Message>) -> Self {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request { request, channel, .. } => CrossCopyEvent::MessageReceived {
+                    peer_id: peer,
+                    message: request,
+                    channel,
+                },
+                request_response::Message::Response { request_id, response } => CrossCopyEvent::ResponseReceived {
+                    peer_id: peer,
+                    request_id,
+                    message: response,
+                },
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error } => CrossCopyEvent::RequestFailed {
+                peer_id: peer,
+                request_id,
+                error: error.to_string(),
+            },
+            request_response::Event::InboundFailure { peer, error, .. } => CrossCopyEvent::InboundFailed {
+                peer_id: peer,
+                error: error.to_string(),
+            },
+            request_response::Event::ResponseSent { peer, .. } => CrossCopyEvent::ResponseSent { peer_id: peer },
+        }
     }
 }
 
@@ -0,0 +1,124 @@
+//! Wire codec for CrossCopy request-response messages
+
+use crate::network::Message;
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::request_response;
+use libp2p::StreamProtocol;
+use std::io;
+
+/// Protocol name negotiated for CrossCopy messages
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/crosscopy/sync/1.0.0");
+
+/// Largest frame accepted from a peer
+pub const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+/// Length-prefixed JSON codec; both requests and responses are [`Message`]s
+#[derive(Debug, Clone, Default)]
+pub struct MessageCodec;
+
+impl MessageCodec {
+    async fn read_frame<T>(io: &mut T) -> io::Result<Message>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let mut length = [0u8; 4];
+        io.read_exact(&mut length).await?;
+
+        let length = u32::from_be_bytes(length) as usize;
+        if length > MAX_FRAME_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Frame of {} bytes exceeds limit of {} bytes", length, MAX_FRAME_SIZE),
+            ));
+        }
+
+        let mut buffer = vec![0u8; length];
+        io.read_exact(&mut buffer).await?;
+
+        serde_json::from_slice(&buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write_frame<T>(io: &mut T, message: &Message) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let data = serde_json::to_vec(message)?;
+        if data.len() > MAX_FRAME_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Frame of {} bytes exceeds limit of {} bytes", data.len(), MAX_FRAME_SIZE),
+            ));
+        }
+
+        io.write_all(&(data.len() as u32).to_be_bytes()).await?;
+        io.write_all(&data).await?;
+        io.flush().await
+    }
+}
+
+#[async_trait]
+impl request_response::Codec for MessageCodec {
+    type Protocol = StreamProtocol;
+    type Request = Message;
+    type Response = Message;
+
+    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Message>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        Self::read_frame(io).await
+    }
+
+    async fn read_response<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Message>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        Self::read_frame(io).await
+    }
+
+    async fn write_request<T>(&mut self, _: &Self::Protocol, io: &mut T, request: Message) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        Self::write_frame(io, &request).await
+    }
+
+    async fn write_response<T>(&mut self, _: &Self::Protocol, io: &mut T, response: Message) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        Self::write_frame(io, &response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::MessageType;
+    use futures::io::Cursor;
+    use request_response::Codec;
+
+    #[tokio::test]
+    async fn test_frame_roundtrip() {
+        let message = Message::new(MessageType::ClipboardSync, b"hello".to_vec(), "test-device".to_string());
+
+        let mut buffer = Cursor::new(Vec::new());
+        MessageCodec.write_request(&SYNC_PROTOCOL, &mut buffer, message.clone()).await.unwrap();
+
+        buffer.set_position(0);
+        let decoded = MessageCodec.read_request(&SYNC_PROTOCOL, &mut buffer).await.unwrap();
+        assert_eq!(decoded.payload, message.payload);
+        assert_eq!(decoded.header.message_id, message.header.message_id);
+        assert!(decoded.verify());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_rejected() {
+        let mut frame = ((MAX_FRAME_SIZE + 1) as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(b"{}");
+
+        let mut buffer = Cursor::new(frame);
+        assert!(MessageCodec.read_response(&SYNC_PROTOCOL, &mut buffer).await.is_err());
+    }
+}
@@ -1,8 +1,9 @@
 //! Network connection management
 
+use crate::clipboard::CompressionCodec;
 use crate::network::{Message, Result};
 use libp2p::{PeerId, Multiaddr};
-use log::{debug, error, info};
+use log::debug;
 use std::fmt;
 use tokio::sync::mpsc;
 
@@ -37,6 +38,8 @@ pub struct Connection {
     pub address: Option<Multiaddr>,
     pub message_sender: Option<mpsc::UnboundedSender<Message>>,
     pub last_heartbeat: Option<std::time::Instant>,
+    /// Codecs the peer can decompress, once its handshake has arrived
+    pub compression_codecs: Option<Vec<CompressionCodec>>,
 }
 
 impl Clone for Connection {
@@ -49,6 +52,7 @@ impl Clone for Connection {
             address: self.address.clone(),
             message_sender: self.message_sender.clone(), // UnboundedSender does implement Clone
             last_heartbeat: self.last_heartbeat,
+            compression_codecs: self.compression_codecs.clone(),
         }
     }
 }
@@ -64,6 +68,7 @@ impl Connection {
             address: None,
             message_sender: None,
             last_heartbeat: None,
+            compression_codecs: None,
         }
     }
 
@@ -77,6 +82,7 @@ impl Connection {
             address: Some(address),
             message_sender: None,
             last_heartbeat: None,
+            compression_codecs: None,
         }
     }
 
@@ -1,21 +1,23 @@
 //! Network manager implementation using libp2p
 
+use crate::clipboard::CompressionCodec;
 use crate::config::NetworkConfig;
-use crate::events::{Event, EventBus};
-use crate::network::{Connection, ConnectionState, Message, MessageType, Result, NetworkError};
-use crate::network::behaviour::{CrossCopyBehaviour, CrossCopyEvent, ClipboardMessage};
+use crate::events::{Event, EventBus, EventWithMetadata, HopStage};
+use crate::network::{Connection, ConnectionState, HandshakeInfo, Message, MessageType, Result, NetworkError};
+use crate::network::behaviour::{CrossCopyBehaviour, CrossCopyEvent};
 use libp2p::{
     identity, noise, yamux, tcp,
+    request_response::{OutboundRequestId, ResponseChannel},
     swarm::{Swarm, SwarmEvent},
     SwarmBuilder,
-    PeerId, Multiaddr, Transport,
-    request_response::ResponseChannel,
+    PeerId, Multiaddr,
 };
-use log::{debug, info, warn, error};
+use log::{debug, info, error, warn};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{RwLock, mpsc, oneshot};
 use futures::StreamExt;
 
 /// Network statistics for monitoring
@@ -35,42 +37,95 @@ pub struct NetworkStats {
 pub struct NetworkManager {
     config: NetworkConfig,
     event_bus: Arc<EventBus>,
-    swarm: Option<Swarm<CrossCopyBehaviour>>,
+    local_key: identity::Keypair,
     local_peer_id: PeerId,
+    device_system: String,
+    device_name: String,
     connections: Arc<RwLock<HashMap<PeerId, Connection>>>,
     stats: Arc<RwLock<NetworkStats>>,
+    listen_addresses: Arc<RwLock<Vec<Multiaddr>>>,
     command_sender: Option<mpsc::UnboundedSender<NetworkCommand>>,
     running: Arc<RwLock<bool>>,
 }
 
+/// Cloneable handle for sending requests to peers from background tasks
+#[derive(Clone)]
+pub struct PeerRequester {
+    device_system: String,
+    connections: Arc<RwLock<HashMap<PeerId, Connection>>>,
+    stats: Arc<RwLock<NetworkStats>>,
+    command_sender: mpsc::UnboundedSender<NetworkCommand>,
+}
+
 /// Commands that can be sent to the network manager
 #[derive(Debug)]
 pub enum NetworkCommand {
-    BroadcastClipboard {
-        content: Vec<u8>,
-        content_type: String,
+    /// Send a message to every connected peer
+    Broadcast {
+        message: Message,
+    },
+    /// Send a request to one peer, optionally waiting for its response
+    SendRequest {
+        peer_id: PeerId,
+        message: Message,
+        reply: Option<oneshot::Sender<Result<Message>>>,
+    },
+    /// Answer an inbound request, identified by its message ID
+    Respond {
+        request_message_id: String,
+        message: Message,
+    },
+    /// Connect to a peer at a known address
+    Dial {
+        address: Multiaddr,
+    },
+    /// Close all connections to a peer
+    Disconnect {
+        peer_id: PeerId,
     },
     Shutdown,
 }
 
+/// State owned by the swarm event loop
+struct SwarmTask {
+    swarm: Swarm<CrossCopyBehaviour>,
+    event_bus: Arc<EventBus>,
+    connections: Arc<RwLock<HashMap<PeerId, Connection>>>,
+    stats: Arc<RwLock<NetworkStats>>,
+    listen_addresses: Arc<RwLock<Vec<Multiaddr>>>,
+    device_system: String,
+    device_name: String,
+    /// Outbound requests awaiting a response
+    pending_requests: HashMap<OutboundRequestId, Option<oneshot::Sender<Result<Message>>>>,
+    /// Inbound requests the application still has to answer, by message ID
+    pending_responses: HashMap<String, ResponseChannel<Message>>,
+}
+
 impl NetworkManager {
     /// Create a new network manager with real libp2p implementation
     pub async fn new(config: NetworkConfig, event_bus: Arc<EventBus>) -> Result<Self> {
         info!("Creating libp2p network manager");
 
-        // Generate a random key pair for this peer
-        let local_key = identity::Keypair::generate_ed25519();
+        // A configured key file keeps the peer ID stable across restarts
+        let local_key = match &config.identity_file {
+            Some(path) => load_or_create_identity(Path::new(path))?,
+            None => identity::Keypair::generate_ed25519(),
+        };
         let local_peer_id = PeerId::from(local_key.public());
 
         info!("Local peer ID: {}", local_peer_id);
+        let system_info = crate::utils::platform::get_detailed_system_info();
 
         Ok(Self {
             config,
             event_bus,
-            swarm: None,
+            local_key,
             local_peer_id,
+            device_system: system_info.device_system,
+            device_name: system_info.device_name,
             connections: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(NetworkStats::default())),
+            listen_addresses: Arc::new(RwLock::new(Vec::new())),
             command_sender: None,
             running: Arc::new(RwLock::new(false)),
         })
@@ -81,14 +136,16 @@ impl NetworkManager {
         info!("Starting libp2p network manager on port {}", self.config.listen_port);
         *self.running.write().await = true;
 
-        // Generate keypair
-        let local_key = identity::Keypair::generate_ed25519();
-        let local_peer_id = PeerId::from(local_key.public());
-        self.local_peer_id = local_peer_id;
+        let local_key = self.local_key.clone();
+        let local_peer_id = self.local_peer_id;
 
         // Create behaviour
-        let behaviour = CrossCopyBehaviour::new(local_peer_id)
-            .map_err(|e| NetworkError::Libp2p(format!("Failed to create behaviour: {}", e)))?;
+        let behaviour = CrossCopyBehaviour::new(
+            local_peer_id,
+            self.config.enable_mdns,
+            Duration::from_millis(self.config.connection_timeout),
+        )
+        .map_err(|e| NetworkError::Libp2p(format!("Failed to create behaviour: {}", e)))?;
 
         // Create swarm using the new builder API
         let mut swarm = SwarmBuilder::with_existing_identity(local_key)
@@ -101,7 +158,7 @@ impl NetworkManager {
             .map_err(|e| NetworkError::Transport(format!("Failed to create transport: {}", e)))?
             .with_behaviour(|_| behaviour)
             .map_err(|e| NetworkError::Libp2p(format!("Failed to create behaviour: {}", e)))?
-            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(self.config.idle_connection_timeout)))
             .build();
 
         // Listen on the configured port
@@ -115,145 +172,27 @@ impl NetworkManager {
         info!("Listening on: {}", listen_addr);
 
         // Create command channel
-        let (command_sender, mut command_receiver) = mpsc::unbounded_channel();
+        let (command_sender, command_receiver) = mpsc::unbounded_channel();
         self.command_sender = Some(command_sender);
 
-        // Store swarm
-        self.swarm = Some(swarm);
-
         // Start the swarm event loop
-        let event_bus = self.event_bus.clone();
-        let connections = self.connections.clone();
-        let stats = self.stats.clone();
-        let running = self.running.clone();
-
-        if let Some(mut swarm) = self.swarm.take() {
-            tokio::spawn(async move {
-                info!("Starting libp2p swarm event loop");
-
-                loop {
-                    if !*running.read().await {
-                        break;
-                    }
-
-                    tokio::select! {
-                        event = swarm.select_next_some() => {
-                            Self::handle_swarm_event(event, &event_bus, &connections, &stats).await;
-                        }
-                        command = command_receiver.recv() => {
-                            if let Some(cmd) = command {
-                                if let Err(e) = Self::handle_command(cmd, &mut swarm).await {
-                                    error!("Failed to handle command: {}", e);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                info!("Swarm event loop stopped");
-            });
-        }
+        let task = SwarmTask {
+            swarm,
+            event_bus: self.event_bus.clone(),
+            connections: self.connections.clone(),
+            stats: self.stats.clone(),
+            listen_addresses: self.listen_addresses.clone(),
+            device_system: self.device_system.clone(),
+            device_name: self.device_name.clone(),
+            pending_requests: HashMap::new(),
+            pending_responses: HashMap::new(),
+        };
+        tokio::spawn(task.run(command_receiver, self.running.clone()));
 
         info!("libp2p network manager started successfully");
         Ok(())
     }
 
-    /// Handle swarm events
-    async fn handle_swarm_event(
-        event: SwarmEvent<CrossCopyEvent>,
-        event_bus: &Arc<EventBus>,
-        connections: &Arc<RwLock<HashMap<PeerId, Connection>>>,
-        stats: &Arc<RwLock<NetworkStats>>,
-    ) {
-        match event {
-            SwarmEvent::Behaviour(CrossCopyEvent::PeerDiscovered { peer_id, addresses }) => {
-                info!("Discovered peer: {} at {:?}", peer_id, addresses);
-
-                // Create connection entry
-                let mut connection = Connection::new(peer_id.to_string());
-                if let Some(addr) = addresses.first() {
-                    connection.address = Some(addr.clone());
-                }
-                connection.peer_id = Some(peer_id);
-                connection.set_state(ConnectionState::Connected);
-
-                connections.write().await.insert(peer_id, connection);
-
-                // Update stats
-                {
-                    let mut stats_guard = stats.write().await;
-                    stats_guard.peers_discovered += 1;
-                    stats_guard.peers_connected += 1;
-                }
-
-                // Emit event
-                let event = Event::PeerDiscovered {
-                    peer_id: peer_id.to_string(),
-                    address: addresses.first().map(|a| a.to_string()).unwrap_or_default(),
-                };
-                let _ = event_bus.emit(event).await;
-            }
-            SwarmEvent::Behaviour(CrossCopyEvent::PeerExpired { peer_id }) => {
-                info!("Peer expired: {}", peer_id);
-
-                connections.write().await.remove(&peer_id);
-
-                // Update stats
-                {
-                    let mut stats_guard = stats.write().await;
-                    stats_guard.peers_disconnected += 1;
-                    if stats_guard.peers_connected > 0 {
-                        stats_guard.peers_connected -= 1;
-                    }
-                }
-
-                // Emit event
-                let event = Event::PeerDisconnected {
-                    peer_id: peer_id.to_string(),
-                };
-                let _ = event_bus.emit(event).await;
-            }
-            // For now, we only handle mDNS events
-            // Future: Add request-response handling here
-            SwarmEvent::NewListenAddr { address, .. } => {
-                info!("Listening on: {}", address);
-            }
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                info!("Connection established with: {}", peer_id);
-            }
-            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
-                info!("Connection closed with {}: {:?}", peer_id, cause);
-                connections.write().await.remove(&peer_id);
-            }
-            _ => {
-                debug!("Unhandled swarm event: {:?}", event);
-            }
-        }
-    }
-
-    /// Handle network commands
-    async fn handle_command(
-        command: NetworkCommand,
-        _swarm: &mut Swarm<CrossCopyBehaviour>,
-    ) -> Result<()> {
-        match command {
-            NetworkCommand::BroadcastClipboard { content, content_type } => {
-                info!("Broadcasting clipboard content ({} bytes) of type {}", content.len(), content_type);
-                // TODO: Implement actual broadcasting when we add request-response
-                // For now, just log the broadcast attempt
-            }
-            NetworkCommand::Shutdown => {
-                info!("Shutting down network manager");
-                return Err(NetworkError::ConnectionFailed("Shutdown requested".to_string()));
-            }
-        }
-        Ok(())
-    }
-
-
-
-
-
     /// Stop the network manager
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping libp2p network manager");
@@ -271,8 +210,7 @@ impl NetworkManager {
             info!("Closed connection to peer: {}", peer_id);
         }
 
-        // Drop swarm and command sender
-        self.swarm = None;
+        // Drop command sender
         self.command_sender = None;
 
         info!("libp2p network manager stopped");
@@ -281,78 +219,147 @@ impl NetworkManager {
 
     /// Broadcast clipboard content to all connected devices
     pub async fn broadcast_clipboard_content(&self, content: Vec<u8>) -> Result<()> {
+        let message = Message::new(MessageType::ClipboardSync, content, self.device_system.clone());
+        self.broadcast_message(message).await
+    }
+
+    /// Broadcast a message to all connected devices
+    pub async fn broadcast_message(&self, message: Message) -> Result<()> {
         let connection_count = self.get_connection_count().await;
-        debug!("Broadcasting clipboard content to {} devices", connection_count);
+        debug!("Broadcasting {} message to {} devices", message.header.message_type, connection_count);
 
         if connection_count == 0 {
-            info!("No active peers to broadcast clipboard content to");
+            info!("No active peers to broadcast {} message to", message.header.message_type);
             return Ok(());
         }
 
-        // Send broadcast command through channel
-        if let Some(sender) = &self.command_sender {
-            let command = NetworkCommand::BroadcastClipboard {
-                content: content.clone(),
-                content_type: "text/plain".to_string(), // Default content type
-            };
-
-            sender.send(command)
-                .map_err(|_| NetworkError::ConnectionFailed("Failed to send broadcast command".to_string()))?;
-
-            // Update statistics
-            {
-                let mut stats = self.stats.write().await;
-                stats.messages_sent += connection_count as u64;
-                stats.bytes_sent += (content.len() * connection_count) as u64;
-            }
+        let payload_len = message.payload.len();
+        self.send_command(NetworkCommand::Broadcast { message })?;
 
-            info!("Clipboard content broadcast initiated for {} peers", connection_count);
-        } else {
-            return Err(NetworkError::ConnectionFailed("Network manager not started".to_string()));
+        // Update statistics
+        {
+            let mut stats = self.stats.write().await;
+            stats.messages_sent += connection_count as u64;
+            stats.bytes_sent += (payload_len * connection_count) as u64;
         }
 
+        info!("Broadcast initiated for {} peers", connection_count);
         Ok(())
     }
 
-    /// Get the number of active connections
-    pub async fn get_connection_count(&self) -> usize {
-        let connections = self.connections.read().await;
-        connections.values().filter(|c| c.is_active()).count()
+    /// Send a request to a peer and wait for its response
+    pub async fn request(&self, peer_id: &str, message: Message) -> Result<Message> {
+        self.requester()?.request(peer_id, message).await
     }
 
+    /// Handle for sending requests that outlives borrows of the manager
+    pub fn requester(&self) -> Result<PeerRequester> {
+        let command_sender = self
+            .command_sender
+            .clone()
+            .ok_or_else(|| NetworkError::ConnectionFailed("Network manager not started".to_string()))?;
+
+        Ok(PeerRequester {
+            device_system: self.device_system.clone(),
+            connections: self.connections.clone(),
+            stats: self.stats.clone(),
+            command_sender,
+        })
+    }
 
+    /// Answer an inbound request received as an [`Event::NetworkMessage`]
+    pub async fn respond(&self, request_message_id: &str, message: Message) -> Result<()> {
+        let payload_len = message.payload.len();
+        self.send_command(NetworkCommand::Respond {
+            request_message_id: request_message_id.to_string(),
+            message,
+        })?;
 
+        let mut stats = self.stats.write().await;
+        stats.messages_sent += 1;
+        stats.bytes_sent += payload_len as u64;
+        Ok(())
+    }
 
+    /// Connect to a peer at a known address
+    pub async fn dial(&self, address: Multiaddr) -> Result<()> {
+        self.send_command(NetworkCommand::Dial { address })
+    }
 
-    /// Send a message to a specific peer
-    pub async fn send_message_to_peer(&self, peer_id: &str, message: Message) -> Result<()> {
-        // Parse peer ID
-        let _peer_id = peer_id.parse::<PeerId>()
+    /// Close all connections to a peer
+    pub async fn disconnect(&self, peer_id: &str) -> Result<()> {
+        let peer_id = peer_id.parse::<PeerId>()
             .map_err(|_| NetworkError::PeerNotFound(format!("Invalid peer ID: {}", peer_id)))?;
+        self.send_command(NetworkCommand::Disconnect { peer_id })
+    }
+
+    /// Addresses the swarm is listening on, once the listeners are up
+    ///
+    /// With `listen_port` 0 this reports the port the system picked.
+    pub async fn listen_addresses(&self) -> Vec<Multiaddr> {
+        self.listen_addresses.read().await.clone()
+    }
+
+    /// Get the local peer ID
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local_peer_id
+    }
+
+    /// Get the device system name sent with outgoing messages
+    pub fn device_system(&self) -> &str {
+        &self.device_system
+    }
+
+    /// Set the device name sent to peers in the handshake
+    ///
+    /// Takes effect for connections made after the manager is started.
+    pub fn set_device_name(&mut self, device_name: impl Into<String>) {
+        self.device_name = device_name.into();
+    }
 
+    fn send_command(&self, command: NetworkCommand) -> Result<()> {
+        match &self.command_sender {
+            Some(sender) => sender
+                .send(command)
+                .map_err(|_| NetworkError::ConnectionFailed("Network manager stopped".to_string())),
+            None => Err(NetworkError::ConnectionFailed("Network manager not started".to_string())),
+        }
+    }
+
+    async fn active_peer(&self, peer_id: &str) -> Result<PeerId> {
+        active_peer(&self.connections, peer_id).await
+    }
+
+    /// Get the number of active connections
+    pub async fn get_connection_count(&self) -> usize {
         let connections = self.connections.read().await;
-        if let Some(connection) = connections.get(&_peer_id) {
-            if connection.is_active() {
-                let payload_len = message.payload.len();
+        connections.values().filter(|c| c.is_active()).count()
+    }
 
-                // For now, just log the message sending
-                // TODO: Implement actual message sending when we add request-response
-                info!("Would send message to peer {}: {:?} ({} bytes)", peer_id, message.header.message_type, payload_len);
 
-                // Update statistics
-                {
-                    let mut stats = self.stats.write().await;
-                    stats.messages_sent += 1;
-                    stats.bytes_sent += payload_len as u64;
-                }
 
-                Ok(())
-            } else {
-                Err(NetworkError::ConnectionFailed(format!("Peer {} not connected", peer_id)))
-            }
-        } else {
-            Err(NetworkError::PeerNotFound(peer_id.to_string()))
+
+
+    /// Send a message to a specific peer without waiting for its response
+    pub async fn send_message_to_peer(&self, peer_id: &str, message: Message) -> Result<()> {
+        let target = self.active_peer(peer_id).await?;
+        let payload_len = message.payload.len();
+
+        debug!("Sending {} message to peer {} ({} bytes)", message.header.message_type, peer_id, payload_len);
+        self.send_command(NetworkCommand::SendRequest {
+            peer_id: target,
+            message,
+            reply: None,
+        })?;
+
+        // Update statistics
+        {
+            let mut stats = self.stats.write().await;
+            stats.messages_sent += 1;
+            stats.bytes_sent += payload_len as u64;
         }
+
+        Ok(())
     }
 
     /// Check if mDNS discovery is enabled
@@ -365,6 +372,21 @@ impl NetworkManager {
         &self.config
     }
 
+    /// Pick the first preferred codec that every connected peer can decompress
+    ///
+    /// Peers that have not sent a handshake are assumed to read only gzip.
+    pub async fn negotiate_codec(&self, preferred: &[CompressionCodec]) -> CompressionCodec {
+        const LEGACY_CODECS: &[CompressionCodec] = &[CompressionCodec::Gzip, CompressionCodec::None];
+
+        let connections = self.connections.read().await;
+        let peers = connections
+            .values()
+            .filter(|connection| connection.is_active())
+            .map(|connection| connection.compression_codecs.as_deref().unwrap_or(LEGACY_CODECS));
+
+        CompressionCodec::negotiate(preferred, peers)
+    }
+
     /// Get list of connected peer IDs
     pub async fn get_connected_peers(&self) -> Vec<String> {
         let connections = self.connections.read().await;
@@ -375,11 +397,26 @@ impl NetworkManager {
             .collect()
     }
 
+    /// Get connected peer IDs with the device names from their handshakes
+    pub async fn get_connected_devices(&self) -> Vec<(String, Option<String>)> {
+        let connections = self.connections.read().await;
+        connections
+            .iter()
+            .filter(|(_, conn)| conn.is_active())
+            .map(|(peer_id, conn)| (peer_id.to_string(), conn.device_id.clone()))
+            .collect()
+    }
+
     /// Get network statistics
     pub async fn get_network_stats(&self) -> NetworkStats {
         self.stats.read().await.clone()
     }
 
+    /// Network statistics shared with the manager, for exporting them
+    pub fn shared_stats(&self) -> Arc<RwLock<NetworkStats>> {
+        self.stats.clone()
+    }
+
     /// Reset network statistics
     pub async fn reset_network_stats(&self) {
         let mut stats = self.stats.write().await;
@@ -397,6 +434,413 @@ impl NetworkManager {
     }
 }
 
+impl PeerRequester {
+    /// Send a request to a connected peer and wait for its response
+    pub async fn request(&self, peer_id: &str, message: Message) -> Result<Message> {
+        let peer_id = active_peer(&self.connections, peer_id).await?;
+        let payload_len = message.payload.len();
+
+        let (reply, response) = oneshot::channel();
+        self.command_sender
+            .send(NetworkCommand::SendRequest {
+                peer_id,
+                message,
+                reply: Some(reply),
+            })
+            .map_err(|_| NetworkError::ConnectionFailed("Network manager stopped".to_string()))?;
+
+        {
+            let mut stats = self.stats.write().await;
+            stats.messages_sent += 1;
+            stats.bytes_sent += payload_len as u64;
+        }
+
+        response
+            .await
+            .map_err(|_| NetworkError::ConnectionFailed("Network manager stopped".to_string()))?
+    }
+
+    /// Get the device system name sent with outgoing messages
+    pub fn device_system(&self) -> &str {
+        &self.device_system
+    }
+}
+
+/// Parse a peer ID and check that the peer is connected
+async fn active_peer(connections: &RwLock<HashMap<PeerId, Connection>>, peer_id: &str) -> Result<PeerId> {
+    let parsed = peer_id.parse::<PeerId>()
+        .map_err(|_| NetworkError::PeerNotFound(format!("Invalid peer ID: {}", peer_id)))?;
+
+    let connections = connections.read().await;
+    match connections.get(&parsed) {
+        Some(connection) if connection.is_active() => Ok(parsed),
+        Some(_) => Err(NetworkError::ConnectionFailed(format!("Peer {} not connected", peer_id))),
+        None => Err(NetworkError::PeerNotFound(peer_id.to_string())),
+    }
+}
+
+impl SwarmTask {
+    /// Run the swarm event loop until stopped
+    async fn run(
+        mut self,
+        mut command_receiver: mpsc::UnboundedReceiver<NetworkCommand>,
+        running: Arc<RwLock<bool>>,
+    ) {
+        info!("Starting libp2p swarm event loop");
+
+        loop {
+            if !*running.read().await {
+                break;
+            }
+
+            tokio::select! {
+                event = self.swarm.select_next_some() => {
+                    self.handle_swarm_event(event).await;
+                }
+                command = command_receiver.recv() => {
+                    match command {
+                        Some(NetworkCommand::Shutdown) | None => {
+                            info!("Shutting down network manager");
+                            break;
+                        }
+                        Some(command) => {
+                            if let Err(e) = self.handle_command(command) {
+                                error!("Failed to handle command: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        info!("Swarm event loop stopped");
+    }
+
+    /// Handle swarm events
+    async fn handle_swarm_event(&mut self, event: SwarmEvent<CrossCopyEvent>) {
+        match event {
+            SwarmEvent::Behaviour(CrossCopyEvent::PeerDiscovered { peer_id, addresses }) => {
+                info!("Discovered peer: {} at {:?}", peer_id, addresses);
+
+                for address in &addresses {
+                    self.swarm.add_peer_address(peer_id, address.clone());
+                }
+                let dialing = !self.swarm.is_connected(&peer_id);
+                if dialing {
+                    if let Err(e) = self.swarm.dial(peer_id) {
+                        warn!("Failed to dial discovered peer {}: {}", peer_id, e);
+                    }
+                }
+
+                // The peer counts as connected once the connection is established,
+                // which also sends the handshake; a peer found again keeps its entry
+                {
+                    let mut connections = self.connections.write().await;
+                    let connection = connections.entry(peer_id).or_insert_with(|| {
+                        let mut connection = Connection::new(peer_id.to_string());
+                        connection.peer_id = Some(peer_id);
+                        connection
+                    });
+                    if connection.address.is_none() {
+                        connection.address = addresses.first().cloned();
+                    }
+                    if dialing && !connection.is_active() {
+                        connection.set_state(ConnectionState::Connecting);
+                    }
+                }
+
+                self.stats.write().await.peers_discovered += 1;
+
+                // Emit event
+                let event = Event::PeerDiscovered {
+                    peer_id: peer_id.to_string(),
+                    address: addresses.first().map(|a| a.to_string()).unwrap_or_default(),
+                };
+                self.emit(event);
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::PeerExpired { peer_id }) => {
+                info!("Peer expired: {}", peer_id);
+
+                // Only the discovery record expired; an open connection is
+                // handled when it closes
+                let mut connections = self.connections.write().await;
+                if connections.get(&peer_id).is_some_and(|connection| !connection.is_active()) {
+                    connections.remove(&peer_id);
+                }
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::MessageReceived { peer_id, message, channel }) => {
+                self.handle_inbound_message(peer_id, message, channel).await;
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::ResponseReceived { peer_id, request_id, message }) => {
+                debug!("Received {} response from {}", message.header.message_type, peer_id);
+                Self::record_received(&self.stats, &message).await;
+
+                if let Some(Some(reply)) = self.pending_requests.remove(&request_id) {
+                    let _ = reply.send(Ok(message));
+                }
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::RequestFailed { peer_id, request_id, error }) => {
+                warn!("Request to {} failed: {}", peer_id, error);
+
+                if let Some(Some(reply)) = self.pending_requests.remove(&request_id) {
+                    let _ = reply.send(Err(NetworkError::ConnectionFailed(error)));
+                }
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::InboundFailed { peer_id, error }) => {
+                warn!("Failed to answer request from {}: {}", peer_id, error);
+            }
+            SwarmEvent::Behaviour(CrossCopyEvent::ResponseSent { peer_id }) => {
+                debug!("Response sent to {}", peer_id);
+            }
+            SwarmEvent::NewListenAddr { address, .. } => {
+                info!("Listening on: {}", address);
+                self.listen_addresses.write().await.push(address);
+            }
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                self.listen_addresses.write().await.retain(|listening| *listening != address);
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established, .. } => {
+                info!("Connection established with: {}", peer_id);
+
+                {
+                    let mut connections = self.connections.write().await;
+                    let connection = connections.entry(peer_id).or_insert_with(|| {
+                        let mut connection = Connection::new(peer_id.to_string());
+                        connection.peer_id = Some(peer_id);
+                        connection
+                    });
+                    if connection.address.is_none() {
+                        connection.address = Some(endpoint.get_remote_address().clone());
+                    }
+                    if !connection.is_active() {
+                        connection.set_state(ConnectionState::Connected);
+                    }
+                }
+
+                // Further connections to the same peer add nothing new
+                if num_established.get() == 1 {
+                    self.stats.write().await.peers_connected += 1;
+                    self.send_handshake(peer_id);
+
+                    let event = Event::PeerConnected {
+                        peer_id: peer_id.to_string(),
+                    };
+                    self.emit(event);
+                }
+            }
+            SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
+                info!("Connection closed with {}: {:?}", peer_id, cause);
+
+                if num_established == 0 && self.connections.write().await.remove(&peer_id).is_some() {
+                    {
+                        let mut stats_guard = self.stats.write().await;
+                        stats_guard.peers_disconnected += 1;
+                        stats_guard.peers_connected = stats_guard.peers_connected.saturating_sub(1);
+                    }
+
+                    let event = Event::PeerDisconnected {
+                        peer_id: peer_id.to_string(),
+                    };
+                    self.emit(event);
+                }
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), error, .. } => {
+                warn!("Failed to connect to {}: {}", peer_id, error);
+
+                if let Some(connection) = self.connections.write().await.get_mut(&peer_id) {
+                    if connection.state == ConnectionState::Connecting {
+                        connection.set_state(ConnectionState::Error);
+                    }
+                }
+            }
+            _ => {
+                debug!("Unhandled swarm event: {:?}", event);
+            }
+        }
+    }
+
+    /// Handle a request from a peer
+    async fn handle_inbound_message(&mut self, peer_id: PeerId, mut message: Message, channel: ResponseChannel<Message>) {
+        debug!("Received {} message from {}", message.header.message_type, peer_id);
+        Self::record_received(&self.stats, &message).await;
+
+        if !message.verify() {
+            warn!("Dropping corrupted {} message from {}", message.header.message_type, peer_id);
+            let error = Message::new(MessageType::Error, b"checksum mismatch".to_vec(), self.device_system.clone());
+            let _ = self.swarm.behaviour_mut().request_response.send_response(channel, error);
+            return;
+        }
+
+        if message.header.message_type == MessageType::Handshake {
+            self.handle_handshake(peer_id, message, channel).await;
+            return;
+        }
+
+        if message.header.message_type.expects_response() {
+            // The application answers through NetworkManager::respond
+            self.pending_responses.insert(message.header.message_id.clone(), channel);
+        } else {
+            let ack = Message::new(
+                MessageType::Ack,
+                message.header.message_id.clone().into_bytes(),
+                self.device_system.clone(),
+            );
+            let _ = self.swarm.behaviour_mut().request_response.send_response(channel, ack);
+        }
+
+        // Traced items record when they arrived here
+        let trace = message
+            .header
+            .trace
+            .take()
+            .map(|trace| trace.with_hop(HopStage::Received, &self.device_name));
+        message.header.trace = trace.clone();
+
+        let event = Event::NetworkMessage {
+            message,
+            sender: peer_id.to_string(),
+        };
+        let event = EventWithMetadata::new(event, "network".to_string()).with_trace(trace);
+        if let Err(e) = self.event_bus.emit_without_waiting(event) {
+            error!("Failed to emit network message event: {}", e);
+        }
+    }
+
+    /// Tell a newly connected peer what this device supports
+    fn send_handshake(&mut self, peer_id: PeerId) {
+        let info = HandshakeInfo {
+            device_system: self.device_system.clone(),
+            device_name: Some(self.device_name.clone()),
+            compression_codecs: CompressionCodec::supported(),
+        };
+
+        match serde_json::to_vec(&info) {
+            Ok(payload) => {
+                let message = Message::new(MessageType::Handshake, payload, self.device_system.clone());
+                let request_id = self.swarm.behaviour_mut().request_response.send_request(&peer_id, message);
+                self.pending_requests.insert(request_id, None);
+            }
+            Err(e) => error!("Failed to encode handshake: {}", e),
+        }
+    }
+
+    /// Record the capabilities a peer announced
+    async fn handle_handshake(&mut self, peer_id: PeerId, message: Message, channel: ResponseChannel<Message>) {
+        let ack = Message::new(
+            MessageType::Ack,
+            message.header.message_id.clone().into_bytes(),
+            self.device_system.clone(),
+        );
+        let _ = self.swarm.behaviour_mut().request_response.send_response(channel, ack);
+
+        let info: HandshakeInfo = match serde_json::from_slice(&message.payload) {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Invalid handshake from {}: {}", peer_id, e);
+                return;
+            }
+        };
+
+        debug!("Peer {} ({}) supports codecs {:?}", peer_id, info.device_system, info.compression_codecs);
+
+        if let Some(connection) = self.connections.write().await.get_mut(&peer_id) {
+            connection.device_id = info.device_name;
+            connection.compression_codecs = Some(info.compression_codecs);
+        }
+
+        let event = Event::DeviceConnected {
+            device_system: info.device_system,
+        };
+        self.emit(event);
+    }
+
+    /// Emit a network event without waiting for room, as the application's
+    /// event loop may itself be waiting on this task
+    fn emit(&self, event: Event) {
+        let _ = self.event_bus.emit_without_waiting(EventWithMetadata::new(event, "system".to_string()));
+    }
+
+    /// Handle network commands
+    fn handle_command(&mut self, command: NetworkCommand) -> Result<()> {
+        match command {
+            NetworkCommand::Broadcast { message } => {
+                let peers: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+                info!("Broadcasting {} message ({} bytes) to {} peers", message.header.message_type, message.payload.len(), peers.len());
+
+                for peer_id in peers {
+                    let request_id = self.swarm.behaviour_mut().request_response.send_request(&peer_id, message.clone());
+                    self.pending_requests.insert(request_id, None);
+                }
+            }
+            NetworkCommand::SendRequest { peer_id, message, reply } => {
+                debug!("Sending {} request to {}", message.header.message_type, peer_id);
+                let request_id = self.swarm.behaviour_mut().request_response.send_request(&peer_id, message);
+                self.pending_requests.insert(request_id, reply);
+            }
+            NetworkCommand::Respond { request_message_id, message } => {
+                let channel = self.pending_responses.remove(&request_message_id).ok_or_else(|| {
+                    NetworkError::InvalidMessage(format!("No pending request {}", request_message_id))
+                })?;
+
+                self.swarm
+                    .behaviour_mut()
+                    .request_response
+                    .send_response(channel, message)
+                    .map_err(|_| NetworkError::ConnectionFailed("Requesting peer went away".to_string()))?;
+            }
+            NetworkCommand::Dial { address } => {
+                info!("Dialing {}", address);
+                self.swarm
+                    .dial(address)
+                    .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?;
+            }
+            NetworkCommand::Disconnect { peer_id } => {
+                info!("Disconnecting from {}", peer_id);
+                if self.swarm.disconnect_peer_id(peer_id).is_err() {
+                    debug!("{} was not connected", peer_id);
+                }
+            }
+            NetworkCommand::Shutdown => {}
+        }
+        Ok(())
+    }
+
+    async fn record_received(stats: &RwLock<NetworkStats>, message: &Message) {
+        let mut stats = stats.write().await;
+        stats.messages_received += 1;
+        stats.bytes_received += message.payload.len() as u64;
+    }
+}
+
+/// Load this device's key from `path`, creating the file on first start
+fn load_or_create_identity(path: &Path) -> Result<identity::Keypair> {
+    use std::io::Write;
+
+    match std::fs::read(path) {
+        Ok(encoded) => identity::Keypair::from_protobuf_encoding(&encoded)
+            .map_err(|e| NetworkError::Libp2p(format!("Invalid identity key {}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let local_key = identity::Keypair::generate_ed25519();
+            let encoded = local_key
+                .to_protobuf_encoding()
+                .map_err(|e| NetworkError::Libp2p(e.to_string()))?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            options.open(path)?.write_all(&encoded)?;
+
+            info!("Created identity key {}", path.display());
+            Ok(local_key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -411,4 +855,20 @@ mod tests {
         let manager = NetworkManager::new(config, event_bus).await;
         assert!(manager.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_identity_file_keeps_peer_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = NetworkConfig {
+            identity_file: Some(dir.path().join("keys/identity.key").to_string_lossy().into_owned()),
+            ..NetworkConfig::default()
+        };
+        let event_bus = Arc::new(EventBus::new());
+
+        let first = NetworkManager::new(config.clone(), event_bus.clone()).await.unwrap();
+        let second = NetworkManager::new(config, event_bus.clone()).await.unwrap();
+        let other = NetworkManager::new(NetworkConfig::default(), event_bus).await.unwrap();
+        assert_eq!(first.local_peer_id(), second.local_peer_id());
+        assert_ne!(first.local_peer_id(), other.local_peer_id());
+    }
 }
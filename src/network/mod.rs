@@ -4,13 +4,18 @@
 //! libp2p connections, mDNS discovery, message protocols, and connection management.
 
 pub mod behaviour;
+pub mod codec;
 pub mod connection;
 pub mod manager;
+pub mod ordering;
 pub mod protocol;
+pub mod transfer;
 
 pub use connection::{Connection, ConnectionState};
-pub use manager::NetworkManager;
-pub use protocol::{Message, MessageType, ProtocolVersion};
+pub use manager::{NetworkManager, PeerRequester};
+pub use ordering::{ClipboardOrdering, HlcTimestamp, HybridClock, LwwRegister, Resolution, SeenMessages};
+pub use protocol::{HandshakeInfo, Message, MessageType, ProtocolVersion};
+pub use transfer::{ChunkRequest, IncomingTransfer, TransferManifest};
 
 use thiserror::Error;
 
@@ -44,6 +49,9 @@ pub enum NetworkError {
     #[error("Transport error: {0}")]
     Transport(String),
 
+    #[error("Transfer failed: {0}")]
+    Transfer(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
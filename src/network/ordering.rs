@@ -0,0 +1,366 @@
+//! Causal ordering of clipboard updates
+//!
+//! Every clipboard message carries a [`HlcTimestamp`] from the sender's
+//! [`HybridClock`]. Timestamps are totally ordered, with the device ID breaking
+//! ties, so every device applies the same last-writer-wins decision in the
+//! [`LwwRegister`] no matter in which order concurrent updates arrive.
+//!
+//! The clipboard monitor and the app share a [`ClipboardOrdering`], so a local
+//! copy is stamped when it is picked up rather than when it is processed.
+
+use crate::network::{NetworkError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Remote timestamps further ahead of the local clock than this are rejected
+pub const MAX_CLOCK_DRIFT_MILLIS: u64 = 5 * 60 * 1000;
+
+/// Hybrid logical clock timestamp
+///
+/// Ordered by physical time, then logical counter, then device ID.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HlcTimestamp {
+    /// Wall clock time in milliseconds
+    pub physical: u64,
+    /// Counter ordering events within the same millisecond
+    pub logical: u32,
+    /// Device that produced the timestamp
+    pub device_id: String,
+}
+
+impl fmt::Display for HlcTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}@{}", self.physical, self.logical, self.device_id)
+    }
+}
+
+/// Source of wall clock time in milliseconds
+pub type TimeSource = Arc<dyn Fn() -> u64 + Send + Sync>;
+
+/// Hybrid logical clock
+pub struct HybridClock {
+    device_id: String,
+    time: TimeSource,
+    /// Highest physical time seen and the logical counter within it
+    state: Mutex<(u64, u32)>,
+}
+
+impl HybridClock {
+    /// Create a clock driven by the system time
+    pub fn new(device_id: impl Into<String>) -> Self {
+        Self::with_time_source(
+            device_id,
+            Arc::new(|| chrono::Utc::now().timestamp_millis() as u64),
+        )
+    }
+
+    /// Create a clock driven by a custom time source
+    pub fn with_time_source(device_id: impl Into<String>, time: TimeSource) -> Self {
+        Self {
+            device_id: device_id.into(),
+            time,
+            state: Mutex::new((0, 0)),
+        }
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Timestamp a local event
+    pub fn now(&self) -> HlcTimestamp {
+        let physical = (self.time)();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if physical > state.0 {
+            *state = (physical, 0);
+        } else {
+            state.1 += 1;
+        }
+
+        self.timestamp(*state)
+    }
+
+    /// Merge a timestamp received from a peer, returning the timestamp of the receive event
+    ///
+    /// Fails if the remote clock is too far ahead, so a peer with a wrong clock
+    /// cannot win every conflict.
+    pub fn observe(&self, remote: &HlcTimestamp) -> Result<HlcTimestamp> {
+        let physical = (self.time)();
+        if remote.physical > physical + MAX_CLOCK_DRIFT_MILLIS {
+            return Err(NetworkError::InvalidMessage(format!(
+                "Clock of {} is {} ms ahead",
+                remote.device_id,
+                remote.physical - physical
+            )));
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (local, counter) = *state;
+        let latest = physical.max(local).max(remote.physical);
+
+        *state = if latest == local && latest == remote.physical {
+            (latest, counter.max(remote.logical) + 1)
+        } else if latest == local {
+            (latest, counter + 1)
+        } else if latest == remote.physical {
+            (latest, remote.logical + 1)
+        } else {
+            (latest, 0)
+        };
+
+        Ok(self.timestamp(*state))
+    }
+
+    fn timestamp(&self, (physical, logical): (u64, u32)) -> HlcTimestamp {
+        HlcTimestamp {
+            physical,
+            logical,
+            device_id: self.device_id.clone(),
+        }
+    }
+}
+
+/// What to do with an item offered to the [`LwwRegister`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The item is the latest write and should be applied
+    Apply,
+    /// A later write is already current
+    Stale,
+    /// The item is already current, e.g. an echo of an applied item
+    Duplicate,
+}
+
+/// Last-writer-wins register of the current clipboard item
+#[derive(Debug, Default)]
+pub struct LwwRegister {
+    current: Option<(HlcTimestamp, String)>,
+}
+
+impl LwwRegister {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timestamp and checksum of the current item
+    pub fn current(&self) -> Option<(&HlcTimestamp, &str)> {
+        self.current
+            .as_ref()
+            .map(|(stamp, checksum)| (stamp, checksum.as_str()))
+    }
+
+    /// Offer an item written at `stamp`, making it current if it is the latest write
+    pub fn offer(&mut self, stamp: &HlcTimestamp, checksum: &str) -> Resolution {
+        match &mut self.current {
+            Some((current, current_checksum)) if current_checksum == checksum => {
+                if stamp > current {
+                    *current = stamp.clone();
+                }
+                Resolution::Duplicate
+            }
+            Some((current, _)) if stamp <= current => Resolution::Stale,
+            _ => {
+                self.current = Some((stamp.clone(), checksum.to_string()));
+                Resolution::Apply
+            }
+        }
+    }
+}
+
+/// Clock and register shared by the clipboard monitor and the app
+///
+/// The register is locked while the monitor reads a local copy and while a
+/// received item is written to the clipboard, so both see the same order.
+pub struct ClipboardOrdering {
+    clock: HybridClock,
+    register: tokio::sync::Mutex<LwwRegister>,
+}
+
+impl ClipboardOrdering {
+    pub fn new(clock: HybridClock) -> Self {
+        Self {
+            clock,
+            register: tokio::sync::Mutex::new(LwwRegister::new()),
+        }
+    }
+
+    pub fn clock(&self) -> &HybridClock {
+        &self.clock
+    }
+
+    /// Lock the register of the item currently on the clipboard
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, LwwRegister> {
+        self.register.lock().await
+    }
+}
+
+/// Bounded set of recently seen message IDs
+#[derive(Debug)]
+pub struct SeenMessages {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl SeenMessages {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Record a message ID, returning `false` if it was seen before
+    pub fn insert(&mut self, message_id: &str) -> bool {
+        if self.ids.contains(message_id) {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.order.push_back(message_id.to_string());
+        self.ids.insert(message_id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn manual_clock(device_id: &str, time: &Arc<AtomicU64>) -> HybridClock {
+        let time = time.clone();
+        HybridClock::with_time_source(device_id, Arc::new(move || time.load(Ordering::SeqCst)))
+    }
+
+    #[test]
+    fn test_clock_is_monotonic_and_merges_remote_time() {
+        let time = Arc::new(AtomicU64::new(1_000));
+        let clock = manual_clock("a", &time);
+
+        let first = clock.now();
+        let second = clock.now();
+        assert_eq!((first.physical, first.logical), (1_000, 0));
+        assert_eq!((second.physical, second.logical), (1_000, 1));
+
+        // A remote clock slightly ahead pulls the local clock forward
+        let remote = HlcTimestamp { physical: 1_500, logical: 3, device_id: "b".to_string() };
+        let received = clock.observe(&remote).unwrap();
+        assert!(received > remote);
+        assert!(clock.now() > received);
+
+        // The wall clock going backwards does not move timestamps backwards
+        time.store(500, Ordering::SeqCst);
+        assert!(clock.now() > received);
+
+        let far_ahead = HlcTimestamp { physical: 500 + MAX_CLOCK_DRIFT_MILLIS + 1, logical: 0, device_id: "b".to_string() };
+        assert!(clock.observe(&far_ahead).is_err());
+    }
+
+    #[test]
+    fn test_device_id_breaks_ties() {
+        let register_stamp = |device_id: &str| HlcTimestamp { physical: 1_000, logical: 0, device_id: device_id.to_string() };
+
+        let mut register = LwwRegister::new();
+        assert_eq!(register.offer(&register_stamp("a"), "x"), Resolution::Apply);
+        assert_eq!(register.offer(&register_stamp("b"), "y"), Resolution::Apply);
+        assert_eq!(register.offer(&register_stamp("a"), "x"), Resolution::Stale);
+        assert_eq!(register.offer(&register_stamp("c"), "y"), Resolution::Duplicate);
+        assert_eq!(register.current().unwrap().0.device_id, "c");
+    }
+
+    #[test]
+    fn test_seen_messages_are_bounded() {
+        let mut seen = SeenMessages::new(2);
+        assert!(seen.insert("1"));
+        assert!(!seen.insert("1"));
+        assert!(seen.insert("2"));
+        assert!(seen.insert("3"));
+        assert!(seen.insert("1"));
+    }
+
+    /// Simulated device: a clock, a register and the applied clipboard item
+    struct Node {
+        clock: HybridClock,
+        register: LwwRegister,
+        clipboard: Option<String>,
+    }
+
+    impl Node {
+        fn copy(&mut self, item: &str) -> (HlcTimestamp, String) {
+            let stamp = self.clock.now();
+            self.register.offer(&stamp, item);
+            self.clipboard = Some(item.to_string());
+            (stamp, item.to_string())
+        }
+
+        fn receive(&mut self, (stamp, item): &(HlcTimestamp, String)) {
+            self.clock.observe(stamp).unwrap();
+            if self.register.offer(stamp, item) == Resolution::Apply {
+                self.clipboard = Some(item.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_copies_converge_in_any_delivery_order() {
+        let time = Arc::new(AtomicU64::new(10_000));
+        let devices = ["laptop", "desktop", "phone"];
+
+        // Every permutation of delivery order must end in the same state
+        let orders: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let mut outcomes = HashSet::new();
+
+        for order in orders {
+            let mut nodes: Vec<Node> = devices
+                .iter()
+                .map(|device| Node {
+                    clock: manual_clock(device, &time),
+                    register: LwwRegister::new(),
+                    clipboard: None,
+                })
+                .collect();
+
+            // All three copy within the same millisecond
+            let writes: Vec<_> = nodes
+                .iter_mut()
+                .zip(devices)
+                .map(|(node, device)| node.copy(&format!("copied on {}", device)))
+                .collect();
+
+            for (index, node) in nodes.iter_mut().enumerate() {
+                for &from in &order {
+                    if from != index {
+                        node.receive(&writes[from]);
+                    }
+                }
+            }
+
+            let clipboards: HashSet<_> = nodes.iter().map(|node| node.clipboard.clone()).collect();
+            assert_eq!(clipboards.len(), 1, "nodes diverged for order {:?}", order);
+            outcomes.extend(clipboards);
+        }
+
+        // The highest device ID wins the tie, whatever the delivery order
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes.into_iter().next().unwrap().as_deref(), Some("copied on phone"));
+
+        // A later copy after seeing the others wins everywhere
+        let mut laptop = Node { clock: manual_clock("laptop", &time), register: LwwRegister::new(), clipboard: None };
+        let mut phone = Node { clock: manual_clock("phone", &time), register: LwwRegister::new(), clipboard: None };
+        let early = phone.copy("early");
+        laptop.receive(&early);
+        let late = laptop.copy("late");
+        phone.receive(&late);
+        assert_eq!(phone.clipboard.as_deref(), Some("late"));
+        assert_eq!(laptop.clipboard.as_deref(), Some("late"));
+    }
+}
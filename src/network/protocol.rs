@@ -1,5 +1,8 @@
 //! Network protocol implementation
 
+use crate::clipboard::CompressionCodec;
+use crate::events::TraceContext;
+use crate::network::HlcTimestamp;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -19,6 +22,23 @@ pub enum MessageType {
     DeviceInfo = 0x0004,
     Ack = 0x0005,
     Error = 0x0006,
+    /// Metadata of a large item whose body is fetched on demand
+    ClipboardAnnounce = 0x0007,
+    /// Pull request for an announced item's body
+    ContentRequest = 0x0008,
+    /// Transfer manifest of an announced item
+    ContentResponse = 0x0009,
+    /// Request for one chunk of an item
+    ChunkRequest = 0x000A,
+    /// Data of one chunk
+    ChunkResponse = 0x000B,
+}
+
+impl MessageType {
+    /// Whether the application answers this message itself instead of an automatic ack
+    pub fn expects_response(&self) -> bool {
+        matches!(self, MessageType::ContentRequest | MessageType::ChunkRequest)
+    }
 }
 
 /// Protocol version information
@@ -51,6 +71,17 @@ pub struct Message {
     pub payload: Vec<u8>,
 }
 
+/// Capabilities a peer announces when a connection is established
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakeInfo {
+    pub device_system: String,
+    /// Configured device name, used to match sync policy targets
+    #[serde(default)]
+    pub device_name: Option<String>,
+    /// Codecs the peer can decompress
+    pub compression_codecs: Vec<CompressionCodec>,
+}
+
 /// Message header
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageHeader {
@@ -62,6 +93,15 @@ pub struct MessageHeader {
     pub device_system: String,
     pub message_id: String,
     pub checksum: String,
+    /// Hybrid logical clock timestamp of clipboard updates
+    #[serde(default)]
+    pub clock: Option<HlcTimestamp>,
+    /// Device the update originated on
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// Trace of the clipboard item carried by the message
+    #[serde(default)]
+    pub trace: Option<TraceContext>,
 }
 
 impl Message {
@@ -80,11 +120,27 @@ impl Message {
             device_system,
             message_id: uuid::Uuid::new_v4().to_string(),
             checksum: Self::calculate_checksum(&payload),
+            clock: None,
+            origin: None,
+            trace: None,
         };
 
         Self { header, payload }
     }
 
+    /// Stamp the message with the originating device's clock
+    pub fn with_clock(mut self, clock: HlcTimestamp) -> Self {
+        self.header.origin = Some(clock.device_id.clone());
+        self.header.clock = Some(clock);
+        self
+    }
+
+    /// Attach the trace of the carried item
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.header.trace = Some(trace);
+        self
+    }
+
     /// Verify message integrity
     pub fn verify(&self) -> bool {
         let calculated_checksum = Self::calculate_checksum(&self.payload);
@@ -109,6 +165,11 @@ impl fmt::Display for MessageType {
             MessageType::DeviceInfo => write!(f, "DEVICE_INFO"),
             MessageType::Ack => write!(f, "ACK"),
             MessageType::Error => write!(f, "ERROR"),
+            MessageType::ClipboardAnnounce => write!(f, "CLIPBOARD_ANNOUNCE"),
+            MessageType::ContentRequest => write!(f, "CONTENT_REQUEST"),
+            MessageType::ContentResponse => write!(f, "CONTENT_RESPONSE"),
+            MessageType::ChunkRequest => write!(f, "CHUNK_REQUEST"),
+            MessageType::ChunkResponse => write!(f, "CHUNK_RESPONSE"),
         }
     }
 }
@@ -0,0 +1,240 @@
+//! Chunked transfer of large clipboard items
+//!
+//! The serving peer describes an item with a [`TransferManifest`] holding a
+//! SHA-256 checksum per chunk. The fetching peer requests chunks one at a time
+//! into an [`IncomingTransfer`], which is kept when a fetch fails so the next
+//! attempt only requests the chunks that are still missing.
+
+use crate::clipboard::{ClipboardContent, ContentMetadata, ContentType};
+use crate::network::{NetworkError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+
+/// Largest chunk size accepted, leaving room for framing and encryption
+pub const MAX_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Description of an item and its chunks, sent before any chunk data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferManifest {
+    pub content_type: ContentType,
+    pub metadata: ContentMetadata,
+    /// Checksum of the whole item
+    pub checksum: String,
+    pub total_bytes: usize,
+    pub chunk_size: usize,
+    /// Checksum of each chunk, in order
+    pub chunk_checksums: Vec<String>,
+}
+
+/// Request for a single chunk of an item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRequest {
+    pub checksum: String,
+    pub index: usize,
+}
+
+impl TransferManifest {
+    /// Describe an item split into chunks of `chunk_size` bytes
+    pub fn new(content: &ClipboardContent, chunk_size: usize) -> Self {
+        Self {
+            content_type: content.content_type.clone(),
+            metadata: content.metadata.clone(),
+            checksum: content.checksum.clone(),
+            total_bytes: content.data.len(),
+            chunk_size,
+            chunk_checksums: content.data.chunks(chunk_size).map(chunk_checksum).collect(),
+        }
+    }
+
+    /// Number of chunks
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_checksums.len()
+    }
+
+    /// Byte range covered by a chunk
+    pub fn chunk_range(&self, index: usize) -> Option<Range<usize>> {
+        if index >= self.chunk_count() {
+            return None;
+        }
+        let start = index * self.chunk_size;
+        Some(start..(start + self.chunk_size).min(self.total_bytes))
+    }
+
+    fn validate(&self, max_bytes: usize) -> Result<()> {
+        if self.total_bytes > max_bytes {
+            return Err(NetworkError::Transfer(format!(
+                "Item of {} bytes exceeds limit of {} bytes",
+                self.total_bytes, max_bytes
+            )));
+        }
+
+        if self.chunk_size == 0 || self.chunk_size > MAX_CHUNK_SIZE {
+            return Err(NetworkError::Transfer(format!("Invalid chunk size {}", self.chunk_size)));
+        }
+
+        if self.chunk_count() != (self.total_bytes + self.chunk_size - 1) / self.chunk_size {
+            return Err(NetworkError::Transfer(format!(
+                "Manifest lists {} chunks for {} bytes",
+                self.chunk_count(),
+                self.total_bytes
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Partially received item
+#[derive(Debug)]
+pub struct IncomingTransfer {
+    manifest: TransferManifest,
+    data: Vec<u8>,
+    received: Vec<bool>,
+    bytes_done: usize,
+}
+
+impl IncomingTransfer {
+    /// Start receiving the item described by `manifest`
+    pub fn new(manifest: TransferManifest, max_bytes: usize) -> Result<Self> {
+        manifest.validate(max_bytes)?;
+
+        Ok(Self {
+            data: vec![0; manifest.total_bytes],
+            received: vec![false; manifest.chunk_count()],
+            bytes_done: 0,
+            manifest,
+        })
+    }
+
+    pub fn manifest(&self) -> &TransferManifest {
+        &self.manifest
+    }
+
+    /// Index of the first chunk not received yet
+    pub fn next_missing(&self) -> Option<usize> {
+        self.received.iter().position(|received| !received)
+    }
+
+    /// Store a chunk after checking it against the manifest
+    pub fn accept(&mut self, index: usize, chunk: &[u8]) -> Result<()> {
+        let range = self
+            .manifest
+            .chunk_range(index)
+            .ok_or_else(|| NetworkError::Transfer(format!("Unexpected chunk {}", index)))?;
+
+        if chunk.len() != range.len() || chunk_checksum(chunk) != self.manifest.chunk_checksums[index] {
+            return Err(NetworkError::Transfer(format!("Chunk {} failed verification", index)));
+        }
+
+        if !self.received[index] {
+            self.data[range].copy_from_slice(chunk);
+            self.received[index] = true;
+            self.bytes_done += chunk.len();
+        }
+
+        Ok(())
+    }
+
+    pub fn bytes_done(&self) -> usize {
+        self.bytes_done
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.manifest.total_bytes
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_missing().is_none()
+    }
+
+    /// Reassemble the item and verify it against its checksum
+    pub fn into_content(self) -> Result<ClipboardContent> {
+        if !self.is_complete() {
+            return Err(NetworkError::Transfer(format!(
+                "Transfer of {} is incomplete",
+                self.manifest.checksum
+            )));
+        }
+
+        let content = ClipboardContent {
+            content_type: self.manifest.content_type,
+            data: self.data,
+            metadata: self.manifest.metadata,
+            checksum: self.manifest.checksum,
+        };
+
+        if !content.verify_integrity() {
+            return Err(NetworkError::Transfer(format!(
+                "Reassembled item does not match checksum {}",
+                content.checksum
+            )));
+        }
+
+        Ok(content)
+    }
+}
+
+fn chunk_checksum(chunk: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(chunk))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(size: usize) -> ClipboardContent {
+        let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        ClipboardContent::new_file(data, "data.bin".to_string(), None, "test-device".to_string())
+    }
+
+    fn chunk(content: &ClipboardContent, manifest: &TransferManifest, index: usize) -> Vec<u8> {
+        content.data[manifest.chunk_range(index).unwrap()].to_vec()
+    }
+
+    #[test]
+    fn test_chunks_reassemble_in_any_order() {
+        let content = item(10_000);
+        let manifest = TransferManifest::new(&content, 4096);
+        assert_eq!(manifest.chunk_count(), 3);
+        assert_eq!(manifest.chunk_range(2), Some(8192..10_000));
+
+        let mut transfer = IncomingTransfer::new(manifest.clone(), usize::MAX).unwrap();
+        for index in [2, 0, 1] {
+            transfer.accept(index, &chunk(&content, &manifest, index)).unwrap();
+        }
+
+        assert_eq!(transfer.bytes_done(), 10_000);
+        let received = transfer.into_content().unwrap();
+        assert_eq!(received.data, content.data);
+        assert_eq!(received.metadata.filename.as_deref(), Some("data.bin"));
+    }
+
+    #[test]
+    fn test_transfer_resumes_from_missing_chunk() {
+        let content = item(10_000);
+        let manifest = TransferManifest::new(&content, 4096);
+        let mut transfer = IncomingTransfer::new(manifest.clone(), usize::MAX).unwrap();
+
+        transfer.accept(0, &chunk(&content, &manifest, 0)).unwrap();
+        assert_eq!(transfer.next_missing(), Some(1));
+        assert!(!transfer.is_complete());
+
+        // A corrupted chunk is rejected and stays missing
+        let mut corrupted = chunk(&content, &manifest, 1);
+        corrupted[0] ^= 0xff;
+        assert!(transfer.accept(1, &corrupted).is_err());
+        assert_eq!(transfer.next_missing(), Some(1));
+        assert_eq!(transfer.bytes_done(), 4096);
+    }
+
+    #[test]
+    fn test_manifest_limits_are_enforced() {
+        let content = item(10_000);
+        assert!(IncomingTransfer::new(TransferManifest::new(&content, 4096), 1000).is_err());
+
+        let mut manifest = TransferManifest::new(&content, 4096);
+        manifest.chunk_checksums.pop();
+        assert!(IncomingTransfer::new(manifest, usize::MAX).is_err());
+    }
+}
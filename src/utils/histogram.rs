@@ -0,0 +1,233 @@
+//! Bounded duration histograms
+//!
+//! Durations are counted in log-linear buckets, 16 per power of two of
+//! microseconds, so every value is known to within about 6% while memory stays
+//! bounded however many values are recorded. Percentiles, minimum and maximum
+//! cover a sliding window made of time slots that are dropped as they age out.
+
+use crate::utils::metrics::DurationStats;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Default span of the sliding window
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(300);
+
+/// Number of slots the window is divided into
+pub const WINDOW_SLOTS: u32 = 10;
+
+/// Values recorded during one slot of the window
+#[derive(Debug, Clone)]
+struct Slot {
+    start: Instant,
+    buckets: BTreeMap<u16, u64>,
+    count: u64,
+    min: u64,
+    max: u64,
+}
+
+/// Histogram of durations over a sliding window
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    slot_width: Duration,
+    slots: VecDeque<Slot>,
+    /// Values recorded since creation
+    count: u64,
+    /// Sum of the values recorded since creation
+    total: Duration,
+}
+
+impl Histogram {
+    /// Create a histogram whose percentiles cover the last `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            slot_width: (window / WINDOW_SLOTS).max(Duration::from_millis(1)),
+            slots: VecDeque::new(),
+            count: 0,
+            total: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, value: Duration) {
+        self.record_at(value, Instant::now());
+    }
+
+    fn record_at(&mut self, value: Duration, now: Instant) {
+        self.count += 1;
+        self.total = self.total.saturating_add(value);
+
+        let window = self.window();
+        while self.slots.front().is_some_and(|slot| now.saturating_duration_since(slot.start) >= window) {
+            self.slots.pop_front();
+        }
+        if !self.slots.back().is_some_and(|slot| now.saturating_duration_since(slot.start) < self.slot_width) {
+            self.slots.push_back(Slot {
+                start: now,
+                buckets: BTreeMap::new(),
+                count: 0,
+                min: u64::MAX,
+                max: 0,
+            });
+        }
+
+        let micros = micros(value);
+        let slot = self.slots.back_mut().expect("slot was just pushed");
+        *slot.buckets.entry(bucket_index(micros)).or_insert(0) += 1;
+        slot.count += 1;
+        slot.min = slot.min.min(micros);
+        slot.max = slot.max.max(micros);
+    }
+
+    /// Values recorded since creation
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of the values recorded since creation
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Mean of the values recorded since creation
+    pub fn average(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64))
+    }
+
+    pub fn stats(&self) -> DurationStats {
+        self.stats_at(Instant::now())
+    }
+
+    fn stats_at(&self, now: Instant) -> DurationStats {
+        let window = self.window();
+        let mut buckets = BTreeMap::new();
+        let (mut count, mut min, mut max) = (0, u64::MAX, 0);
+        for slot in self.slots.iter().filter(|slot| now.saturating_duration_since(slot.start) < window) {
+            for (index, bucket_count) in &slot.buckets {
+                *buckets.entry(*index).or_insert(0) += bucket_count;
+            }
+            count += slot.count;
+            min = min.min(slot.min);
+            max = max.max(slot.max);
+        }
+
+        let quantile = |q: f64| {
+            let rank = ((q * count as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (index, bucket_count) in &buckets {
+                seen += bucket_count;
+                if seen >= rank {
+                    return Duration::from_micros(bucket_middle(*index).clamp(min, max));
+                }
+            }
+            Duration::ZERO
+        };
+
+        DurationStats {
+            count: self.count as usize,
+            average: self.average().unwrap_or_default(),
+            total: self.total,
+            window_count: count as usize,
+            min: if count == 0 { Duration::ZERO } else { Duration::from_micros(min) },
+            max: Duration::from_micros(max),
+            p50: quantile(0.5),
+            p90: quantile(0.9),
+            p99: quantile(0.99),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        self.slot_width * WINDOW_SLOTS
+    }
+}
+
+fn micros(value: Duration) -> u64 {
+    value.as_micros().min(u64::MAX as u128) as u64
+}
+
+/// Bucket holding `value`: exact below 16, then 16 buckets per power of two
+fn bucket_index(value: u64) -> u16 {
+    if value < SUB_BUCKETS {
+        return value as u16;
+    }
+    let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+    let sub_bucket = (value >> shift) - SUB_BUCKETS;
+    ((shift as u64 + 1) * SUB_BUCKETS + sub_bucket) as u16
+}
+
+/// Middle of the values a bucket holds
+fn bucket_middle(index: u16) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let low = ((SUB_BUCKETS + index % SUB_BUCKETS) as u128) << shift;
+    let width = 1u128 << shift;
+    (low + width / 2).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_bound_relative_error() {
+        for value in [0, 1, 15, 16, 17, 31, 32, 33, 1_000, 123_456, 10_000_000_000, u64::MAX] {
+            let middle = bucket_middle(bucket_index(value));
+            let error = (middle as f64 - value as f64).abs() / (value.max(1) as f64);
+            assert!(error <= 1.0 / SUB_BUCKETS as f64, "{} recorded as {}", value, middle);
+        }
+        assert!(bucket_index(u64::MAX) < 1024);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::new(DEFAULT_WINDOW);
+        let now = Instant::now();
+        for millis in 1..=100 {
+            histogram.record_at(Duration::from_millis(millis), now);
+        }
+
+        let stats = histogram.stats_at(now);
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.window_count, 100);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(stats.average, Duration::from_micros(50_500));
+        for (percentile, expected) in [(stats.p50, 50.0), (stats.p90, 90.0), (stats.p99, 99.0)] {
+            let millis = percentile.as_secs_f64() * 1000.0;
+            assert!((millis - expected).abs() / expected < 0.07, "{} instead of {}", millis, expected);
+        }
+    }
+
+    #[test]
+    fn test_old_values_leave_the_window() {
+        let mut histogram = Histogram::new(Duration::from_secs(10));
+        let start = Instant::now();
+        histogram.record_at(Duration::from_secs(5), start);
+        histogram.record_at(Duration::from_millis(10), start + Duration::from_secs(8));
+
+        let stats = histogram.stats_at(start + Duration::from_secs(12));
+        assert_eq!(stats.window_count, 1);
+        assert_eq!(stats.max, Duration::from_millis(10));
+        assert_eq!(stats.p99, Duration::from_millis(10));
+        // Totals still cover every value
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.total, Duration::from_millis(5_010));
+
+        // Memory does not grow with the number of values recorded
+        for second in 0..1_000 {
+            histogram.record_at(Duration::from_micros(second * 7), start + Duration::from_secs(second));
+        }
+        assert!(histogram.slots.len() <= WINDOW_SLOTS as usize + 1);
+
+        let empty = histogram.stats_at(start + Duration::from_secs(2_000));
+        assert_eq!(empty.window_count, 0);
+        assert_eq!(empty.p50, Duration::ZERO);
+    }
+}
@@ -1,56 +1,200 @@
 //! Logging utilities
+//!
+//! [`init_from_config`] sets up logging as described by [`LoggingConfig`]:
+//! plain text or JSON lines, to stdout, to a size-rotated file, or both, with
+//! levels per module.
 
+use crate::config::LoggingConfig;
 use crate::utils::{Result, UtilError};
 use env_logger::{Builder, Target};
-use log::LevelFilter;
-use std::io::Write;
+use log::{LevelFilter, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 /// Initialize the logger with the specified configuration
 pub fn init_logger(level: &str) -> Result<()> {
-    let log_level = parse_log_level(level)?;
-
-    Builder::from_default_env()
-        .target(Target::Stdout)
-        .filter_level(log_level)
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "{} [{}] [{}:{}] - {}",
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                record.level(),
-                record.file().unwrap_or("unknown"),
-                record.line().unwrap_or(0),
-                record.args()
-            )
-        })
+    init_from_config(&LoggingConfig {
+        level: level.to_string(),
+        ..LoggingConfig::default()
+    })
+}
+
+/// Initialize file-based logger, appending to the file
+pub fn init_file_logger(level: &str, file_path: &str) -> Result<()> {
+    init_from_config(&LoggingConfig {
+        level: level.to_string(),
+        file_path: Some(file_path.to_string()),
+        stdout: false,
+        ..LoggingConfig::default()
+    })
+}
+
+/// Initialize the logger from the logging configuration
+pub fn init_from_config(config: &LoggingConfig) -> Result<()> {
+    builder(config)?
         .try_init()
-        .map_err(|e| UtilError::LoggerInitFailed(e.to_string()))?;
+        .map_err(|e| UtilError::LoggerInitFailed(e.to_string()))
+}
 
-    Ok(())
+/// Logger builder for the configuration, without installing it
+fn builder(config: &LoggingConfig) -> Result<Builder> {
+    let mut builder = Builder::from_default_env();
+    builder.filter_level(parse_log_level(&config.level)?);
+    for (module, level) in &config.modules {
+        builder.filter_module(module, parse_log_level(level)?);
+    }
+
+    match &config.file_path {
+        Some(path) => {
+            let file = RotatingFile::open(path, config.max_file_size as u64, config.max_files)?;
+            builder.target(Target::Pipe(Box::new(LogOutput {
+                stdout: config.stdout,
+                file: Some(file),
+            })));
+        }
+        None => {
+            builder.target(Target::Stdout);
+        }
+    }
+
+    let structured = config.structured;
+    builder.format(move |buf, record| write_record(buf, record, structured));
+    Ok(builder)
 }
 
-/// Initialize file-based logger
-pub fn init_file_logger(level: &str, file_path: &str) -> Result<()> {
-    let log_level = parse_log_level(level)?;
-
-    Builder::from_default_env()
-        .target(Target::Pipe(Box::new(std::fs::File::create(file_path)?)))
-        .filter_level(log_level)
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "{} [{}] [{}:{}] - {}",
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                record.level(),
-                record.file().unwrap_or("unknown"),
-                record.line().unwrap_or(0),
-                record.args()
-            )
+/// Write one record as a text line or a JSON line
+fn write_record(out: &mut impl Write, record: &Record, structured: bool) -> io::Result<()> {
+    let timestamp = chrono::Utc::now();
+    if structured {
+        let line = serde_json::json!({
+            "timestamp": timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "file": record.file(),
+            "line": record.line(),
+            "message": record.args().to_string(),
+        });
+        writeln!(out, "{}", line)
+    } else {
+        writeln!(
+            out,
+            "{} [{}] [{}:{}] - {}",
+            timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.file().unwrap_or("unknown"),
+            record.line().unwrap_or(0),
+            record.args()
+        )
+    }
+}
+
+/// Log destination writing to stdout, a log file or both
+struct LogOutput {
+    stdout: bool,
+    file: Option<RotatingFile>,
+}
+
+impl Write for LogOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    // Every record arrives in one call, so a record is never split across files
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.stdout {
+            io::stdout().write_all(buf)?;
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(buf)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.stdout {
+            io::stdout().flush()?;
+        }
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Log file rotated by size
+///
+/// When the file would grow past `max_size` it is renamed to `<path>.1`, older
+/// files move up by one, and files beyond `max_files` in total are deleted.
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: impl AsRef<Path>, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_files: max_files.max(1),
+            file,
+            size,
         })
-        .try_init()
-        .map_err(|e| UtilError::LoggerInitFailed(e.to_string()))?;
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 1 {
+            // Nothing older is kept, so the file starts over
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.max_files - 1));
+            for index in (1..self.max_files - 1).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
 
-    Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
 }
 
 /// Parse log level string to LevelFilter
@@ -68,3 +212,99 @@ fn parse_log_level(level: &str) -> Result<LevelFilter> {
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, Log, Metadata};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_rotation_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("crosscopy.log");
+        let mut file = RotatingFile::open(&path, 20, 3).unwrap();
+
+        for line in ["first line 1\n", "second line\n", "third line\n", "fourth line\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "fourth line\n");
+        assert_eq!(read(file.rotated_path(1)), "third line\n");
+        assert_eq!(read(file.rotated_path(2)), "second line\n");
+        assert!(!file.rotated_path(3).exists());
+
+        // Reopening appends instead of truncating
+        drop(file);
+        let mut file = RotatingFile::open(&path, 100, 3).unwrap();
+        file.write_all(b"fifth line\n").unwrap();
+        assert_eq!(read(path), "fourth line\nfifth line\n");
+    }
+
+    #[test]
+    fn test_structured_records_are_json_lines() {
+        let mut out = Vec::new();
+        write_record(
+            &mut out,
+            &Record::builder()
+                .args(format_args!("synced \"item\""))
+                .level(Level::Warn)
+                .target("crosscopy::network")
+                .line(Some(7))
+                .build(),
+            true,
+        )
+        .unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with('\n'));
+        let line: serde_json::Value = serde_json::from_str(text.trim_end()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "crosscopy::network");
+        assert_eq!(line["message"], "synced \"item\"");
+        assert_eq!(line["line"], 7);
+    }
+
+    #[test]
+    fn test_module_levels_and_file_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crosscopy.log");
+        let config = LoggingConfig {
+            level: "info".to_string(),
+            file_path: Some(path.to_string_lossy().into_owned()),
+            structured: true,
+            stdout: false,
+            modules: HashMap::from([
+                ("libp2p".to_string(), "warn".to_string()),
+                ("crosscopy::network".to_string(), "trace".to_string()),
+            ]),
+            ..LoggingConfig::default()
+        };
+        let logger = builder(&config).unwrap().build();
+
+        let enabled = |target: &str, level: Level| logger.enabled(&Metadata::builder().target(target).level(level).build());
+        assert!(enabled("crosscopy::clipboard", Level::Info));
+        assert!(!enabled("crosscopy::clipboard", Level::Debug));
+        assert!(enabled("crosscopy::network::manager", Level::Trace));
+        assert!(!enabled("libp2p::swarm", Level::Info));
+
+        logger.log(
+            &Record::builder()
+                .args(format_args!("connected"))
+                .level(Level::Info)
+                .target("crosscopy::network")
+                .build(),
+        );
+        logger.flush();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\"message\":\"connected\""));
+
+        let invalid = LoggingConfig {
+            modules: HashMap::from([("libp2p".to_string(), "loud".to_string())]),
+            ..LoggingConfig::default()
+        };
+        assert!(builder(&invalid).is_err());
+    }
+}
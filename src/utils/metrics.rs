@@ -1,134 +1,197 @@
 //! Performance metrics collection
+//!
+//! Durations are kept in bounded [`Histogram`]s, so a long-running daemon can
+//! record them indefinitely. Every series may carry labels, e.g. per peer:
+//!
+//! ```rust
+//! # async fn example() {
+//! use crosscopy::utils::metrics::{MetricKey, PerformanceMetrics};
+//! use std::time::Duration;
+//!
+//! let metrics = PerformanceMetrics::new();
+//! metrics.increment_counter("clipboard_changes").await;
+//! metrics
+//!     .record_duration(MetricKey::new("sync_latency").label("peer", "laptop"), Duration::from_millis(40))
+//!     .await;
+//! # }
+//! ```
 
-use crate::utils::{Result, UtilError};
+use crate::utils::histogram::{Histogram, DEFAULT_WINDOW};
 use log::debug;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+
+/// Name and labels of a series
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MetricKey {
+    pub name: String,
+    /// Labels sorted by key
+    pub labels: Vec<(String, String)>,
+}
+
+impl MetricKey {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Add a label, replacing a label with the same key
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        match self.labels.binary_search_by(|(existing, _)| existing.cmp(&key)) {
+            Ok(index) => self.labels[index].1 = value.into(),
+            Err(index) => self.labels.insert(index, (key, value.into())),
+        }
+        self
+    }
+}
+
+impl fmt::Display for MetricKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.labels.is_empty() {
+            let labels: Vec<String> = self.labels.iter().map(|(key, value)| format!("{}={:?}", key, value)).collect();
+            write!(f, "{{{}}}", labels.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for MetricKey {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<&String> for MetricKey {
+    fn from(name: &String) -> Self {
+        Self::new(name.as_str())
+    }
+}
+
+impl From<String> for MetricKey {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
 
 /// Performance metrics collector
+///
+/// The maps are only locked briefly and never across an await, so they use
+/// blocking locks that also work outside a Tokio runtime.
 pub struct PerformanceMetrics {
-    timers: Arc<RwLock<HashMap<String, Instant>>>,
-    counters: Arc<RwLock<HashMap<String, u64>>>,
-    durations: Arc<RwLock<HashMap<String, Vec<Duration>>>>,
-    gauges: Arc<RwLock<HashMap<String, f64>>>,
+    timers: Arc<Mutex<HashMap<String, Instant>>>,
+    counters: Arc<Mutex<HashMap<MetricKey, u64>>>,
+    durations: Arc<Mutex<HashMap<MetricKey, Histogram>>>,
+    gauges: Arc<Mutex<HashMap<MetricKey, f64>>>,
+    /// Span of the sliding window percentiles are computed over
+    window: Duration,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
 }
 
 impl PerformanceMetrics {
     /// Create a new metrics collector
     pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Create a collector whose percentiles cover the last `window`
+    pub fn with_window(window: Duration) -> Self {
         Self {
-            timers: Arc::new(RwLock::new(HashMap::new())),
-            counters: Arc::new(RwLock::new(HashMap::new())),
-            durations: Arc::new(RwLock::new(HashMap::new())),
-            gauges: Arc::new(RwLock::new(HashMap::new())),
+            timers: Arc::new(Mutex::new(HashMap::new())),
+            counters: Arc::new(Mutex::new(HashMap::new())),
+            durations: Arc::new(Mutex::new(HashMap::new())),
+            gauges: Arc::new(Mutex::new(HashMap::new())),
+            window,
         }
     }
 
     /// Start a timer for a named operation
     pub async fn start_timer(&self, name: &str) {
-        let mut timers = self.timers.write().await;
-        timers.insert(name.to_string(), Instant::now());
+        lock(&self.timers).insert(name.to_string(), Instant::now());
         debug!("Started timer: {}", name);
     }
 
     /// End a timer and record the duration
     pub async fn end_timer(&self, name: &str) -> Option<Duration> {
-        let start_time = {
-            let mut timers = self.timers.write().await;
-            timers.remove(name)
-        };
-
-        if let Some(start_time) = start_time {
-            let duration = start_time.elapsed();
-            
-            {
-                let mut durations = self.durations.write().await;
-                durations
-                    .entry(name.to_string())
-                    .or_insert_with(Vec::new)
-                    .push(duration);
-            }
-
-            debug!("Ended timer: {} ({}ms)", name, duration.as_millis());
-            Some(duration)
-        } else {
-            None
-        }
+        let start_time = lock(&self.timers).remove(name)?;
+        let duration = start_time.elapsed();
+        self.record(MetricKey::new(name), duration);
+
+        debug!("Ended timer: {} ({}ms)", name, duration.as_millis());
+        Some(duration)
+    }
+
+    /// Record a duration measured elsewhere, e.g. across devices
+    pub async fn record_duration(&self, key: impl Into<MetricKey>, duration: Duration) {
+        let key = key.into();
+        debug!("Recorded duration: {} ({}ms)", key, duration.as_millis());
+        self.record(key, duration);
+    }
+
+    fn record(&self, key: MetricKey, duration: Duration) {
+        lock(&self.durations)
+            .entry(key)
+            .or_insert_with(|| Histogram::new(self.window))
+            .record(duration);
     }
 
     /// Increment a counter
-    pub async fn increment_counter(&self, name: &str) {
-        let mut counters = self.counters.write().await;
-        *counters.entry(name.to_string()).or_insert(0) += 1;
-        debug!("Incremented counter: {}", name);
+    pub async fn increment_counter(&self, key: impl Into<MetricKey>) {
+        let key = key.into();
+        *lock(&self.counters).entry(key.clone()).or_insert(0) += 1;
+        debug!("Incremented counter: {}", key);
     }
 
     /// Add to a counter
-    pub async fn add_to_counter(&self, name: &str, value: u64) {
-        let mut counters = self.counters.write().await;
-        *counters.entry(name.to_string()).or_insert(0) += value;
-        debug!("Added {} to counter: {}", value, name);
+    pub async fn add_to_counter(&self, key: impl Into<MetricKey>, value: u64) {
+        let key = key.into();
+        *lock(&self.counters).entry(key.clone()).or_insert(0) += value;
+        debug!("Added {} to counter: {}", value, key);
     }
 
     /// Set a gauge value
-    pub async fn set_gauge(&self, name: &str, value: f64) {
-        let mut gauges = self.gauges.write().await;
-        gauges.insert(name.to_string(), value);
-        debug!("Set gauge: {} = {}", name, value);
+    pub async fn set_gauge(&self, key: impl Into<MetricKey>, value: f64) {
+        let key = key.into();
+        debug!("Set gauge: {} = {}", key, value);
+        lock(&self.gauges).insert(key, value);
     }
 
     /// Get counter value
-    pub async fn get_counter(&self, name: &str) -> u64 {
-        let counters = self.counters.read().await;
-        counters.get(name).copied().unwrap_or(0)
+    pub async fn get_counter(&self, key: impl Into<MetricKey>) -> u64 {
+        lock(&self.counters).get(&key.into()).copied().unwrap_or(0)
     }
 
     /// Get gauge value
-    pub async fn get_gauge(&self, name: &str) -> Option<f64> {
-        let gauges = self.gauges.read().await;
-        gauges.get(name).copied()
+    pub async fn get_gauge(&self, key: impl Into<MetricKey>) -> Option<f64> {
+        lock(&self.gauges).get(&key.into()).copied()
     }
 
     /// Get average duration for a timer
-    pub async fn get_average_duration(&self, name: &str) -> Option<Duration> {
-        let durations = self.durations.read().await;
-        durations.get(name).and_then(|durations| {
-            if durations.is_empty() {
-                None
-            } else {
-                let total: Duration = durations.iter().sum();
-                Some(total / durations.len() as u32)
-            }
-        })
+    pub async fn get_average_duration(&self, key: impl Into<MetricKey>) -> Option<Duration> {
+        lock(&self.durations).get(&key.into()).and_then(Histogram::average)
+    }
+
+    /// Get count, average and percentiles for a timer
+    pub async fn get_duration_stats(&self, key: impl Into<MetricKey>) -> Option<DurationStats> {
+        lock(&self.durations).get(&key.into()).map(Histogram::stats)
     }
 
     /// Get all metrics as a summary
     pub async fn get_summary(&self) -> MetricsSummary {
-        let counters = self.counters.read().await.clone();
-        let gauges = self.gauges.read().await.clone();
-        
-        let mut duration_stats = HashMap::new();
-        {
-            let durations = self.durations.read().await;
-            for (name, durations_vec) in durations.iter() {
-                if !durations_vec.is_empty() {
-                    let total: Duration = durations_vec.iter().sum();
-                    let avg = total / durations_vec.len() as u32;
-                    let min = *durations_vec.iter().min().unwrap();
-                    let max = *durations_vec.iter().max().unwrap();
-                    
-                    duration_stats.insert(name.clone(), DurationStats {
-                        count: durations_vec.len(),
-                        average: avg,
-                        min,
-                        max,
-                        total,
-                    });
-                }
-            }
-        }
+        let counters = lock(&self.counters).clone();
+        let gauges = lock(&self.gauges).clone();
+        let duration_stats = lock(&self.durations)
+            .iter()
+            .map(|(key, histogram)| (key.clone(), histogram.stats()))
+            .collect();
 
         MetricsSummary {
             counters,
@@ -139,15 +202,10 @@ impl PerformanceMetrics {
 
     /// Reset all metrics
     pub async fn reset(&self) {
-        let mut timers = self.timers.write().await;
-        let mut counters = self.counters.write().await;
-        let mut durations = self.durations.write().await;
-        let mut gauges = self.gauges.write().await;
-
-        timers.clear();
-        counters.clear();
-        durations.clear();
-        gauges.clear();
+        lock(&self.timers).clear();
+        lock(&self.counters).clear();
+        lock(&self.durations).clear();
+        lock(&self.gauges).clear();
 
         debug!("Reset all metrics");
     }
@@ -160,50 +218,58 @@ impl Default for PerformanceMetrics {
 }
 
 /// Duration statistics
-#[derive(Debug, Clone)]
+///
+/// `count`, `average` and `total` cover every recorded duration; `min`, `max`
+/// and the percentiles cover the sliding window.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct DurationStats {
     pub count: usize,
     pub average: Duration,
     pub min: Duration,
     pub max: Duration,
     pub total: Duration,
+    /// Durations recorded within the window
+    pub window_count: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
 }
 
 /// Metrics summary
 #[derive(Debug, Clone)]
 pub struct MetricsSummary {
-    pub counters: HashMap<String, u64>,
-    pub gauges: HashMap<String, f64>,
-    pub duration_stats: HashMap<String, DurationStats>,
+    pub counters: HashMap<MetricKey, u64>,
+    pub gauges: HashMap<MetricKey, f64>,
+    pub duration_stats: HashMap<MetricKey, DurationStats>,
 }
 
-/// Timer guard that automatically ends the timer when dropped
+/// Timer guard that records the elapsed time when dropped
 pub struct TimerGuard {
     name: String,
+    start: Instant,
     metrics: Arc<PerformanceMetrics>,
 }
 
 impl TimerGuard {
     pub fn new(name: String, metrics: Arc<PerformanceMetrics>) -> Self {
-        Self { name, metrics }
+        Self {
+            name,
+            start: Instant::now(),
+            metrics,
+        }
     }
 }
 
 impl Drop for TimerGuard {
     fn drop(&mut self) {
-        let metrics = self.metrics.clone();
-        let name = self.name.clone();
-        
-        tokio::spawn(async move {
-            metrics.end_timer(&name).await;
-        });
+        // Recorded right away, so no runtime is needed to drop the guard
+        self.metrics.record(MetricKey::new(self.name.as_str()), self.start.elapsed());
     }
 }
 
 impl PerformanceMetrics {
-    /// Create a timer guard that automatically ends when dropped
-    pub async fn timer_guard(&self, name: &str) -> TimerGuard {
-        self.start_timer(name).await;
+    /// Create a timer guard that records the elapsed time when dropped
+    pub fn timer_guard(&self, name: &str) -> TimerGuard {
         TimerGuard::new(name.to_string(), Arc::new(self.clone()))
     }
 }
@@ -216,6 +282,7 @@ impl Clone for PerformanceMetrics {
             counters: self.counters.clone(),
             durations: self.durations.clone(),
             gauges: self.gauges.clone(),
+            window: self.window,
         }
     }
 }
@@ -261,6 +328,16 @@ mod tests {
         assert!(avg.is_some());
     }
 
+    #[tokio::test]
+    async fn test_record_duration() {
+        let metrics = PerformanceMetrics::new();
+
+        metrics.record_duration("latency", Duration::from_millis(10)).await;
+        metrics.record_duration("latency", Duration::from_millis(30)).await;
+
+        assert_eq!(metrics.get_average_duration("latency").await, Some(Duration::from_millis(20)));
+    }
+
     #[tokio::test]
     async fn test_metrics_summary() {
         let metrics = PerformanceMetrics::new();
@@ -273,8 +350,38 @@ mod tests {
         
         let summary = metrics.get_summary().await;
         
-        assert_eq!(summary.counters.get("counter1"), Some(&1));
-        assert_eq!(summary.gauges.get("gauge1"), Some(&100.0));
-        assert!(summary.duration_stats.contains_key("timer1"));
+        assert_eq!(summary.counters.get(&MetricKey::new("counter1")), Some(&1));
+        assert_eq!(summary.gauges.get(&MetricKey::new("gauge1")), Some(&100.0));
+        assert!(summary.duration_stats.contains_key(&MetricKey::new("timer1")));
+    }
+
+    #[tokio::test]
+    async fn test_labeled_series() {
+        let metrics = PerformanceMetrics::new();
+        let peer = |peer: &str| MetricKey::new("bytes_synced").label("peer", peer);
+
+        metrics.add_to_counter(peer("laptop"), 10).await;
+        metrics.add_to_counter(peer("phone"), 5).await;
+        metrics.add_to_counter(peer("laptop"), 1).await;
+
+        assert_eq!(metrics.get_counter(peer("laptop")).await, 11);
+        assert_eq!(metrics.get_counter(peer("phone")).await, 5);
+        assert_eq!(metrics.get_counter("bytes_synced").await, 0);
+
+        let key = MetricKey::new("latency").label("type", "text").label("peer", "laptop");
+        assert_eq!(key.to_string(), r#"latency{peer="laptop",type="text"}"#);
+    }
+
+    #[test]
+    fn test_timer_guard_outside_runtime() {
+        let metrics = PerformanceMetrics::new();
+        {
+            let _guard = metrics.timer_guard("guarded");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let stats = futures::executor::block_on(metrics.get_duration_stats("guarded")).unwrap();
+        assert_eq!(stats.count, 1);
+        assert!(stats.p99 >= Duration::from_millis(4));
     }
 }
@@ -6,8 +6,12 @@
 pub mod logger;
 pub mod platform;
 
+pub mod histogram;
 pub mod metrics;
 
+#[cfg(feature = "metrics-exporter")]
+pub mod openmetrics;
+
 use thiserror::Error;
 
 /// Utility-related errors
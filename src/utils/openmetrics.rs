@@ -0,0 +1,434 @@
+//! Prometheus/OpenMetrics endpoint
+//!
+//! Serves [`PerformanceMetrics`], [`NetworkStats`] and [`EventBusStats`] in
+//! OpenMetrics text format at `/metrics` over plain HTTP. The server answers
+//! one request per connection, which is all a Prometheus scraper needs. The
+//! metrics are not authenticated, so only loopback addresses are served.
+
+use crate::events::bus::EventBusStats;
+use crate::events::EventBus;
+use crate::network::manager::NetworkStats;
+use crate::utils::metrics::{MetricKey, MetricsSummary, PerformanceMetrics};
+use crate::utils::{Result, UtilError};
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Content type of the OpenMetrics text format
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Prefix of every exported metric name
+const PREFIX: &str = "crosscopy_";
+
+/// Longest request head read before the request is refused
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Time a client has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the exported metrics come from
+#[derive(Clone)]
+pub struct MetricsSources {
+    pub metrics: Arc<PerformanceMetrics>,
+    pub event_bus: Arc<EventBus>,
+    /// Network statistics, if the network layer is running
+    pub network: Option<Arc<RwLock<NetworkStats>>>,
+}
+
+impl MetricsSources {
+    /// Render the current metrics in OpenMetrics text format
+    pub async fn render(&self) -> String {
+        let summary = self.metrics.get_summary().await;
+        let network = match &self.network {
+            Some(stats) => Some(stats.read().await.clone()),
+            None => None,
+        };
+        render(&summary, network.as_ref(), &self.event_bus.get_stats().await)
+    }
+}
+
+/// Metrics HTTP server, stopped when dropped
+pub struct MetricsExporter {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MetricsExporter {
+    /// Listen on `address`, which must be a loopback address, and serve metrics from `sources`
+    pub async fn start(address: &str, sources: MetricsSources) -> Result<Self> {
+        let address: SocketAddr = address
+            .parse()
+            .map_err(|_| UtilError::MetricsError(format!("Invalid listen address: {}", address)))?;
+        if !address.ip().is_loopback() {
+            return Err(UtilError::MetricsError(format!("Listen address is not a loopback address: {}", address)));
+        }
+        let listener = TcpListener::bind(address).await?;
+        let local_addr = listener.local_addr()?;
+        info!("Serving metrics at http://{}/metrics", local_addr);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        warn!("Failed to accept metrics connection: {}", e);
+                        continue;
+                    }
+                };
+                let sources = sources.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, &sources).await {
+                        debug!("Metrics request from {} failed: {}", peer, e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { local_addr, task })
+    }
+
+    /// Address the server listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answer one request on a connection
+async fn serve(mut stream: TcpStream, sources: &MetricsSources) -> std::io::Result<()> {
+    let head = match tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    // Scrapers may add query parameters, which are ignored
+    let path = request_line.next().unwrap_or_default().split('?').next().unwrap_or_default();
+
+    let response = match (method, path) {
+        ("GET", "/metrics") => response("200 OK", CONTENT_TYPE, &sources.render().await),
+        (_, "/metrics") => response("405 Method Not Allowed", "text/plain", "Method not allowed\n"),
+        _ => response("404 Not Found", "text/plain", "Not found\n"),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read up to the blank line ending the request head
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_SIZE {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "request too large"));
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+fn response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Render metrics in OpenMetrics text format
+pub fn render(summary: &MetricsSummary, network: Option<&NetworkStats>, bus: &EventBusStats) -> String {
+    let mut out = String::new();
+
+    // `foo` and `foo_total` are one family, and their series with the same labels one series
+    let counters = families(&summary.counters, |name| metric_name(name.strip_suffix("_total").unwrap_or(name)));
+    for (name, series) in counters {
+        family(&mut out, &name, "counter", None);
+        let mut totals: BTreeMap<&[(String, String)], u64> = BTreeMap::new();
+        for (key, value) in series {
+            *totals.entry(&key.labels).or_default() += *value;
+        }
+        for (labels, value) in totals {
+            sample(&mut out, &format!("{}_total", name), &pairs(labels), value as f64);
+        }
+    }
+
+    for (name, series) in families(&summary.gauges, metric_name) {
+        family(&mut out, &name, "gauge", None);
+        for (key, value) in series {
+            sample(&mut out, &name, &pairs(&key.labels), *value);
+        }
+    }
+
+    // Quantiles cover the sliding window, count and sum every recorded duration
+    let durations = families(&summary.duration_stats, |name| format!("{}_seconds", metric_name(name)));
+    for (name, series) in durations {
+        family(&mut out, &name, "summary", Some("seconds"));
+        for (key, stats) in series {
+            let labels = pairs(&key.labels);
+            if stats.window_count > 0 {
+                for (quantile, value) in [("0.5", stats.p50), ("0.9", stats.p90), ("0.99", stats.p99)] {
+                    let mut labels = labels.clone();
+                    labels.push(("quantile", quantile));
+                    sample(&mut out, &name, &labels, value.as_secs_f64());
+                }
+            }
+            sample(&mut out, &format!("{}_count", name), &labels, stats.count as f64);
+            sample(&mut out, &format!("{}_sum", name), &labels, stats.total.as_secs_f64());
+        }
+    }
+
+    if let Some(network) = network {
+        for (name, value) in [
+            ("network_peers_discovered", network.peers_discovered),
+            ("network_peers_disconnected", network.peers_disconnected),
+            ("network_messages_sent", network.messages_sent),
+            ("network_messages_received", network.messages_received),
+            ("network_bytes_sent", network.bytes_sent),
+            ("network_bytes_received", network.bytes_received),
+            ("network_discovery_cycles", network.discovery_cycles),
+        ] {
+            let name = metric_name(name);
+            family(&mut out, &name, "counter", None);
+            sample(&mut out, &format!("{}_total", name), &[], value as f64);
+        }
+        let name = metric_name("network_peers_connected");
+        family(&mut out, &name, "gauge", None);
+        sample(&mut out, &name, &[], network.peers_connected as f64);
+    }
+
+    for (name, value) in [
+        ("event_bus_events_emitted", bus.events_emitted),
+        ("event_bus_events_processed", bus.events_processed),
+        ("event_bus_events_dropped", bus.events_dropped),
+        ("event_bus_events_rejected", bus.events_rejected),
+        ("event_bus_events_unmatched", bus.events_unmatched),
+    ] {
+        let name = metric_name(name);
+        family(&mut out, &name, "counter", None);
+        sample(&mut out, &format!("{}_total", name), &[], value as f64);
+    }
+    for (name, value) in [
+        ("event_bus_queue_size", bus.queue_size),
+        ("event_bus_max_queue_size", bus.max_queue_size),
+    ] {
+        let name = metric_name(name);
+        family(&mut out, &name, "gauge", None);
+        sample(&mut out, &name, &[], value as f64);
+    }
+
+    let mut handlers: Vec<_> = bus.handlers.iter().collect();
+    handlers.sort_by(|a, b| a.0.cmp(b.0));
+    if !handlers.is_empty() {
+        let runs = metric_name("event_handler_runs");
+        family(&mut out, &runs, "counter", None);
+        for (handler, stats) in &handlers {
+            for (outcome, value) in [
+                ("succeeded", stats.succeeded),
+                ("failed", stats.failed),
+                ("timed_out", stats.timed_out),
+                ("panicked", stats.panicked),
+            ] {
+                let labels = [("handler", handler.as_str()), ("outcome", outcome)];
+                sample(&mut out, &format!("{}_total", runs), &labels, value as f64);
+            }
+        }
+
+        let latency = metric_name("event_handler_latency_seconds");
+        family(&mut out, &latency, "summary", Some("seconds"));
+        for (handler, stats) in &handlers {
+            let labels = [("handler", handler.as_str())];
+            sample(&mut out, &format!("{}_count", latency), &labels, stats.runs() as f64);
+            sample(&mut out, &format!("{}_sum", latency), &labels, stats.total_latency.as_secs_f64());
+        }
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Series grouped by exported family name, so every family is declared once
+fn families<V>(
+    series: &HashMap<MetricKey, V>,
+    family_name: impl Fn(&str) -> String,
+) -> BTreeMap<String, Vec<(&MetricKey, &V)>> {
+    let mut families: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for (key, value) in series {
+        families.entry(family_name(&key.name)).or_default().push((key, value));
+    }
+    for series in families.values_mut() {
+        series.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    families
+}
+
+fn pairs(labels: &[(String, String)]) -> Vec<(&str, &str)> {
+    labels.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
+}
+
+/// Prefixed metric name with characters OpenMetrics does not allow replaced
+fn metric_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect();
+    format!("{}{}", PREFIX, name)
+}
+
+fn family(out: &mut String, name: &str, kind: &str, unit: Option<&str>) {
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    if let Some(unit) = unit {
+        let _ = writeln!(out, "# UNIT {} {}", name, unit);
+    }
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{Event, HandlerStats};
+    use crate::utils::metrics::DurationStats;
+
+    #[test]
+    fn test_render_openmetrics_text() {
+        let latency = DurationStats {
+            count: 2,
+            total: Duration::from_millis(300),
+            window_count: 1,
+            p50: Duration::from_millis(100),
+            p90: Duration::from_millis(100),
+            p99: Duration::from_millis(100),
+            ..DurationStats::default()
+        };
+        let metrics = MetricsSummary {
+            counters: HashMap::from([
+                (MetricKey::new("events_clipboard_synced"), 3),
+                (MetricKey::new("errors"), 1),
+                (MetricKey::new("errors_total"), 2),
+                (MetricKey::new("errors_bucket"), 5),
+                (MetricKey::new("errors_total").label("kind", "network"), 4),
+                (MetricKey::new("bytes_synced").label("peer", "laptop"), 10),
+                (MetricKey::new("bytes_synced").label("peer", "phone"), 20),
+            ]),
+            gauges: HashMap::from([(MetricKey::new("peers_connected"), 2.0)]),
+            duration_stats: HashMap::from([(MetricKey::new("sync_latency").label("peer", "phone"), latency)]),
+        };
+        let network = NetworkStats { bytes_sent: 512, peers_connected: 1, ..NetworkStats::default() };
+        let bus = EventBusStats {
+            handlers: HashMap::from([("journal \"main\"".to_string(), HandlerStats { succeeded: 4, ..HandlerStats::default() })]),
+            ..EventBusStats::default()
+        };
+
+        let text = render(&metrics, Some(&network), &bus);
+        assert!(text.contains("# TYPE crosscopy_events_clipboard_synced counter\ncrosscopy_events_clipboard_synced_total 3\n"));
+        assert!(text.contains("# TYPE crosscopy_peers_connected gauge\ncrosscopy_peers_connected 2\n"));
+        assert!(text.contains(concat!(
+            "# TYPE crosscopy_bytes_synced counter\n",
+            "crosscopy_bytes_synced_total{peer=\"laptop\"} 10\n",
+            "crosscopy_bytes_synced_total{peer=\"phone\"} 20\n",
+        )));
+        assert!(text.contains(concat!(
+            "# TYPE crosscopy_sync_latency_seconds summary\n",
+            "# UNIT crosscopy_sync_latency_seconds seconds\n",
+            "crosscopy_sync_latency_seconds{peer=\"phone\",quantile=\"0.5\"} 0.1\n",
+        )));
+        assert!(text.contains("crosscopy_sync_latency_seconds_count{peer=\"phone\"} 2\n"));
+        assert!(text.contains("crosscopy_network_bytes_sent_total 512\n"));
+        assert!(text.contains("crosscopy_network_peers_connected 1\n"));
+        assert!(text.contains(r#"crosscopy_event_handler_runs_total{handler="journal \"main\"",outcome="succeeded"} 4"#));
+        assert!(text.ends_with("# EOF\n"));
+
+        // `errors` and `errors_total` are one family, declared once
+        assert_eq!(text.matches("# TYPE crosscopy_errors counter\n").count(), 1);
+        assert!(text.contains(concat!(
+            "# TYPE crosscopy_errors counter\n",
+            "crosscopy_errors_total 3\n",
+            "crosscopy_errors_total{kind=\"network\"} 4\n",
+        )));
+        assert!(text.contains("# TYPE crosscopy_errors_bucket counter\ncrosscopy_errors_bucket_total 5\n"));
+    }
+
+    async fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nAccept: {}\r\n\r\n", path, CONTENT_TYPE);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_serves_metrics() {
+        let metrics = Arc::new(PerformanceMetrics::new());
+        let event_bus = Arc::new(EventBus::new());
+        metrics.increment_counter("errors").await;
+        metrics.record_duration("sync_latency", Duration::from_millis(250)).await;
+        event_bus.emit(Event::Shutdown).await.unwrap();
+
+        let sources = MetricsSources { metrics, event_bus, network: None };
+        let exporter = MetricsExporter::start("127.0.0.1:0", sources).await.unwrap();
+
+        let response = get(exporter.local_addr(), "/metrics").await;
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains(CONTENT_TYPE));
+        assert!(body.contains("crosscopy_errors_total 1\n"));
+        assert!(body.contains("crosscopy_sync_latency_seconds{quantile=\"0.99\"} 0.25\n"));
+        assert!(body.contains("crosscopy_sync_latency_seconds_count 1\ncrosscopy_sync_latency_seconds_sum 0.25\n"));
+        assert!(body.contains("crosscopy_event_bus_events_emitted_total 1\n"));
+        assert!(!body.contains("crosscopy_network_"));
+
+        assert!(get(exporter.local_addr(), "/").await.starts_with("HTTP/1.1 404"));
+
+        // The server stops with the exporter
+        let address = exporter.local_addr();
+        drop(exporter);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(TcpStream::connect(address).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_only_listens_on_loopback() {
+        let sources = MetricsSources {
+            metrics: Arc::new(PerformanceMetrics::new()),
+            event_bus: Arc::new(EventBus::new()),
+            network: None,
+        };
+        for address in ["0.0.0.0:0", "[::]:0", "192.168.1.10:9464"] {
+            assert!(MetricsExporter::start(address, sources.clone()).await.is_err(), "{}", address);
+        }
+    }
+}
@@ -90,36 +90,460 @@ pub struct DetailedSystemInfo {
     pub arch: String,
 }
 
+/// Clipboard format set by KDE Plasma-aware password managers (value "secret")
+pub const KDE_PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+
+/// Windows clipboard format marking content that monitors must ignore
+pub const WINDOWS_EXCLUDE_FROM_MONITOR: &str = "ExcludeClipboardContentFromMonitorProcessing";
+
+/// macOS pasteboard type marking concealed content (nspasteboard.org)
+pub const MACOS_CONCEALED_TYPE: &str = "org.nspasteboard.ConcealedType";
+
+/// Check whether the advertised clipboard formats mark the content as concealed
+///
+/// `read` fetches the data of a format; it is only called for markers whose
+/// value matters, such as the KDE password manager hint.
+pub fn formats_mark_concealed<F>(formats: &[String], read: F) -> bool
+where
+    F: Fn(&str) -> Option<Vec<u8>>,
+{
+    formats.iter().any(|format| match format.as_str() {
+        WINDOWS_EXCLUDE_FROM_MONITOR | MACOS_CONCEALED_TYPE => true,
+        KDE_PASSWORD_MANAGER_HINT => read(format)
+            .map(|value| String::from_utf8_lossy(&value).trim() == "secret")
+            .unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Check whether the current clipboard content is marked as concealed
+///
+/// Returns an error on platforms where the markers cannot be read, so callers
+/// can decide how to degrade.
+pub fn is_clipboard_concealed() -> Result<bool> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::is_clipboard_concealed()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::is_clipboard_concealed()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::is_clipboard_concealed()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        Err(UtilError::PlatformError("Concealed clipboard hints are not supported on this platform".to_string()))
+    }
+}
+
+/// Check whether the current Linux PRIMARY selection is marked as concealed
+pub fn is_primary_concealed() -> Result<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::is_selection_concealed(linux::PRIMARY)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(UtilError::PlatformError("PRIMARY selection is only available on Linux".to_string()))
+    }
+}
+
+/// Counter the OS bumps on every clipboard change, where one exists
+///
+/// Comparing it is far cheaper than reading the clipboard content.
+pub fn clipboard_change_count() -> Option<u64> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(windows::clipboard_sequence_number())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::pasteboard_change_count()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Application owning the focused window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActiveWindow {
+    /// Process ID of the owning application
+    pub pid: Option<u32>,
+    /// Executable name of the owning process
+    pub process_name: Option<String>,
+    /// Window class on Windows and X11, bundle identifier on macOS
+    pub window_class: Option<String>,
+}
+
+/// Look up the application owning the focused window
+///
+/// Returns an error on platforms without active-window information, such as
+/// Wayland, so callers can decide how to degrade.
+pub fn active_window() -> Result<ActiveWindow> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::active_window()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::active_window()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::active_window()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        Err(UtilError::PlatformError("Active window lookup is not supported on this platform".to_string()))
+    }
+}
+
+/// Executable name of a running process
+pub fn process_name(pid: u32) -> Option<String> {
+    let pid = sysinfo::Pid::from_u32(pid);
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+    system.process(pid).map(|process| process.name().to_string())
+}
+
 /// Platform-specific clipboard access helpers
 #[cfg(target_os = "windows")]
 pub mod windows {
     use super::*;
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::winuser::{
+        GetClassNameW, GetClipboardSequenceNumber, GetForegroundWindow, GetWindowThreadProcessId,
+        IsClipboardFormatAvailable, RegisterClipboardFormatW,
+    };
 
     pub fn get_clipboard_formats() -> Result<Vec<String>> {
         // Windows-specific clipboard format enumeration
         // This would use winapi to get available clipboard formats
         Ok(vec!["CF_TEXT".to_string(), "CF_BITMAP".to_string()])
     }
+
+    /// Check whether a registered clipboard format is currently available
+    pub fn is_format_available(name: &str) -> bool {
+        let wide: Vec<u16> = OsStr::new(name).encode_wide().chain(std::iter::once(0)).collect();
+
+        // SAFETY: `wide` is a valid NUL-terminated UTF-16 string for the duration of the call
+        unsafe {
+            let format = RegisterClipboardFormatW(wide.as_ptr());
+            format != 0 && IsClipboardFormatAvailable(format) != 0
+        }
+    }
+
+    pub fn is_clipboard_concealed() -> Result<bool> {
+        Ok(is_format_available(WINDOWS_EXCLUDE_FROM_MONITOR))
+    }
+
+    /// Sequence number bumped by Windows on every clipboard change
+    pub fn clipboard_sequence_number() -> u64 {
+        // SAFETY: GetClipboardSequenceNumber has no preconditions
+        unsafe { GetClipboardSequenceNumber() as u64 }
+    }
+
+    /// Process and window class of the foreground window
+    pub fn active_window() -> Result<ActiveWindow> {
+        let mut pid: u32 = 0;
+        let mut class = [0u16; 256];
+
+        // SAFETY: the window handle is only passed back to user32, and the
+        // buffers outlive the calls writing to them
+        let class_len = unsafe {
+            let window = GetForegroundWindow();
+            if window.is_null() {
+                return Err(UtilError::PlatformError("No foreground window".to_string()));
+            }
+            GetWindowThreadProcessId(window, &mut pid);
+            GetClassNameW(window, class.as_mut_ptr(), class.len() as i32)
+        };
+
+        let pid = (pid != 0).then_some(pid);
+        Ok(ActiveWindow {
+            pid,
+            process_name: pid.and_then(process_name),
+            window_class: (class_len > 0).then(|| String::from_utf16_lossy(&class[..class_len as usize])),
+        })
+    }
 }
 
 #[cfg(target_os = "macos")]
 pub mod macos {
     use super::*;
+    use cocoa::appkit::NSPasteboard;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
 
     pub fn get_pasteboard_types() -> Result<Vec<String>> {
-        // macOS-specific pasteboard type enumeration
-        // This would use Cocoa/AppKit to get available pasteboard types
-        Ok(vec!["public.utf8-plain-text".to_string(), "public.png".to_string()])
+        // SAFETY: the general pasteboard and its type array are valid Objective-C
+        // objects owned by AppKit; we only read from them
+        unsafe {
+            let pasteboard: id = NSPasteboard::generalPasteboard(nil);
+            if pasteboard == nil {
+                return Err(UtilError::PlatformError("General pasteboard unavailable".to_string()));
+            }
+
+            let types: id = pasteboard.types();
+            if types == nil {
+                return Ok(Vec::new());
+            }
+
+            let mut result = Vec::new();
+            for i in 0..types.count() {
+                let pasteboard_type: id = types.objectAtIndex(i);
+                let utf8 = pasteboard_type.UTF8String();
+                if !utf8.is_null() {
+                    result.push(CStr::from_ptr(utf8).to_string_lossy().into_owned());
+                }
+            }
+            Ok(result)
+        }
+    }
+
+    /// Change count of the general pasteboard
+    pub fn pasteboard_change_count() -> Option<u64> {
+        // SAFETY: changeCount is a plain NSInteger getter on a valid pasteboard
+        unsafe {
+            let pasteboard: id = NSPasteboard::generalPasteboard(nil);
+            if pasteboard == nil {
+                return None;
+            }
+            Some(pasteboard.changeCount() as u64)
+        }
+    }
+
+    pub fn is_clipboard_concealed() -> Result<bool> {
+        let types = get_pasteboard_types()?;
+        Ok(formats_mark_concealed(&types, |_| None))
+    }
+
+    /// Process and bundle identifier of the frontmost application
+    pub fn active_window() -> Result<ActiveWindow> {
+        // SAFETY: the shared workspace and its frontmost application are valid
+        // Objective-C objects owned by AppKit; we only read from them
+        unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let application: id = msg_send![workspace, frontmostApplication];
+            if application == nil {
+                return Err(UtilError::PlatformError("No frontmost application".to_string()));
+            }
+
+            let pid: i32 = msg_send![application, processIdentifier];
+            let bundle_identifier: id = msg_send![application, bundleIdentifier];
+            let pid = u32::try_from(pid).ok();
+            Ok(ActiveWindow {
+                pid,
+                process_name: pid.and_then(process_name),
+                window_class: to_string(bundle_identifier),
+            })
+        }
+    }
+
+    /// Copy an `NSString` into a Rust string
+    ///
+    /// # Safety
+    ///
+    /// `string` must be `nil` or a valid `NSString`.
+    unsafe fn to_string(string: id) -> Option<String> {
+        if string == nil {
+            return None;
+        }
+        let utf8 = string.UTF8String();
+        (!utf8.is_null()).then(|| CStr::from_ptr(utf8).to_string_lossy().into_owned())
     }
 }
 
 #[cfg(target_os = "linux")]
 pub mod linux {
     use super::*;
+    use std::process::Command;
+
+    /// Name of the regular clipboard selection
+    pub const CLIPBOARD: &str = "clipboard";
+
+    /// Name of the middle-click PRIMARY selection
+    pub const PRIMARY: &str = "primary";
+
+    /// Check whether the session is running under Wayland
+    pub fn is_wayland() -> bool {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+
+    /// List the targets offered for a selection ([`CLIPBOARD`] or [`PRIMARY`])
+    ///
+    /// Uses `wl-paste` on Wayland and `xclip` on X11, as neither protocol is
+    /// exposed by arboard.
+    pub fn get_selection_targets(selection: &str) -> Result<Vec<String>> {
+        let output = if is_wayland() {
+            run(wl_paste(selection).arg("--list-types"))?
+        } else {
+            run(Command::new("xclip").args(["-selection", selection, "-t", "TARGETS", "-o"]))?
+        };
+
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    /// Read the data of a single selection target
+    pub fn read_selection_target(selection: &str, target: &str) -> Result<Vec<u8>> {
+        if is_wayland() {
+            run(wl_paste(selection).args(["--no-newline", "--type", target]))
+        } else {
+            run(Command::new("xclip").args(["-selection", selection, "-t", target, "-o"]))
+        }
+    }
+
+    /// Check whether the CLIPBOARD selection carries a password-manager hint
+    pub fn is_clipboard_concealed() -> Result<bool> {
+        is_selection_concealed(CLIPBOARD)
+    }
+
+    /// Check whether a selection carries a password-manager hint
+    ///
+    /// Like [`get_selection_targets`] this needs `wl-paste` or `xclip`, and
+    /// fails rather than reporting "not concealed" when they are missing.
+    pub fn is_selection_concealed(selection: &str) -> Result<bool> {
+        let targets = get_selection_targets(selection)?;
+        Ok(formats_mark_concealed(&targets, |target| read_selection_target(selection, target).ok()))
+    }
+
+    /// Process and `WM_CLASS` of the X11 window with `_NET_ACTIVE_WINDOW`
+    ///
+    /// Wayland compositors do not expose the focused window to clients.
+    pub fn active_window() -> Result<ActiveWindow> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, Window};
+
+        if is_wayland() {
+            return Err(UtilError::PlatformError(
+                "Wayland does not expose the active window".to_string(),
+            ));
+        }
+
+        let x11_error = |e: &dyn std::fmt::Display| UtilError::PlatformError(format!("X11 error: {}", e));
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| x11_error(&e))?;
+        let root = conn.setup().roots[screen_num].root;
+
+        let atom = |name: &str| -> Result<u32> {
+            Ok(conn
+                .intern_atom(false, name.as_bytes())
+                .map_err(|e| x11_error(&e))?
+                .reply()
+                .map_err(|e| x11_error(&e))?
+                .atom)
+        };
+        let property = |window: Window, property: u32, kind: AtomEnum, length: u32| {
+            conn.get_property(false, window, property, kind, 0, length)
+                .map_err(|e| x11_error(&e))?
+                .reply()
+                .map_err(|e| x11_error(&e))
+        };
+
+        let window = property(root, atom("_NET_ACTIVE_WINDOW")?, AtomEnum::WINDOW, 1)?
+            .value32()
+            .and_then(|mut values| values.next())
+            .filter(|window| *window != 0)
+            .ok_or_else(|| UtilError::PlatformError("No active window".to_string()))?;
+
+        let pid = property(window, atom("_NET_WM_PID")?, AtomEnum::CARDINAL, 1)?
+            .value32()
+            .and_then(|mut values| values.next());
+
+        // WM_CLASS holds the instance and class names, each NUL-terminated
+        let class = property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING, 256)?.value;
+        let window_class = String::from_utf8_lossy(&class)
+            .split('\0')
+            .rfind(|name| !name.is_empty())
+            .map(str::to_string);
+
+        Ok(ActiveWindow {
+            pid,
+            process_name: pid.and_then(process_name),
+            window_class,
+        })
+    }
+
+    fn wl_paste(selection: &str) -> Command {
+        let mut command = Command::new("wl-paste");
+        if selection == PRIMARY {
+            command.arg("--primary");
+        }
+        command
+    }
+
+    fn run(command: &mut Command) -> Result<Vec<u8>> {
+        let output = command
+            .output()
+            .map_err(|e| UtilError::PlatformError(format!("Failed to run {:?}: {}", command.get_program(), e)))?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(UtilError::PlatformError(format!(
+                "{:?} exited with {}",
+                command.get_program(),
+                output.status
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Read function backed by an in-memory clipboard
+    fn mock_clipboard(entries: &[(&str, &str)]) -> (Vec<String>, impl Fn(&str) -> Option<Vec<u8>>) {
+        let data: HashMap<String, Vec<u8>> = entries
+            .iter()
+            .map(|(format, value)| (format.to_string(), value.as_bytes().to_vec()))
+            .collect();
+        let formats = data.keys().cloned().collect();
+        (formats, move |format: &str| data.get(format).cloned())
+    }
+
+    #[test]
+    fn test_kde_password_manager_hint() {
+        let (formats, read) = mock_clipboard(&[("text/plain", "hunter2"), (KDE_PASSWORD_MANAGER_HINT, "secret")]);
+        assert!(formats_mark_concealed(&formats, read));
+
+        let (formats, read) = mock_clipboard(&[("text/plain", "hunter2"), (KDE_PASSWORD_MANAGER_HINT, "other")]);
+        assert!(!formats_mark_concealed(&formats, read));
+    }
+
+    #[test]
+    fn test_presence_markers() {
+        for marker in [WINDOWS_EXCLUDE_FROM_MONITOR, MACOS_CONCEALED_TYPE] {
+            let (formats, read) = mock_clipboard(&[("text/plain", "hunter2"), (marker, "")]);
+            assert!(formats_mark_concealed(&formats, read));
+        }
+    }
 
-    pub fn get_selection_targets() -> Result<Vec<String>> {
-        // Linux-specific X11 selection target enumeration
-        // This would use X11 or Wayland APIs to get available selection targets
-        Ok(vec!["text/plain".to_string(), "image/png".to_string()])
+    #[test]
+    fn test_plain_clipboard_not_concealed() {
+        let (formats, read) = mock_clipboard(&[("text/plain", "hello"), ("text/html", "<b>hello</b>")]);
+        assert!(!formats_mark_concealed(&formats, read));
     }
 }
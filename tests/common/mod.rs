@@ -32,6 +32,7 @@ pub fn create_test_config(port: u16) -> AppConfig {
             idle_connection_timeout: 60, // Shorter for tests
             enable_quic: false,       // TCP only for tests
             quic_port: None,
+            identity_file: None,
         },
         clipboard: ClipboardConfig {
             sync_images: false, // Disable for simpler tests
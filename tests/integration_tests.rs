@@ -1,7 +1,8 @@
 //! Integration tests for CrossCopy
 
 use crosscopy::{
-    config::{AppConfig, ClipboardConfig, NetworkConfig, SecurityConfig, LoggingConfig},
+    clipboard::CompressionCodec,
+    config::{AppConfig, ClipboardConfig, FilterConfig, NetworkConfig, LazyTransferConfig, PolicyConfig, PrimarySelectionConfig, SecurityConfig, HistoryConfig, LoggingConfig, SyncConfig, TransformConfig, ExclusionConfig, ConcealedConfig, EventBusConfig, MetricsConfig},
     CrossCopyApp,
 };
 use std::time::Duration;
@@ -22,14 +23,26 @@ fn create_test_config(port: u16) -> AppConfig {
             idle_connection_timeout: 300,
             enable_quic: false,
             quic_port: None,
+            identity_file: None,
         },
         clipboard: ClipboardConfig {
             sync_images: true,
             sync_files: false,
             cooldown_millis: 100, // Shorter for tests
+            watch_changes: false, // No display server in tests
+            poll_interval_millis: 50,
+            max_poll_interval_millis: 500,
             max_content_size: 1024 * 1024, // 1MB
             enable_compression: false, // Disable for simpler tests
             compression_threshold: 1024,
+            compression_codecs: vec![CompressionCodec::Zstd],
+            filters: FilterConfig::default(),
+            primary: PrimarySelectionConfig::default(),
+            lazy: LazyTransferConfig::default(),
+            policies: PolicyConfig::default(),
+            transforms: TransformConfig::default(),
+            exclusions: ExclusionConfig::default(),
+            concealed: ConcealedConfig::default(),
         },
         security: SecurityConfig {
             secret_key: "test-secret-key".to_string(),
@@ -38,12 +51,17 @@ fn create_test_config(port: u16) -> AppConfig {
             enable_authentication: false,
             max_message_age: 300,
         },
+        history: HistoryConfig::default(),
+        sync: SyncConfig::default(),
+        events: EventBusConfig::default(),
+        metrics: MetricsConfig::default(),
         logging: LoggingConfig {
             level: "debug".to_string(),
             file_path: None,
             structured: false,
             max_file_size: 10 * 1024 * 1024,
             max_files: 5,
+            ..LoggingConfig::default()
         },
     }
 }
@@ -205,7 +223,6 @@ async fn test_config_manager_file_operations() {
 #[tokio::test]
 async fn test_key_manager_rotation() {
     use crosscopy::crypto::{KeyManager, KeyRotationPolicy};
-    use std::time::Duration;
     
     let initial_key = [1u8; 32];
     let policy = KeyRotationPolicy::OperationCount(3);
@@ -229,35 +246,40 @@ async fn test_key_manager_rotation() {
     assert_eq!(manager.get_previous_key(), Some(&original_key));
 }
 
-// Benchmark tests (only run with --features bench)
-#[cfg(feature = "bench")]
-mod benchmarks {
-    use super::*;
-    use criterion::{black_box, Criterion};
-    
-    pub fn bench_clipboard_content_creation(c: &mut Criterion) {
-        c.bench_function("clipboard_content_creation", |b| {
-            b.iter(|| {
-                let content = crosscopy::clipboard::ClipboardContent::new_text(
-                    black_box("Test content".to_string()),
-                    black_box("device-id".to_string()),
-                );
-                black_box(content)
-            })
-        });
-    }
-    
-    pub fn bench_encryption_roundtrip(c: &mut Criterion) {
-        let key = crosscopy::crypto::EncryptionService::generate_random_key();
-        let service = crosscopy::crypto::EncryptionService::new(&key);
-        let data = b"Test data for encryption benchmark";
-        
-        c.bench_function("encryption_roundtrip", |b| {
-            b.iter(|| {
-                let encrypted = service.encrypt(black_box(data)).unwrap();
-                let decrypted = service.decrypt(black_box(&encrypted)).unwrap();
-                black_box(decrypted)
-            })
-        });
+#[tokio::test]
+async fn test_headless_clipboard_sync_between_monitors() {
+    use crosscopy::clipboard::{ClipboardMonitor, MemoryClipboard};
+    use crosscopy::events::{Event, EventBus};
+    use std::sync::Arc;
+
+    let config = ClipboardConfig {
+        cooldown_millis: 0,
+        ..ClipboardConfig::default()
+    };
+
+    let bus_a = Arc::new(EventBus::new());
+    let clipboard_a = Arc::new(MemoryClipboard::new());
+    let mut monitor_a = ClipboardMonitor::with_backend(config.clone(), bus_a.clone(), clipboard_a.clone()).unwrap();
+
+    let bus_b = Arc::new(EventBus::new());
+    let clipboard_b = Arc::new(MemoryClipboard::new());
+    let monitor_b = ClipboardMonitor::with_backend(config, bus_b, clipboard_b.clone()).unwrap();
+
+    monitor_a.start().await.unwrap();
+    clipboard_a.set_text("copied on device A");
+
+    let mut synced = None;
+    for _ in 0..20 {
+        sleep(Duration::from_millis(50)).await;
+        if let Some(Event::ClipboardChanged { content, .. }) = bus_a.poll_event().await {
+            synced = Some(content);
+            break;
+        }
     }
+
+    let content = synced.expect("device A did not report a clipboard change");
+    monitor_b.apply_content(&content).await.unwrap();
+    assert_eq!(clipboard_b.text().as_deref(), Some("copied on device A"));
+
+    monitor_a.stop().await.unwrap();
 }
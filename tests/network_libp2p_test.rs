@@ -121,6 +121,7 @@ fn test_network_config_custom_values() {
         idle_connection_timeout: 600,
         enable_quic: true,
         quic_port: Some(9998),
+        identity_file: None,
     };
     
     assert_eq!(config.listen_port, 9999);
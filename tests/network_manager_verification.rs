@@ -21,6 +21,7 @@ struct TestEventHandler {
     name: String,
 }
 
+#[allow(dead_code)]
 impl TestEventHandler {
     fn new() -> Self {
         Self {
@@ -59,8 +60,9 @@ impl TestEventHandler {
 impl EventHandler for TestEventHandler {
     fn handle(&self, event: &Event) -> crosscopy::events::Result<()> {
         debug!("Test handler received event: {:?}", event);
-        // We can't use async operations in the sync trait method,
-        // so we'll use a different approach for testing
+        if let Ok(mut events) = self.events.try_lock() {
+            events.push(event.clone());
+        }
         Ok(())
     }
 
@@ -81,6 +83,7 @@ fn create_test_network_config() -> NetworkConfig {
         idle_connection_timeout: 30,
         enable_quic: false,
         quic_port: None,
+        identity_file: None,
     }
 }
 
@@ -104,6 +107,7 @@ async fn test_network_manager_creation_and_configuration() {
 }
 
 #[tokio::test]
+#[ignore = "requires mDNS peers on the local network"]
 async fn test_peer_discovery_via_mdns() {
     env_logger::try_init().ok();
     info!("Testing automatic peer discovery via mDNS");
@@ -139,6 +143,7 @@ async fn test_peer_discovery_via_mdns() {
 }
 
 #[tokio::test]
+#[ignore = "requires mDNS peers on the local network"]
 async fn test_connection_management() {
     env_logger::try_init().ok();
     info!("Testing connection management");
@@ -174,6 +179,7 @@ async fn test_connection_management() {
 }
 
 #[tokio::test]
+#[ignore = "requires mDNS peers on the local network"]
 async fn test_network_statistics_and_monitoring() {
     env_logger::try_init().ok();
     info!("Testing network statistics and monitoring");
@@ -290,6 +296,7 @@ async fn test_peer_to_peer_messaging() {
 }
 
 #[tokio::test]
+#[ignore = "requires mDNS peers on the local network"]
 async fn test_event_handling_integration() {
     env_logger::try_init().ok();
     info!("Testing event handling integration");
@@ -317,3 +324,101 @@ async fn test_event_handling_integration() {
     manager.stop().await.unwrap();
     info!("✓ Event handling integration test passed");
 }
+
+/// Start two managers on localhost without mDNS, the second dialing the first
+async fn start_local_pair(port: u16) -> (NetworkManager, Arc<EventBus>, NetworkManager, Arc<EventBus>) {
+    let config = |listen_port| NetworkConfig {
+        listen_port,
+        enable_mdns: false,
+        ..create_test_network_config()
+    };
+
+    let bus_a = Arc::new(EventBus::new());
+    let mut manager_a = NetworkManager::new(config(port), bus_a.clone()).await.unwrap();
+    manager_a.start().await.unwrap();
+
+    let bus_b = Arc::new(EventBus::new());
+    let mut manager_b = NetworkManager::new(config(port + 1), bus_b.clone()).await.unwrap();
+    manager_b.start().await.unwrap();
+
+    sleep(Duration::from_millis(200)).await;
+    manager_b
+        .dial(format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap())
+        .await
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    while manager_a.get_connection_count().await == 0 || manager_b.get_connection_count().await == 0 {
+        assert!(start.elapsed() < Duration::from_secs(10), "local peers did not connect");
+        sleep(Duration::from_millis(50)).await;
+    }
+
+    (manager_a, bus_a, manager_b, bus_b)
+}
+
+/// Wait for the next network message on a bus
+async fn next_message(bus: &EventBus) -> (Message, String) {
+    let start = std::time::Instant::now();
+    loop {
+        if let Some(Event::NetworkMessage { message, sender }) = bus.poll_event().await {
+            return (message, sender);
+        }
+        assert!(start.elapsed() < Duration::from_secs(10), "no network message arrived");
+        sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_request_response_between_local_peers() {
+    env_logger::try_init().ok();
+
+    let (manager_a, bus_a, mut manager_b, bus_b) = start_local_pair(18890).await;
+
+    // Broadcasts are delivered to the peer's event bus
+    manager_a.broadcast_clipboard_content(b"hello".to_vec()).await.unwrap();
+    let (message, sender) = next_message(&bus_b).await;
+    assert_eq!(message.header.message_type, MessageType::ClipboardSync);
+    assert_eq!(message.payload, b"hello");
+    assert_eq!(sender, manager_a.local_peer_id().to_string());
+
+    // A content request is answered by the application on the other side
+    let responder = tokio::spawn(async move {
+        let (request, _) = next_message(&bus_a).await;
+        assert_eq!(request.header.message_type, MessageType::ContentRequest);
+        let response = Message::new(MessageType::ContentResponse, b"body".to_vec(), "peer-a".to_string());
+        manager_a.respond(&request.header.message_id, response).await.unwrap();
+        manager_a
+    });
+
+    let request = Message::new(MessageType::ContentRequest, b"checksum".to_vec(), "peer-b".to_string());
+    let peer_a = manager_b.get_connected_peers().await.remove(0);
+    let response = manager_b.request(&peer_a, request).await.unwrap();
+    assert_eq!(response.header.message_type, MessageType::ContentResponse);
+    assert_eq!(response.payload, b"body");
+
+    let mut manager_a = responder.await.unwrap();
+    manager_a.stop().await.unwrap();
+    manager_b.stop().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_handshake_negotiates_compression_codec() {
+    use crosscopy::clipboard::CompressionCodec;
+
+    env_logger::try_init().ok();
+
+    let (mut manager_a, _bus_a, mut manager_b, _bus_b) = start_local_pair(18892).await;
+    let preferred = [CompressionCodec::Lz4, CompressionCodec::Gzip];
+
+    // Until the handshake arrives the peer is assumed to read only gzip
+    let start = std::time::Instant::now();
+    while manager_a.negotiate_codec(&preferred).await != CompressionCodec::Lz4
+        || manager_b.negotiate_codec(&[CompressionCodec::Zstd]).await != CompressionCodec::Zstd
+    {
+        assert!(start.elapsed() < Duration::from_secs(10), "handshake did not arrive");
+        sleep(Duration::from_millis(50)).await;
+    }
+
+    manager_a.stop().await.unwrap();
+    manager_b.stop().await.unwrap();
+}